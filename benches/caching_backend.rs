@@ -0,0 +1,32 @@
+//! Explores a program that branches on every input byte, with and without a cache in
+//! front of the solver. Compare the two timings to see what the cache saves on a
+//! branch-heavy program.
+
+#![feature(rustc_private, test)]
+
+extern crate seer;
+extern crate test;
+
+use test::Bencher;
+
+/// Each iteration gets a fresh backend from `backend`, so that the cache starts empty.
+fn explore<B, F>(b: &mut Bencher, backend: F)
+    where B: ::seer::SolverBackend + 'static, F: Fn() -> B
+{
+    b.iter(|| {
+        let args = vec!["seer".to_string(), "tests/symbolic/branch_heavy.rs".to_string()];
+        ::seer::ExecutionConfig::new()
+            .solver_backend(backend())
+            .run(args);
+    });
+}
+
+#[bench]
+fn branch_heavy(b: &mut Bencher) {
+    explore(b, || ::seer::Z3Backend);
+}
+
+#[bench]
+fn branch_heavy_with_cache(b: &mut Bencher) {
+    explore(b, || ::seer::CachingBackend::new(::seer::Z3Backend));
+}
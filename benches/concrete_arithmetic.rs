@@ -0,0 +1,28 @@
+//! Explores a program that loops over concrete arithmetic, with and without the concrete
+//! fast path. Compare the two timings to see the per-statement overhead that it saves.
+
+#![feature(rustc_private, test)]
+
+extern crate seer;
+extern crate test;
+
+use test::Bencher;
+
+fn explore(b: &mut Bencher, fast_path: bool) {
+    b.iter(|| {
+        let args = vec!["seer".to_string(), "tests/symbolic/concrete_loop.rs".to_string()];
+        ::seer::ExecutionConfig::new()
+            .concrete_fast_path(fast_path)
+            .run(args);
+    });
+}
+
+#[bench]
+fn concrete_arithmetic(b: &mut Bencher) {
+    explore(b, true);
+}
+
+#[bench]
+fn concrete_arithmetic_without_fast_path(b: &mut Bencher) {
+    explore(b, false);
+}
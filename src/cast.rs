@@ -29,6 +29,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     // TODO(optimization): check to see if the cast has made
                     // the value concrete.
                     Ok(PrimVal::Abstract(sbytes))
+                } else if src_kind.is_int() && dest_kind == Ptr {
+                    // The integer becomes an address that does not point into any
+                    // allocation. Dereferencing it forks on whether it is null.
+                    let ptr_size = self.memory.pointer_size() as usize;
+                    for idx in ptr_size .. src_kind.num_bytes() {
+                        sbytes[idx] = SByte::Concrete(0);
+                    }
+                    Ok(PrimVal::Abstract(sbytes))
                 } else if src_kind == Bool && dest_kind.is_int() {
                     let dest_kind = self.ty_to_primval_kind(dest_ty)?;
                     let primval = self.memory.constraints.add_if_then_else(
@@ -38,7 +46,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         PrimVal::Bytes(0));
                     Ok(primval)
                 } else {
-                    unimplemented!()
+                    let op = format!("cast to {}", dest_ty);
+                    let ty = format!("{:?}", src_kind);
+                    if self.concretize_on_unsupported {
+                        if let Some(val) = self.concretize(val, src_kind, &op, &ty) {
+                            return self.cast_primval(val, src_ty, dest_ty);
+                        }
+                    }
+                    Err(EvalError::UnsupportedSymbolicOperation { op, ty })
                 }
             }
             PrimVal::Undef => Ok(PrimVal::Undef),
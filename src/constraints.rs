@@ -1,25 +1,35 @@
 use rustc::mir;
-use z3;
 
-use std::num;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use input::{ConcreteValue, SymbolicInput};
 use memory::{AbstractVariable, SByte};
+use solver::{ConstraintSet, Model, SatResult, SolverBackend, Z3Backend};
 use value::{PrimVal, PrimValKind};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
 pub enum NumericIntrinsic {
+    Bitreverse,
+    Ctlz,
+    Ctpop,
     Cttz,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum VarType {
+/// The sort of an abstract variable.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VarType {
     Bool,
     BitVec8,
     Array, // Array of BitVec8, indexed by BitVec64?
 }
 
 impl VarType {
-    fn from_prim_val_kind(kind: PrimValKind) -> Self {
+    pub(crate) fn from_prim_val_kind(kind: PrimValKind) -> Self {
         use value::PrimValKind::*;
         match kind {
             Bool => VarType::Bool,
@@ -27,14 +37,17 @@ impl VarType {
             U16 | I16 => VarType::BitVec8,
             U32 | I32 => VarType::BitVec8,
             U64 | I64 => VarType::BitVec8,
+            F32 | F64 => VarType::BitVec8,
             _ => unimplemented!(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum VarOrigin {
+/// Where an abstract variable comes from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VarOrigin {
     StdIn, // abstract byte read from stdin
+    Input, // part of a typed symbolic input
     Inner, // anything else
 }
 
@@ -45,9 +58,36 @@ pub struct ConstraintContext {
     variables: Vec<(VarType, VarOrigin)>,
 
     constraints: Vec<Constraint>,
+
+    /// The typed symbolic inputs created so far, in order of creation.
+    inputs: Vec<SymbolicInput>,
+
+    /// Decides the satisfiability of the constraints.
+    backend: Rc<SolverBackend>,
+
+    /// Time budget for each solver query, in milliseconds.
+    solver_timeout_ms: Option<u64>,
+
+    /// Every query must finish by this time, whatever its own time budget.
+    deadline: Option<Instant>,
+
+    /// Set to the elapsed time when a query exhausts its time budget.
+    timed_out: Cell<Option<u64>>,
+
+    /// The number of queries sent to the backend.
+    queries: Cell<u64>,
+
+    /// Answers of `witness_and_uniqueness()`, keyed by the bytes of the abstract value. A
+    /// value that is uniquely determined stays so as constraints are added, so a unique
+    /// value is cached forever. Otherwise we remember how many constraints there were,
+    /// along with the witness, if any.
+    concretized: RefCell<HashMap<[SByte; 8], (PrimValKind, Result<u128, (usize, Option<u128>)>)>>,
+
+    /// If set, every new input variable is pinned to its value in this model.
+    replay_model: Option<Rc<Model>>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Constraint {
     Binop {
         operator: mir::BinOp,
@@ -100,6 +140,39 @@ pub enum Constraint {
     }
 }
 
+// `mir::BinOp` and `mir::UnOp` do not implement `Hash`, so hash them by discriminant.
+impl Hash for Constraint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match *self {
+            Constraint::Binop { operator, kind, rhs_operand1, rhs_operand2, lhs, lhs_kind } => {
+                (operator as u8).hash(state);
+                (kind, rhs_operand1, rhs_operand2, lhs, lhs_kind).hash(state);
+            }
+            Constraint::Unop { operator, kind, operand, lhs } => {
+                (operator as u8).hash(state);
+                (kind, operand, lhs).hash(state);
+            }
+            Constraint::Compare { op, kind, lhs, rhs } => {
+                (op as u8).hash(state);
+                (kind, lhs, rhs).hash(state);
+            }
+            Constraint::NumericIntrinsic { operator, kind, operand, lhs } => {
+                (operator, kind, operand, lhs).hash(state);
+            }
+            Constraint::IfThenElse { discriminant, kind, then_branch, else_branch, lhs } => {
+                (discriminant, kind, then_branch, else_branch, lhs).hash(state);
+            }
+            Constraint::ArrayElement { array, index, value } => {
+                (array, index, value).hash(state);
+            }
+            Constraint::ArrayStore { array, index, value, lhs } => {
+                (array, index, value, lhs).hash(state);
+            }
+        }
+    }
+}
+
 impl Constraint {
     pub fn new_binop(
         operator: mir::BinOp,
@@ -146,19 +219,186 @@ impl ConstraintContext {
         ConstraintContext {
             variables: Vec::new(),
             constraints: Vec::new(),
+            inputs: Vec::new(),
+            backend: Rc::new(Z3Backend),
+            solver_timeout_ms: None,
+            deadline: None,
+            timed_out: Cell::new(None),
+            queries: Cell::new(0),
+            concretized: RefCell::new(HashMap::new()),
+            replay_model: None,
+        }
+    }
+
+    /// The number of queries sent to the solver backend on this path, including those of
+    /// the paths it was forked from.
+    pub fn solver_queries(&self) -> u64 {
+        self.queries.get()
+    }
+
+    pub fn set_solver_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.solver_timeout_ms = timeout_ms;
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// The time budget of the next query: the solver timeout, shortened so that
+    /// the query ends by the deadline.
+    fn query_timeout_ms(&self) -> Option<u64> {
+        let remaining_ms = self.deadline.map(|deadline| {
+            let now = Instant::now();
+            if deadline > now {
+                // Never hand out a budget of zero.
+                ::std::cmp::max(duration_ms(deadline - now), 1)
+            } else {
+                1
+            }
+        });
+        match (self.solver_timeout_ms, remaining_ms) {
+            (Some(a), Some(b)) => Some(::std::cmp::min(a, b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// If a solver query ran out of time since the last call, returns
+    /// how many milliseconds it took.
+    pub fn take_solver_timeout(&self) -> Option<u64> {
+        self.timed_out.replace(None)
+    }
+
+    /// Whether a solver query ran out of time since the last call to
+    /// `take_solver_timeout`, without resetting it.
+    pub fn solver_timed_out(&self) -> bool {
+        self.timed_out.get().is_some()
+    }
+
+    pub fn set_backend(&mut self, backend: Rc<SolverBackend>) {
+        self.backend = backend;
+    }
+
+    /// The simplified query of the current constraints together with `constraints`, or
+    /// `None` if it is unsatisfiable on its face. See `ConstraintSet::simplified`.
+    fn simplified_query(&self, constraints: &[Constraint]) -> Option<Vec<Constraint>> {
+        ConstraintSet {
+            variables: &self.variables,
+            constraints: &self.constraints,
+            extra_constraints: constraints,
+            timeout_ms: None,
+        }.simplified()
+    }
+
+    fn constraint_set<'c>(
+        &'c self,
+        constraints: &'c [Constraint],
+        timeout_ms: Option<u64>)
+        -> ConstraintSet<'c>
+    {
+        ConstraintSet {
+            variables: &self.variables,
+            constraints,
+            extra_constraints: &[],
+            timeout_ms,
+        }
+    }
+
+    /// Checks the current constraints together with `constraints`, recording
+    /// whether the query timed out. A query that times out is reported as
+    /// unsatisfiable. Queries that simplify to false do not reach the backend.
+    fn check(&self, constraints: &[Constraint]) -> bool {
+        let constraints = match self.simplified_query(constraints) {
+            Some(constraints) => constraints,
+            None => return false,
+        };
+        self.queries.set(self.queries.get() + 1);
+        let start = Instant::now();
+        let timeout_ms = self.query_timeout_ms();
+        match self.backend.check_sat(&self.constraint_set(&constraints, timeout_ms)) {
+            SatResult::Sat => true,
+            SatResult::Unsat => false,
+            SatResult::Unknown => {
+                self.timed_out.set(Some(elapsed_ms(start)));
+                false
+            }
         }
     }
 
+    /// Finds a model of the current constraints together with `constraints`,
+    /// recording whether the query timed out.
+    fn model(&self, constraints: &[Constraint]) -> Option<Model> {
+        let constraints = self.simplified_query(constraints)?;
+        self.queries.set(self.queries.get() + 1);
+        let start = Instant::now();
+        let timeout_ms = self.query_timeout_ms();
+        let model = self.backend.get_model(&self.constraint_set(&constraints, timeout_ms));
+        if model.is_none() {
+            let elapsed_ms = elapsed_ms(start);
+            if timeout_ms.map_or(false, |timeout_ms| elapsed_ms >= timeout_ms) {
+                self.timed_out.set(Some(elapsed_ms));
+            }
+        }
+        model
+    }
+
     fn allocate_abstract_var(&mut self, var_type: VarType, origin: VarOrigin) -> AbstractVariable {
         let id = self.variables.len() as u32;
         self.variables.push((var_type, origin));
+        let pinned = match (origin, &self.replay_model) {
+            (VarOrigin::Inner, _) | (_, &None) => None,
+            (_, &Some(ref model)) => model.values.get(id as usize).and_then(|&value| value),
+        };
+        if let Some(value) = pinned {
+            let mut sbytes = [SByte::Concrete(0); 8];
+            sbytes[0] = SByte::Abstract(AbstractVariable(id));
+            let kind = match var_type {
+                VarType::Bool => PrimValKind::Bool,
+                _ => PrimValKind::U8,
+            };
+            self.push_constraint(Constraint::new_compare(
+                mir::BinOp::Eq, kind, PrimVal::Abstract(sbytes), PrimVal::Bytes(value as u128)));
+        }
         AbstractVariable(id)
     }
 
+    /// Pins the stdin bytes and typed inputs created from now on to their values in
+    /// `model`, which comes from a path of an earlier run of the same program. The path
+    /// then follows that run, since inputs are created in the same order.
+    pub fn set_replay_model(&mut self, model: Model) {
+        self.replay_model = Some(Rc::new(model));
+    }
+
+    /// A satisfying assignment of the current constraints, or `None` if there is
+    /// none or the solver gave up.
+    pub fn get_model(&self) -> Option<Model> {
+        self.model(&[])
+    }
+
     pub fn fresh_stdin_byte(&mut self) -> SByte {
         SByte::Abstract(self.allocate_abstract_var(VarType::BitVec8, VarOrigin::StdIn))
     }
 
+    pub(crate) fn fresh_input_var(&mut self, var_type: VarType) -> SByte {
+        SByte::Abstract(self.allocate_abstract_var(var_type, VarOrigin::Input))
+    }
+
+    pub(crate) fn record_input(&mut self, input: SymbolicInput) {
+        self.inputs.push(input);
+    }
+
+    pub(crate) fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub(crate) fn num_variables(&self) -> usize {
+        self.variables.len()
+    }
+
+    /// All constraints added so far, in the order they were pushed.
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
     pub fn push_constraint(&mut self, constraint: Constraint) {
         self.constraints.push(constraint);
     }
@@ -188,6 +428,8 @@ impl ConstraintContext {
             (_, U16) | (_, I16) => (2, VarType::BitVec8, kind),
             (_, U32) | (_, I32) => (4, VarType::BitVec8, kind),
             (_, U64) | (_, I64) => (8, VarType::BitVec8, kind),
+            (_, F32) => (4, VarType::BitVec8, kind),
+            (_, F64) => (8, VarType::BitVec8, kind),
             _ => unimplemented!(),
         };
 
@@ -218,6 +460,8 @@ impl ConstraintContext {
         let (num_bytes, var_type) = match kind {
             Bool => (1, VarType::Bool),
             U8 | I8 => (1, VarType::BitVec8),
+            F32 => (4, VarType::BitVec8),
+            F64 => (8, VarType::BitVec8),
             _ => unimplemented!(),
         };
 
@@ -331,364 +575,316 @@ impl ConstraintContext {
     }
 
     pub fn get_satisfying_values(&self) -> Vec<u8> {
-        let cfg = z3::Config::new();
-        let ctx = z3::Context::new(&cfg);
-        let solver = z3::Solver::new(&ctx);
-
-        let mut consts = Vec::new();
-
-        let mut result_consts = Vec::new();
+        let model = match self.model(&[]) {
+            Some(model) => model,
+            None => {
+                assert!(self.solver_timed_out(), "path constraints are unsatisfiable");
+                return Vec::new();
+            }
+        };
 
+        let mut result = Vec::new();
         for (idx, v) in self.variables.iter().enumerate() {
-            let (var_type, var_origin) = *v;
-            match var_type {
-                VarType::Bool => {
-                    consts.push(ctx.numbered_bool_const(idx as u32));
-                }
-                VarType::BitVec8 => {
-                    consts.push(ctx.numbered_bitvector_const(idx as u32, 8));
-                    if let VarOrigin::StdIn = var_origin {
-                        result_consts.push(ctx.numbered_bitvector_const(idx as u32, 8));
-                    }
-                }
-                VarType::Array => {
-                    consts.push(
-                        ::z3::Ast::new_const(
-                            &::z3::Symbol::from_int(&ctx, idx as u32),
-                            &ctx.array_sort(
-                                &ctx.bitvector_sort(64),
-                                &ctx.bitvector_sort(8))));
-                }
+            if let (VarType::BitVec8, VarOrigin::StdIn) = *v {
+                result.push(model.values.get(idx).and_then(|&b| b).unwrap_or(0));
             }
-
         }
 
-        for c in &self.constraints {
-            solver.assert(&self.constraint_to_ast(&ctx, *c));
-        }
+        result
+    }
 
-        assert!(solver.check());
-        let model = solver.get_model();
+    /// Returns the value of each typed symbolic input under a satisfying assignment,
+    /// along with the name it was created with.
+    pub fn get_input_model(&self) -> Vec<(String, ConcreteValue)> {
+        let model = match self.model(&[]) {
+            Some(model) => model,
+            None => return Vec::new(),
+        };
+        self.inputs.iter().map(|input| (input.name.clone(), input.reconstruct(&model))).collect()
+    }
 
-        let mut result = Vec::new();
-        for rc in result_consts {
-            result.push(model.eval(&rc).unwrap().as_u64().unwrap() as u8);
-        }
+    /// The Rust type of each typed symbolic input, in the order of `get_input_model`.
+    pub(crate) fn get_input_types(&self) -> Vec<String> {
+        self.inputs.iter().map(|input| input.ty.clone()).collect()
+    }
 
-        result
+    /// Returns a concrete value that `primval` can take under the current
+    /// constraints, or `None` if the constraints are unsatisfiable.
+    pub fn get_witness(&self, primval: PrimVal, kind: PrimValKind) -> Option<u128> {
+        self.get_witness_with(primval, kind, &[])
     }
 
-    pub fn is_feasible_with(
+    /// Like `get_witness()`, but with `constraints` added to the current ones.
+    pub fn get_witness_with(
         &self,
+        primval: PrimVal,
+        kind: PrimValKind,
         constraints: &[Constraint])
-        -> bool
+        -> Option<u128>
     {
-        let cfg = z3::Config::new();
-        let ctx = z3::Context::new(&cfg);
-        let solver = z3::Solver::new(&ctx);
+        if let PrimVal::Bytes(n) = primval {
+            return Some(n);
+        }
 
-        let mut all_constraints: Vec<Constraint> = Vec::new();
-        all_constraints.extend(self.constraints.iter().clone());
-        all_constraints.extend(constraints.iter().clone());
+        self.model(constraints).and_then(|model| model.eval(primval, kind))
+    }
 
-        for c in all_constraints {
-            solver.assert(&self.constraint_to_ast(&ctx, c));
+    /// If the current constraints allow `primval` to take only a single value,
+    /// returns that value.
+    pub fn try_concretize(&self, primval: PrimVal, kind: PrimValKind) -> Option<u128> {
+        match self.witness_and_uniqueness(primval, kind) {
+            Some((n, true)) => Some(n),
+            _ => None,
         }
-
-        solver.check()
     }
 
-    fn sbyte_to_ast<'a>(
-        &self,
-        ctx: &'a z3::Context,
-        sbyte: SByte)
-        -> z3::Ast<'a>
-    {
-        match sbyte {
-            SByte::Abstract(b) => {
-                ctx.numbered_bitvector_const(b.0, 8)
+    /// Returns a value that `primval` can take under the current constraints, and whether
+    /// it is the only one. `None` if the constraints are unsatisfiable, or if `primval` is
+    /// a pointer or undefined. Takes at most two queries, one for the value and one for
+    /// another, and none if nothing changed since the last call for `primval`.
+    pub fn witness_and_uniqueness(&self, primval: PrimVal, kind: PrimValKind) -> Option<(u128, bool)> {
+        let sbytes = match primval {
+            PrimVal::Bytes(n) => return Some((n, true)),
+            PrimVal::Abstract(sbytes) => sbytes,
+            PrimVal::Ptr(_) | PrimVal::Undef => return None,
+        };
+
+        if let Some(&(cached_kind, cached)) = self.concretized.borrow().get(&sbytes) {
+            if cached_kind == kind {
+                match cached {
+                    Ok(n) => return Some((n, true)),
+                    Err((num_constraints, witness)) if num_constraints == self.constraints.len() =>
+                        return witness.map(|n| (n, false)),
+                    Err(_) => {}
+                }
             }
-            SByte::Concrete(b) => {
-                z3::Ast::bv_from_u64(&ctx, b as u64, 8)
+        }
+
+        let result = match self.get_witness(primval, kind) {
+            Some(witness) => {
+                let other_value = Constraint::new_compare(
+                    mir::BinOp::Ne, kind, primval, PrimVal::Bytes(witness));
+                if self.is_feasible_with(&[other_value]) {
+                    Err((self.constraints.len(), Some(witness)))
+                } else {
+                    Ok(witness)
+                }
             }
+            None => Err((self.constraints.len(), None)),
+        };
+        self.concretized.borrow_mut().insert(sbytes, (kind, result));
+        match result {
+            Ok(n) => Some((n, true)),
+            Err((_, witness)) => witness.map(|n| (n, false)),
         }
     }
 
-    fn sbyte_slice_to_ast<'a>(
+    /// Enumerates up to `limit` distinct values that `primval` can take under the current
+    /// constraints, in ascending order, by asking for a new witness that differs from all
+    /// the previous ones. The flag is true if the enumeration stopped at `limit` while
+    /// more values were feasible, or if a solver query timed out, so that there may be
+    /// more values.
+    pub fn enumerate_values(
         &self,
-        ctx: &'a z3::Context,
-        sbytes: &[SByte])
-        -> z3::Ast<'a>
+        primval: PrimVal,
+        kind: PrimValKind,
+        limit: usize)
+        -> (Vec<u128>, bool)
     {
-        if sbytes.is_empty() {
-            panic!("expected non-empty sbyte slice");
-        } else if sbytes.len() == 1 {
-            self.sbyte_to_ast(ctx, sbytes[0])
-        } else {
-            let mut result = self.sbyte_to_ast(ctx, sbytes[0]);
-            for sbyte in &sbytes[1..] {
-                result = self.sbyte_to_ast(ctx, *sbyte).concat(&result);
-            }
+        if let PrimVal::Bytes(n) = primval {
+            return (vec![n], false);
+        }
 
-            result
+        let mut values = Vec::new();
+        let mut excluded = Vec::new();
+        loop {
+            let witness = match self.get_witness_with(primval, kind, &excluded) {
+                Some(witness) => witness,
+                None => break,
+            };
+            if values.len() == limit {
+                values.sort();
+                return (values, true);
+            }
+            values.push(witness);
+            excluded.push(Constraint::new_compare(
+                mir::BinOp::Ne, kind, primval, PrimVal::Bytes(witness)));
         }
+        values.sort();
+        (values, self.solver_timed_out())
     }
 
-    fn constraint_to_ast<'a>(
+    /// Returns the largest value that the unsigned `primval` can take under the
+    /// current constraints together with `constraints`, assuming it is at most `limit`.
+    pub fn max_value_with(
         &self,
-        ctx: &'a z3::Context,
-        constraint: Constraint)
-        -> z3::Ast<'a>
+        primval: PrimVal,
+        kind: PrimValKind,
+        constraints: &[Constraint],
+        limit: u64)
+        -> u64
     {
-        match constraint {
-            Constraint::Binop { operator, kind, lhs, rhs_operand1,
-                                rhs_operand2, lhs_kind } => {
-                self.primval_to_ast(&ctx, lhs, lhs_kind)._eq(
-                    &self.mir_binop_to_ast(
-                        &ctx,
-                        operator,
-                        self.primval_to_ast(&ctx, rhs_operand1, kind),
-                        self.primval_to_ast(&ctx, rhs_operand2, kind),
-                        kind))
-            }
-            Constraint::Unop { operator, kind, lhs, operand, .. } => {
-                self.primval_to_ast(&ctx, lhs, kind)._eq(
-                    &self.mir_unop_to_ast(
-                        &ctx,
-                        operator,
-                        self.primval_to_ast(&ctx, operand, kind)))
-            }
-
-            Constraint::NumericIntrinsic { operator, kind, lhs, operand, .. } => {
-                self.primval_to_ast(&ctx, lhs, kind)._eq(
-                    &self.mir_intrinsic_to_ast(
-                        &ctx,
-                        operator,
-                        self.primval_to_ast(&ctx, operand, kind),
-                        kind))
+        // Binary search for the smallest `hi` such that `primval > hi` is infeasible.
+        let (mut lo, mut hi) = (0u64, limit);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let mut above = constraints.to_vec();
+            above.push(Constraint::new_compare(
+                mir::BinOp::Gt, kind, primval, PrimVal::Bytes(mid as u128)));
+            if self.is_feasible_with(&above) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
             }
-
-            Constraint::Compare { op, lhs, rhs, kind, .. } => {
-                // TODO(cleanup) this duplicates some functionality of mir_binop_to_ast().
-                // Can we consolidate?
-                match op {
-                    mir::BinOp::Eq => {
-                        self.primval_to_ast(&ctx, lhs, kind)._eq(
-                            &self.primval_to_ast(&ctx, rhs, kind))
-                    }
-                    mir::BinOp::Ne => {
-                        self.primval_to_ast(&ctx, lhs, kind)._eq(
-                            &self.primval_to_ast(&ctx, rhs, kind)).not()
-                    }
-                    mir::BinOp::Gt => {
-                        if kind.is_signed_int() {
-                            unimplemented!()
-                        }
-                        self.primval_to_ast(&ctx, lhs, kind).bvugt(
-                            &self.primval_to_ast(&ctx, rhs, kind))
-                    }
-
-                    mir::BinOp::Lt => {
-                        if kind.is_signed_int() {
-                            unimplemented!()
-                        }
-                        self.primval_to_ast(ctx, lhs, kind).bvult(
-                            &self.primval_to_ast(&ctx, rhs, kind))
-                    }
-
-                    _ => {
-                        unimplemented!()
-                    }
-                }
-            }
-
-            Constraint::IfThenElse { discriminant, kind, then_branch, else_branch, lhs } => {
-                self.primval_to_ast(&ctx, lhs, kind)._eq(
-                    &self.primval_to_ast(&ctx, discriminant, PrimValKind::Bool).ite(
-                        &self.primval_to_ast(&ctx, then_branch, kind),
-                        &self.primval_to_ast(&ctx, else_branch, kind)))
-
-            }
-
-            Constraint::ArrayElement { array, index, value, } => {
-                let c = ::z3::Ast::new_const(
-                    &::z3::Symbol::from_int(ctx, array.0),
-                    &ctx.array_sort(
-                        &ctx.bitvector_sort(64),
-                        &ctx.bitvector_sort(8)));
-
-                c.select(&self.primval_to_ast(ctx, index, PrimValKind::U64))._eq(
-                    &self.sbyte_to_ast(ctx, value))
-            }
-
-            Constraint::ArrayStore { array, index, value, lhs } => {
-                let c0 = ::z3::Ast::new_const(
-                    &::z3::Symbol::from_int(ctx, array.0),
-                    &ctx.array_sort(
-                        &ctx.bitvector_sort(64),
-                        &ctx.bitvector_sort(8)));
-
-                let c1 = ::z3::Ast::new_const(
-                    &::z3::Symbol::from_int(ctx, lhs.0),
-                    &ctx.array_sort(
-                        &ctx.bitvector_sort(64),
-                        &ctx.bitvector_sort(8)));
-
-                c1._eq(
-                    &c0.store(
-                        &self.primval_to_ast(ctx, index, PrimValKind::U64),
-                        &self.sbyte_to_ast(ctx, value)))
-            }
-
         }
+        hi
     }
 
-    fn primval_to_ast<'a>(
+    /// Returns the smallest value that the unsigned `primval` can take under the
+    /// current constraints together with `constraints`, assuming it is at most `limit`.
+    pub fn min_value_with(
         &self,
-        ctx: &'a z3::Context,
         primval: PrimVal,
-        kind: PrimValKind)
-        -> z3::Ast<'a>
+        kind: PrimValKind,
+        constraints: &[Constraint],
+        limit: u64)
+        -> u64
     {
-        match primval {
-            PrimVal::Undef => {
-                unimplemented!()
-            }
-            PrimVal::Ptr(_) => {
-                unimplemented!()
-            }
-            PrimVal::Abstract(sbytes) => {
-                if let PrimValKind::Bool = kind {
-                    match sbytes[0] {
-                        SByte::Abstract(b) => {
-                            ctx.numbered_bool_const(b.0)
-                        }
-                        SByte::Concrete(_b) => {
-                            unimplemented!()
-                        }
-                    }
-                } else {
-                    let num_bytes = kind.num_bytes();
-                    self.sbyte_slice_to_ast(ctx, &sbytes[..num_bytes])
-                }
-            }
-            PrimVal::Bytes(v) => {
-                match kind {
-                    PrimValKind::Bool => z3::Ast::from_bool(&ctx, v != 0),
-                    PrimValKind::U8 | PrimValKind::I8 => z3::Ast::bv_from_u64(&ctx, v as u64, 8),
-                    PrimValKind::U16 | PrimValKind::I16 => z3::Ast::bv_from_u64(&ctx, v as u64, 16),
-                    PrimValKind::U32 | PrimValKind::I32 => z3::Ast::bv_from_u64(&ctx, v as u64, 32),
-                    PrimValKind::U64 | PrimValKind::I64 => z3::Ast::bv_from_u64(&ctx, v as u64, 64),
-
-                    PrimValKind::Char => z3::Ast::bv_from_u64(&ctx, v as u64, 32),
-
-                    _ => {
-                        unimplemented!()
-                    }
-                }
+        // Binary search for the largest `lo` such that `primval < lo` is infeasible.
+        let (mut lo, mut hi) = (0u64, limit);
+        while lo < hi {
+            let mid = hi - (hi - lo) / 2;
+            let mut below = constraints.to_vec();
+            below.push(Constraint::new_compare(
+                mir::BinOp::Lt, kind, primval, PrimVal::Bytes(mid as u128)));
+            if self.is_feasible_with(&below) {
+                hi = mid - 1;
+            } else {
+                lo = mid;
             }
         }
+        lo
     }
 
-    fn mir_binop_to_ast<'a>(
+    pub fn is_feasible_with(
         &self,
-        _ctx: &'a z3::Context,
-        operator: mir::BinOp,
-        left: z3::Ast<'a>,
-        right: z3::Ast<'a>,
-        kind: PrimValKind)
-        -> z3::Ast<'a>
+        constraints: &[Constraint])
+        -> bool
     {
-        match (operator, kind) {
-            (mir::BinOp::Eq, _) => left._eq(&right),
-            (mir::BinOp::Ne, _) => left._eq(&right).not(),
-
-            (mir::BinOp::Lt, kind) if kind.is_signed_int() => left.bvslt(&right),
-            (mir::BinOp::Lt, _) => left.bvult(&right),
-
-            (mir::BinOp::Le, kind) if kind.is_signed_int() => left.bvsle(&right),
-            (mir::BinOp::Le, _) => left.bvule(&right),
-
-            (mir::BinOp::Gt, kind) if kind.is_signed_int() => left.bvsgt(&right),
-            (mir::BinOp::Gt, _) => left.bvugt(&right),
-
-            (mir::BinOp::Ge, kind) if kind.is_signed_int() => left.bvsge(&right),
-            (mir::BinOp::Ge, _) => left.bvuge(&right),
-
-            (mir::BinOp::Add, _) => left.bvadd(&right),
-            (mir::BinOp::Sub, _) => left.bvsub(&right),
-
-            (mir::BinOp::BitXor, PrimValKind::Bool) => left.xor(&right),
-            (mir::BinOp::BitXor, _) => left.bvxor(&right),
-
-            (mir::BinOp::BitAnd, PrimValKind::Bool) => left.and(&[&right]),
-            (mir::BinOp::BitAnd, _) => left.bvand(&right),
-
-            (mir::BinOp::BitOr, PrimValKind::Bool) => left.or(&[&right]),
-            (mir::BinOp::BitOr, _) => left.bvor(&right),
-
-            (mir::BinOp::Mul, _) => left.bvmul(&right),
-            (mir::BinOp::Shl, _) => left.bvshl(&right),
-
-            (mir::BinOp::Shr, kind) if kind.is_signed_int() => left.bvashr(&right),
-            (mir::BinOp::Shr, _) => left.bvlshr(&right),
+        self.check(constraints)
+    }
 
-            (mir::BinOp::Div, kind) if kind.is_signed_int() => left.bvsdiv(&right),
-            (mir::BinOp::Div, _) => left.bvudiv(&right),
+    /// Joins the constraints of `other`, a path forked from the same ancestor, into those
+    /// of this path, so that they hold if either path's constraints do. Returns a fresh
+    /// boolean that is true on this path and false on `other`, or `None` if the paths
+    /// have diverged too far to be merged.
+    pub(crate) fn merge(&mut self, other: &ConstraintContext) -> Option<PrimVal> {
+        if self.inputs.len() != other.inputs.len() ||
+            self.inputs.iter().zip(&other.inputs).any(|(a, b)| a.name != b.name || a.bytes != b.bytes)
+        {
+            return None;
+        }
 
-            (mir::BinOp::Rem, kind) if kind.is_signed_int() => left.bvsrem(&right),
-            (mir::BinOp::Rem, _) => left.bvurem(&right),
+        // Variables created after the fork reuse the same ids on both paths, so only
+        // one of the paths may have created any. Constraints that define them then
+        // hold whatever the other path's values, and need no guard.
+        let common_vars = self.variables.iter().zip(&other.variables)
+            .take_while(|&(a, b)| a == b)
+            .count();
+        if self.variables.len() > common_vars && other.variables.len() > common_vars {
+            return None;
+        }
+        {
+            let fresh_vars = if self.variables.len() > common_vars { &self.variables } else { &other.variables };
+            if fresh_vars[common_vars..].iter().any(|&(_, origin)| origin != VarOrigin::Inner) {
+                return None;
+            }
+        }
 
-            _ => {
-                println!("{:?}", operator);
-                unimplemented!()
+        let common_constraints = self.constraints.iter().zip(&other.constraints)
+            .take_while(|&(a, b)| a == b)
+            .count();
+        let mut definitions = Vec::new();
+        let mut here = Vec::new();
+        let mut there = Vec::new();
+        for (suffix, conditions) in vec![(&self.constraints[common_constraints..], &mut here),
+                                         (&other.constraints[common_constraints..], &mut there)] {
+            for &constraint in suffix {
+                match constraint {
+                    Constraint::Compare { .. } => conditions.push(constraint),
+                    _ if defines_fresh_vars(constraint, common_vars as u32) => definitions.push(constraint),
+                    _ => return None,
+                }
             }
         }
+
+        if other.variables.len() > self.variables.len() {
+            self.variables = other.variables.clone();
+        }
+        self.constraints.truncate(common_constraints);
+        self.constraints.extend(definitions);
+        self.concretized.borrow_mut().clear();
+
+        let mut guard = [SByte::Concrete(0); 8];
+        guard[0] = SByte::Abstract(self.allocate_abstract_var(VarType::Bool, VarOrigin::Inner));
+        let guard = PrimVal::Abstract(guard);
+        let here = self.all_of(&here);
+        let there = self.all_of(&there);
+        let holds = self.add_if_then_else(guard, PrimValKind::Bool, here, there);
+        self.push_constraint(Constraint::new_compare(
+            mir::BinOp::Eq, PrimValKind::Bool, holds, PrimVal::from_bool(true)));
+        Some(guard)
     }
 
-    fn mir_unop_to_ast<'a>(
-        &self,
-        _ctx: &'a z3::Context,
-        operator: mir::UnOp,
-        val: z3::Ast<'a>,)
-        -> z3::Ast<'a>
-    {
-        match operator {
-            mir::UnOp::Not => val.not(),
-            mir::UnOp::Neg => val.bvneg(),
+    /// Returns a boolean that is true if all of the `Compare` constraints `conditions` hold.
+    fn all_of(&mut self, conditions: &[Constraint]) -> PrimVal {
+        let mut result = PrimVal::from_bool(true);
+        for (idx, &condition) in conditions.iter().enumerate() {
+            let holds = match condition {
+                Constraint::Compare { op, kind, lhs, rhs } =>
+                    self.add_binop_constraint(op, lhs, rhs, kind),
+                _ => bug!("all_of() on a constraint that is not a comparison"),
+            };
+            result = if idx == 0 {
+                holds
+            } else {
+                self.add_binop_constraint(mir::BinOp::BitAnd, result, holds, PrimValKind::Bool)
+            };
         }
+        result
     }
+}
 
-    fn mir_intrinsic_to_ast<'a>(
-        &self,
-        ctx: &'a z3::Context,
-        operator: NumericIntrinsic,
-        val: z3::Ast<'a>,
-        kind: PrimValKind)
-        -> z3::Ast<'a>
-    {
-        match operator {
-            NumericIntrinsic::Cttz => {
-                let mut bits = kind.num_bytes() * 8;
-                if bits > 128 {
-                    unimplemented!();
-                }
-                let num_bits = bits as u32;
-                let zero = z3::Ast::bv_from_u64(&ctx, 0, num_bits);
-                let mut r = z3::Ast::bv_from_u64(&ctx, 0, num_bits);
-                let mut x = val;
-                while bits > 1 {
-                    bits /= 2;
-                    let mask = (num::Wrapping(1u64) << bits) - num::Wrapping(1u64);
-                    let z3mask = z3::Ast::bv_from_u64(&ctx, mask.0, num_bits);
-                    let z3bits = z3::Ast::bv_from_u64(&ctx, bits as u64, num_bits);
-                    let ends_with_zeros = x.bvand(&z3mask)._eq(&zero);
-                    r = ends_with_zeros.ite(&r.bvadd(&z3bits), &r);
-                    x = ends_with_zeros.ite(&x.bvlshr(&z3bits), &x);
-                }
-                let one = z3::Ast::bv_from_u64(&ctx, 1, num_bits);
-                r.bvadd(&one.bvsub(&x.bvand(&one)))
-            },
+/// Whether `constraint` only defines variables with ids of at least `first_fresh`, so
+/// that it can be satisfied whatever the values of the older variables.
+fn defines_fresh_vars(constraint: Constraint, first_fresh: u32) -> bool {
+    let fresh_sbyte = |sbyte: SByte| match sbyte {
+        SByte::Abstract(var) => var.0 >= first_fresh,
+        SByte::Concrete(_) => false,
+    };
+    let fresh = |primval: PrimVal| match primval {
+        PrimVal::Abstract(sbytes) => {
+            sbytes.iter().any(|&sbyte| fresh_sbyte(sbyte)) &&
+                sbytes.iter().all(|&sbyte| fresh_sbyte(sbyte) || sbyte == SByte::Concrete(0))
         }
+        _ => false,
+    };
+    match constraint {
+        Constraint::Binop { lhs, .. } |
+        Constraint::Unop { lhs, .. } |
+        Constraint::NumericIntrinsic { lhs, .. } |
+        Constraint::IfThenElse { lhs, .. } => fresh(lhs),
+        Constraint::ArrayElement { array, value, .. } =>
+            array.0 >= first_fresh || fresh_sbyte(value),
+        Constraint::ArrayStore { lhs, .. } => lhs.0 >= first_fresh,
+        Constraint::Compare { .. } => false,
     }
 }
+
+fn elapsed_ms(start: Instant) -> u64 {
+    duration_ms(start.elapsed())
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1_000_000) as u64
+}
@@ -0,0 +1,98 @@
+//! Records which basic blocks and branch edges the explored paths have visited.
+
+use rustc::mir;
+use rustc_data_structures::indexed_vec::Idx;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Shared by all paths of an exploration. Functions are identified by the
+/// printed form of their `Instance`.
+#[derive(Debug, Default)]
+pub(crate) struct CoverageCollector {
+    functions: BTreeMap<String, FunctionData>,
+}
+
+#[derive(Debug)]
+struct FunctionData {
+    blocks: usize,
+    edges: usize,
+    visited_blocks: BTreeSet<usize>,
+    visited_edges: BTreeSet<(usize, usize)>,
+}
+
+impl CoverageCollector {
+    /// Records that `function`, whose body is `mir`, was entered.
+    pub(crate) fn enter(&mut self, function: String, mir: &mir::Mir) {
+        self.functions.entry(function).or_insert_with(|| {
+            let edges = mir.basic_blocks().iter()
+                .map(|block| block.terminator().successors().len())
+                .sum();
+            FunctionData {
+                blocks: mir.basic_blocks().len(),
+                edges,
+                visited_blocks: BTreeSet::new(),
+                visited_edges: BTreeSet::new(),
+            }
+        }).visited_blocks.insert(mir::START_BLOCK.index());
+    }
+
+    /// Records that `function` went from block `from` to block `to`.
+    pub(crate) fn edge(&mut self, function: &str, from: mir::BasicBlock, to: mir::BasicBlock) {
+        if let Some(data) = self.functions.get_mut(function) {
+            data.visited_blocks.insert(to.index());
+            data.visited_edges.insert((from.index(), to.index()));
+        }
+    }
+
+    pub(crate) fn report(&self) -> CoverageReport {
+        let functions = self.functions.iter().map(|(name, data)| {
+            FunctionCoverage {
+                name: name.clone(),
+                blocks: data.blocks,
+                visited_blocks: data.visited_blocks.len(),
+                edges: data.edges,
+                visited_edges: data.visited_edges.len(),
+                unreached_blocks: (0..data.blocks)
+                    .filter(|block| !data.visited_blocks.contains(block))
+                    .collect(),
+            }
+        }).collect();
+        CoverageReport { functions }
+    }
+}
+
+/// The coverage of all paths explored so far, as returned by `ExecutionConfig::coverage`.
+#[derive(Clone, Debug)]
+pub struct CoverageReport {
+    /// Every function with MIR that was entered, sorted by name.
+    pub functions: Vec<FunctionCoverage>,
+}
+
+impl CoverageReport {
+    /// The coverage of the function printed as `name`, e.g. `main`.
+    pub fn function(&self, name: &str) -> Option<&FunctionCoverage> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FunctionCoverage {
+    pub name: String,
+
+    /// The number of basic blocks in the function.
+    pub blocks: usize,
+    pub visited_blocks: usize,
+
+    /// The number of edges between basic blocks, including unwind edges.
+    pub edges: usize,
+    pub visited_edges: usize,
+
+    unreached_blocks: Vec<usize>,
+}
+
+impl FunctionCoverage {
+    /// The indices of the basic blocks that no path has visited.
+    pub fn unreached_blocks(&self) -> &[usize] {
+        &self.unreached_blocks
+    }
+}
@@ -1,5 +1,8 @@
 use getopts;
+use rustc::hir;
+use rustc::hir::def_id::DefId;
 use rustc::session::Session;
+use rustc::ty::TyCtxt;
 use rustc::middle::cstore::CrateStore;
 use rustc_driver::{self, Compilation, CompilerCalls, RustcDefaultCalls};
 use rustc_driver::driver::{CompileState, CompileController};
@@ -7,9 +10,14 @@ use rustc_errors;
 use rustc::session::config::{self, Input, ErrorOutputType};
 use syntax;
 use syntax::ast::{MetaItemKind, NestedMetaItemKind, self};
+use std::cell::RefCell;
 use std::path::PathBuf;
+use std::rc::Rc;
 
-struct SeerCompilerCalls(RustcDefaultCalls, ::ExecutionConfig);
+/// Called with the function to explore once the crate has been analyzed.
+type Entry = Rc<RefCell<for<'a, 'tcx> FnMut(TyCtxt<'a, 'tcx, 'tcx>, DefId)>>;
+
+struct SeerCompilerCalls(RustcDefaultCalls, ::ExecutionConfig, Entry);
 
 impl<'a> CompilerCalls<'a> for SeerCompilerCalls {
     fn early_callback(
@@ -48,7 +56,7 @@ impl<'a> CompilerCalls<'a> for SeerCompilerCalls {
     fn build_controller(&mut self, sess: &Session, matches: &getopts::Matches) -> CompileController<'a> {
         let mut control = self.0.build_controller(sess, matches);
         control.after_hir_lowering.callback = Box::new(after_hir_lowering);
-        control.after_analysis.callback = after_analysis_run_main(self.1.clone());
+        control.after_analysis.callback = after_analysis_run_main(self.1.clone(), self.2.clone());
         control.after_analysis.stop = Compilation::Stop;
         control
     }
@@ -59,20 +67,29 @@ fn after_hir_lowering(state: &mut CompileState) {
     state.session.plugin_attributes.borrow_mut().push(attr);
 }
 
-fn after_analysis_run_main<'a, 'tcx>(config: ::ExecutionConfig)
+fn after_analysis_run_main<'a, 'tcx>(config: ::ExecutionConfig, entry: Entry)
     -> Box<Fn(&mut CompileState) + 'static>
 {
     Box::new(move |state: &mut CompileState| {
         state.session.abort_if_errors();
 
         let tcx = state.tcx.unwrap();
-        let limits = resource_limits_from_attributes(state);
 
-        if let Some((entry_node_id, _, _)) = *state.session.entry_fn.borrow() {
+        let entry_node_id = match config.entry_function {
+            Some(ref name) => match find_function(tcx, name) {
+                Some(node_id) => Some(node_id),
+                None => {
+                    state.session.err(&format!("no function with the path `{}` found", name));
+                    return;
+                }
+            },
+            None => state.session.entry_fn.borrow().as_ref().map(|&(node_id, _, _)| node_id),
+        };
+
+        if let Some(entry_node_id) = entry_node_id {
             let entry_def_id = tcx.hir.local_def_id(entry_node_id);
 
-            let mut executor = ::executor::Executor::new(tcx, entry_def_id, limits, config.clone());
-            executor.run();
+            (&mut *entry.borrow_mut())(tcx, entry_def_id);
 
             state.session.abort_if_errors();
         } else {
@@ -81,14 +98,24 @@ fn after_analysis_run_main<'a, 'tcx>(config: ::ExecutionConfig)
     })
 }
 
-fn resource_limits_from_attributes(state: &CompileState) -> ::ResourceLimits {
+fn find_function(tcx: TyCtxt, name: &str) -> Option<ast::NodeId> {
+    tcx.hir.krate().items.iter().find(|&(&node_id, item)| {
+        match item.node {
+            hir::ItemFn(..) => tcx.item_path_str(tcx.hir.local_def_id(node_id)) == name,
+            _ => false,
+        }
+    }).map(|(&node_id, _)| node_id)
+}
+
+/// The limits set by the `miri` attributes of the crate.
+pub(crate) fn resource_limits_from_attributes(tcx: TyCtxt) -> ::ResourceLimits {
     let mut limits = ::ResourceLimits::default();
-    let krate = state.hir_crate.as_ref().unwrap();
+    let krate = tcx.hir.krate();
     let err_msg = "miri attributes need to be in the form `miri(key = value)`";
     let extract_int = |lit: &syntax::ast::Lit| -> u128 {
         match lit.node {
             syntax::ast::LitKind::Int(i, _) => i,
-            _ => state.session.span_fatal(lit.span, "expected an integer literal"),
+            _ => tcx.sess.span_fatal(lit.span, "expected an integer literal"),
         }
     };
 
@@ -101,17 +128,31 @@ fn resource_limits_from_attributes(state: &CompileState) -> ::ResourceLimits {
                             "memory_size" => limits.memory_size = extract_int(value) as u64,
                             "step_limit" => limits.step_limit = extract_int(value) as u64,
                             "stack_limit" => limits.stack_limit = extract_int(value) as usize,
-                            _ => state.session.span_err(item.span, "unknown miri attribute"),
+                            "solver_timeout_ms" =>
+                                limits.solver_timeout_ms = Some(extract_int(value) as u64),
+                            "fn_ptr_target_limit" =>
+                                limits.fn_ptr_target_limit = extract_int(value) as usize,
+                            "time_limit_ms" =>
+                                limits.time_limit_ms = Some(extract_int(value) as u64),
+                            "time_check_interval" =>
+                                limits.time_check_interval = extract_int(value) as u64,
+                            "max_steps_per_frame" =>
+                                limits.max_steps_per_frame = Some(extract_int(value) as u64),
+                            "loop_bound" =>
+                                limits.loop_bound = Some(extract_int(value) as usize),
+                            "max_c_str_len" =>
+                                limits.max_c_str_len = Some(extract_int(value) as u64),
+                            _ => tcx.sess.span_err(item.span, "unknown miri attribute"),
                         }
                     } else {
-                        state.session.span_err(inner.span, err_msg);
+                        tcx.sess.span_err(inner.span, err_msg);
                     }
                 } else {
-                    state.session.span_err(item.span, err_msg);
+                    tcx.sess.span_err(item.span, err_msg);
                 }
             }
         } else {
-            state.session.span_err(attr.span, err_msg);
+            tcx.sess.span_err(attr.span, err_msg);
         }
     }
     limits
@@ -129,7 +170,7 @@ fn find_sysroot() -> String {
     }
 }
 
-pub fn main_helper(mut args: Vec<String>, config: ::ExecutionConfig)
+pub fn main_helper(mut args: Vec<String>, config: ::ExecutionConfig, entry: Entry)
 {
     let sysroot_flag = String::from("--sysroot");
     if !args.contains(&sysroot_flag) {
@@ -137,11 +178,26 @@ pub fn main_helper(mut args: Vec<String>, config: ::ExecutionConfig)
         args.push(find_sysroot());
     }
 
+    let target_flag = String::from("--target");
+    if let Some(triple) = config.target_triple() {
+        if !args.contains(&target_flag) {
+            args.push(target_flag);
+            args.push(triple);
+        }
+    }
+
+    if !args.iter().any(|arg| arg.contains("debug-assertions")) {
+        let enabled = if config.debug_assertions_enabled() { "on" } else { "off" };
+        args.push(format!("-Cdebug-assertions={}", enabled));
+    }
+
     // TODO(cleanup) is this still necessary?
-    args.push("-Zmir-opt-level=0".to_owned());
+    if !args.iter().any(|arg| arg.contains("mir-opt-level")) {
+        args.push("-Zmir-opt-level=0".to_owned());
+    }
     // for auxilary builds in unit tests
     args.push("-Zalways-encode-mir".to_owned());
 
-    rustc_driver::run_compiler(&args, &mut SeerCompilerCalls(RustcDefaultCalls, config),
+    rustc_driver::run_compiler(&args, &mut SeerCompilerCalls(RustcDefaultCalls, config, entry),
                                None, None);
 }
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::fmt;
+use rustc::hir::def_id::DefId;
 use rustc::mir;
 use rustc::ty::{FnSig, Ty, layout};
 use memory::{MemoryPointer, PointerOffset};
@@ -12,8 +13,15 @@ pub enum EvalError<'tcx> {
     NoMirFor(String),
     UnterminatedCString(MemoryPointer),
     DanglingPointerDeref,
+    UseAfterFree {
+        freed_at: Span,
+    },
     InvalidMemoryAccess,
     InvalidFunctionPointer,
+    TooManyFunctionPointerTargets {
+        targets: usize,
+        limit: usize,
+    },
     InvalidBool,
     InvalidDiscriminant,
     PointerOutOfBounds {
@@ -28,12 +36,21 @@ pub enum EvalError<'tcx> {
     ReadUndefBytes,
     InvalidBoolOp(mir::BinOp),
     Unimplemented(String),
+    UnsupportedSymbolicOperation {
+        op: String,
+        ty: String,
+    },
     DerefFunctionPointer,
     ExecuteMemory,
     ArrayIndexOutOfBounds(Span, u64, u64),
     Math(Span, ConstMathErr),
     Intrinsic(String),
     OverflowingMath,
+    DivisionByZero(Option<Span>),
+    InexactDivision {
+        dividend: u128,
+        divisor: u128,
+    },
     InvalidChar(u128),
     OutOfMemory {
         allocation_size: u64,
@@ -41,7 +58,14 @@ pub enum EvalError<'tcx> {
         memory_usage: u64,
     },
     ExecutionTimeLimitReached,
+    SolverTimeout {
+        elapsed_ms: u64,
+    },
     StackFrameLimitReached,
+    BranchDepthLimitReached,
+    FrameStepLimitReached,
+    LoopBoundReached,
+    NonProgressingRecursion(DefId),
     AlignmentCheckFailed {
         required: u64,
         has: u64,
@@ -54,7 +78,7 @@ pub enum EvalError<'tcx> {
     TypeNotPrimitive(Ty<'tcx>),
     ReallocatedStaticMemory,
     DeallocatedStaticMemory,
-    Layout(layout::LayoutError<'tcx>),
+    Layout(LayoutErrorCause<'tcx>),
     HeapAllocZeroBytes,
     HeapAllocNonPowerOfTwoAlignment(u64),
     Unreachable,
@@ -65,6 +89,22 @@ pub enum EvalError<'tcx> {
 
 pub type EvalResult<'tcx, T = ()> = Result<T, EvalError<'tcx>>;
 
+/// Wraps a rustc `LayoutError` so that it can be returned from `Error::cause`.
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutErrorCause<'tcx>(pub layout::LayoutError<'tcx>);
+
+impl<'tcx> Error for LayoutErrorCause<'tcx> {
+    fn description(&self) -> &str {
+        "rustc layout computation failed"
+    }
+}
+
+impl<'tcx> fmt::Display for LayoutErrorCause<'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
 impl<'tcx> Error for EvalError<'tcx> {
     fn description(&self) -> &str {
         match *self {
@@ -74,8 +114,12 @@ impl<'tcx> Error for EvalError<'tcx> {
                 "tried to access memory through an invalid pointer",
             EvalError::DanglingPointerDeref =>
                 "dangling pointer was dereferenced",
+            EvalError::UseAfterFree { .. } =>
+                "pointer to freed memory was dereferenced",
             EvalError::InvalidFunctionPointer =>
                 "tried to use an integer pointer or a dangling pointer as a function pointer",
+            EvalError::TooManyFunctionPointerTargets { .. } =>
+                "a symbolic function pointer has more feasible targets than the configured limit",
             EvalError::InvalidBool =>
                 "invalid boolean value read",
             EvalError::InvalidDiscriminant =>
@@ -95,6 +139,8 @@ impl<'tcx> Error for EvalError<'tcx> {
             EvalError::InvalidBoolOp(_) =>
                 "invalid boolean operation",
             EvalError::Unimplemented(ref msg) => msg,
+            EvalError::UnsupportedSymbolicOperation { .. } =>
+                "tried to perform an operation that is not supported on symbolic values",
             EvalError::DerefFunctionPointer =>
                 "tried to dereference a function pointer",
             EvalError::ExecuteMemory =>
@@ -107,6 +153,10 @@ impl<'tcx> Error for EvalError<'tcx> {
                 "intrinsic failed",
             EvalError::OverflowingMath =>
                 "attempted to do overflowing math",
+            EvalError::DivisionByZero(..) =>
+                "attempted to divide by zero",
+            EvalError::InexactDivision { .. } =>
+                "exact division has a remainder",
             EvalError::NoMirFor(..) =>
                 "mir not found",
             EvalError::InvalidChar(..) =>
@@ -115,8 +165,18 @@ impl<'tcx> Error for EvalError<'tcx> {
                 "could not allocate more memory",
             EvalError::ExecutionTimeLimitReached =>
                 "reached the configured maximum execution time",
+            EvalError::SolverTimeout { .. } =>
+                "the constraint solver exceeded its time budget",
             EvalError::StackFrameLimitReached =>
                 "reached the configured maximum number of stack frames",
+            EvalError::BranchDepthLimitReached =>
+                "reached the configured maximum number of branches on a single path",
+            EvalError::FrameStepLimitReached =>
+                "reached the configured maximum number of steps in a single stack frame",
+            EvalError::LoopBoundReached =>
+                "reached the configured maximum number of iterations of a loop on a single path",
+            EvalError::NonProgressingRecursion(_) =>
+                "a function called itself recursively with the arguments it was entered with",
             EvalError::AlignmentCheckFailed{..} =>
                 "tried to execute a misaligned read or write",
             EvalError::CalledClosureAsFunction =>
@@ -128,7 +188,7 @@ impl<'tcx> Error for EvalError<'tcx> {
             EvalError::AssumptionNotHeld =>
                 "`assume` argument was false",
             EvalError::InlineAsm =>
-                "miri does not support inline assembly",
+                "miri does not support inline assembly without a summary",
             EvalError::TypeNotPrimitive(_) =>
                 "expected primitive type, got nonprimitive",
             EvalError::ReallocatedStaticMemory =>
@@ -148,13 +208,18 @@ impl<'tcx> Error for EvalError<'tcx> {
             EvalError::Panic =>
                 "the evaluated program panicked",
             EvalError::ReadFromReturnPointer =>
-                "tried to read from the return pointer",
+                "tried to read from the return pointer before the return value was written",
             EvalError::TypeckError =>
                 "encountered constants with type errors, stopping evaluation",
         }
     }
 
-    fn cause(&self) -> Option<&Error> { None }
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            EvalError::Layout(ref err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl<'tcx> fmt::Display for EvalError<'tcx> {
@@ -167,61 +232,138 @@ impl<'tcx> fmt::Display for EvalError<'tcx> {
                                if access { "memory access" } else { "pointer computed" },
                                ptr_offset, ptr.alloc_id, allocation_size)
                     }
-                    _ => unimplemented!(),
+                    PointerOffset::Abstract(sbytes) => {
+                        write!(f, "{} at symbolic offset {:?}, outside bounds of allocation {} which has size {}",
+                               if access { "memory access" } else { "pointer computed" },
+                               sbytes, ptr.alloc_id, allocation_size)
+                    }
                 }
             },
             EvalError::NoMirFor(ref func) => write!(f, "no mir for `{}`", func),
             EvalError::FunctionPointerTyMismatch(sig, got) =>
-                write!(f, "tried to call a function with sig {} through a function pointer of type {}", sig, got),
+                write!(f, "tried to call a function with sig {} ({} ABI, {} arguments) \
+                           through a function pointer of type {} ({} ABI, {} arguments)",
+                       sig, sig.abi.name(), sig.inputs().len(), got, got.abi.name(), got.inputs().len()),
             EvalError::ArrayIndexOutOfBounds(span, len, index) =>
                 write!(f, "index out of bounds: the len is {} but the index is {} at {:?}", len, index, span),
             EvalError::Math(span, ref err) =>
                 write!(f, "{:?} at {:?}", err, span),
+            EvalError::DivisionByZero(Some(span)) =>
+                write!(f, "attempted to divide by zero at {:?}", span),
+            EvalError::UseAfterFree { freed_at } =>
+                write!(f, "pointer to memory freed at {:?} was dereferenced", freed_at),
+            EvalError::InexactDivision { dividend, divisor } =>
+                write!(f, "exact division of {} by {} has a remainder", dividend, divisor),
             EvalError::InvalidChar(c) =>
                 write!(f, "tried to interpret an invalid 32-bit value as a char: {}", c),
             EvalError::OutOfMemory { allocation_size, memory_size, memory_usage } =>
                 write!(f, "tried to allocate {} more bytes, but only {} bytes are free of the {} byte memory",
                        allocation_size, memory_size - memory_usage, memory_size),
+            EvalError::SolverTimeout { elapsed_ms } =>
+                write!(f, "the constraint solver gave up after {} ms", elapsed_ms),
+            EvalError::TooManyFunctionPointerTargets { targets, limit } =>
+                write!(f, "symbolic function pointer has at least {} feasible targets, but the limit is {}",
+                       targets, limit),
             EvalError::AlignmentCheckFailed { required, has } =>
                write!(f, "tried to access memory with alignment {}, but alignment {} is required",
                       has, required),
+            EvalError::UnsupportedSymbolicOperation { ref op, ref ty } =>
+                write!(f, "{} is not supported on a symbolic {} value", op, ty),
             EvalError::TypeNotPrimitive(ty) =>
                 write!(f, "expected primitive type, got {}", ty),
             EvalError::Layout(ref err) =>
-                write!(f, "rustc layout computation failed: {:?}", err),
+                write!(f, "rustc layout computation failed: {}", err),
             _ => write!(f, "{}", self.description()),
         }
     }
 }
 
+impl<'tcx> EvalError<'tcx> {
+    /// The source location that the error records, if any. Errors without one are
+    /// located at the statement that was evaluated, see `ExecutionComplete::location`.
+    pub fn span(&self) -> Option<Span> {
+        match *self {
+            EvalError::ArrayIndexOutOfBounds(span, ..) |
+            EvalError::Math(span, _) |
+            EvalError::DivisionByZero(Some(span)) => Some(span),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum StaticEvalError {
-    FunctionPointerTyMismatch,
+    FunctionPointerTyMismatch {
+        /// The signature of the function that was called.
+        expected: String,
+        /// The type of the function pointer it was called through.
+        got: String,
+        /// The calling conventions of the function and the pointer, e.g. `C` or `Rust`.
+        expected_abi: String,
+        got_abi: String,
+        /// The number of arguments of the function and the pointer.
+        expected_args: usize,
+        got_args: usize,
+    },
     NoMirFor(String),
     UnterminatedCString(MemoryPointer),
     DanglingPointerDeref,
+    UseAfterFree {
+        /// Where the evaluated program freed the allocation.
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::span"))]
+        freed_at: Span,
+    },
     InvalidMemoryAccess,
     InvalidFunctionPointer,
+    TooManyFunctionPointerTargets {
+        targets: usize,
+        limit: usize,
+    },
     InvalidBool,
     InvalidDiscriminant,
     PointerOutOfBounds {
         ptr: MemoryPointer,
         access: bool,
         allocation_size: u64,
+        /// A concrete offset that triggers the out-of-bounds access. For
+        /// symbolic offsets this is filled in from a model of the path
+        /// constraints by the executor.
+        offset_witness: Option<u64>,
     },
     InvalidNullPointerUsage,
     ReadPointerAsBytes,
     ReadBytesAsPointer,
     InvalidPointerMath,
     ReadUndefBytes,
-    InvalidBoolOp(mir::BinOp),
+    InvalidBoolOp(
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::BinOpDef"))] mir::BinOp,
+    ),
     Unimplemented(String),
+    UnsupportedSymbolicOperation {
+        op: String,
+        ty: String,
+    },
     DerefFunctionPointer,
     ExecuteMemory,
-    ArrayIndexOutOfBounds(Span, u64, u64),
-    Math(Span, ConstMathErr),
+    ArrayIndexOutOfBounds(
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::span"))] Span,
+        u64,
+        u64,
+    ),
+    Math(
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::span"))] Span,
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::ConstMathErrDef"))] ConstMathErr,
+    ),
     Intrinsic(String),
     OverflowingMath,
+    DivisionByZero(
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::option_span"))] Option<Span>,
+    ),
+    InexactDivision {
+        dividend: u128,
+        divisor: u128,
+    },
     InvalidChar(u128),
     OutOfMemory {
         allocation_size: u64,
@@ -229,7 +371,16 @@ pub enum StaticEvalError {
         memory_usage: u64,
     },
     ExecutionTimeLimitReached,
+    SolverTimeout {
+        elapsed_ms: u64,
+    },
     StackFrameLimitReached,
+    BranchDepthLimitReached,
+    FrameStepLimitReached,
+    LoopBoundReached,
+    NonProgressingRecursion(
+        #[cfg_attr(feature = "serialize", serde(with = "::serialize::def_id"))] DefId,
+    ),
     AlignmentCheckFailed {
         required: u64,
         has: u64,
@@ -254,20 +405,36 @@ pub enum StaticEvalError {
 impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
     fn from(v: EvalError<'tcx>) -> Self {
         match v {
-            EvalError::FunctionPointerTyMismatch(..) =>
-                StaticEvalError::FunctionPointerTyMismatch,
+            EvalError::FunctionPointerTyMismatch(sig, got) =>
+                StaticEvalError::FunctionPointerTyMismatch {
+                    expected: sig.to_string(),
+                    got: got.to_string(),
+                    expected_abi: sig.abi.name().to_string(),
+                    got_abi: got.abi.name().to_string(),
+                    expected_args: sig.inputs().len(),
+                    got_args: got.inputs().len(),
+                },
             EvalError::InvalidMemoryAccess =>
                 StaticEvalError::InvalidMemoryAccess,
             EvalError::DanglingPointerDeref =>
                 StaticEvalError::DanglingPointerDeref,
+            EvalError::UseAfterFree { freed_at } =>
+                StaticEvalError::UseAfterFree { freed_at },
             EvalError::InvalidFunctionPointer =>
                 StaticEvalError::InvalidFunctionPointer,
+            EvalError::TooManyFunctionPointerTargets { targets, limit } =>
+                StaticEvalError::TooManyFunctionPointerTargets { targets, limit },
             EvalError::InvalidBool =>
                 StaticEvalError::InvalidBool,
             EvalError::InvalidDiscriminant =>
                 StaticEvalError::InvalidDiscriminant,
-            EvalError::PointerOutOfBounds { ptr, access, allocation_size } =>
-                StaticEvalError::PointerOutOfBounds { ptr, access, allocation_size },
+            EvalError::PointerOutOfBounds { ptr, access, allocation_size } => {
+                let offset_witness = match ptr.offset {
+                    PointerOffset::Concrete(offset) => Some(offset),
+                    PointerOffset::Abstract(_) => None,
+                };
+                StaticEvalError::PointerOutOfBounds { ptr, access, allocation_size, offset_witness }
+            }
             EvalError::InvalidNullPointerUsage =>
                 StaticEvalError::InvalidNullPointerUsage,
             EvalError::ReadPointerAsBytes =>
@@ -282,6 +449,8 @@ impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
                 StaticEvalError::InvalidBoolOp(op),
             EvalError::Unimplemented(ref msg) =>
                 StaticEvalError::Unimplemented(msg.clone()),
+            EvalError::UnsupportedSymbolicOperation { op, ty } =>
+                StaticEvalError::UnsupportedSymbolicOperation { op, ty },
             EvalError::DerefFunctionPointer =>
                 StaticEvalError::DerefFunctionPointer,
             EvalError::ExecuteMemory =>
@@ -294,6 +463,10 @@ impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
                 StaticEvalError::Intrinsic(s),
             EvalError::OverflowingMath =>
                 StaticEvalError::OverflowingMath,
+            EvalError::DivisionByZero(span) =>
+                StaticEvalError::DivisionByZero(span),
+            EvalError::InexactDivision { dividend, divisor } =>
+                StaticEvalError::InexactDivision { dividend, divisor },
             EvalError::NoMirFor(ref s) =>
                 StaticEvalError::NoMirFor(s.clone()),
             EvalError::InvalidChar(c) =>
@@ -302,8 +475,18 @@ impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
                 StaticEvalError::OutOfMemory { allocation_size, memory_size, memory_usage },
             EvalError::ExecutionTimeLimitReached =>
                 StaticEvalError::ExecutionTimeLimitReached,
+            EvalError::SolverTimeout { elapsed_ms } =>
+                StaticEvalError::SolverTimeout { elapsed_ms },
             EvalError::StackFrameLimitReached =>
                 StaticEvalError::StackFrameLimitReached,
+            EvalError::BranchDepthLimitReached =>
+                StaticEvalError::BranchDepthLimitReached,
+            EvalError::FrameStepLimitReached =>
+                StaticEvalError::FrameStepLimitReached,
+            EvalError::LoopBoundReached =>
+                StaticEvalError::LoopBoundReached,
+            EvalError::NonProgressingRecursion(def_id) =>
+                StaticEvalError::NonProgressingRecursion(def_id),
             EvalError::AlignmentCheckFailed { required, has, } =>
                 StaticEvalError::AlignmentCheckFailed { required, has, },
             EvalError::CalledClosureAsFunction =>
@@ -1,5 +1,8 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use rustc::hir::def_id::DefId;
 use rustc::middle::const_val::ConstVal;
@@ -7,12 +10,18 @@ use rustc::mir;
 use rustc::ty::layout::{self, Size, HasDataLayout, LayoutOf, TyLayout};
 use rustc::ty::subst::{Subst, Substs, Kind};
 use rustc::ty::{self, Ty, TyCtxt, TypeFoldable, Binder};
+use rustc_data_structures::control_flow_graph::dominators::Dominators;
 use rustc_data_structures::indexed_vec::Idx;
 use syntax::codemap::{self, DUMMY_SP};
 
-use error::{EvalError, EvalResult};
+use constraints::{Constraint, ConstraintContext, VarType};
+use coverage::CoverageCollector;
+use error::{EvalError, EvalResult, LayoutErrorCause};
+use executor::{FinishStep, FinishStepVariant};
+use input::{InputShape, SymbolicInput};
 use lvalue::{Global, GlobalId, Lvalue, LvalueExtra};
-use memory::{Memory, MemoryPointer};
+use memory::{AllocId, AllocKind, AllocationReport, Memory, MemoryPointer, PointerOffset, SByte};
+use solver::Model;
 use value::{PrimVal, PrimValKind, Value};
 
 
@@ -37,9 +46,167 @@ pub struct EvalContext<'a, 'tcx: 'a> {
     /// Remove once halting problem is solved.
     pub(crate) steps_remaining: u64,
 
+    /// When the exploration started. Paths forked from this one keep the same start time.
+    pub(crate) start_time: Instant,
+
+    /// The wall-clock budget of the exploration, if any.
+    pub(crate) time_limit: Option<Duration>,
+
+    /// How many steps pass between two checks of the time limit.
+    pub(crate) time_check_interval: u64,
+
+    /// Steps left until the time limit is checked again.
+    pub(crate) steps_until_time_check: u64,
+
+    /// The maximum number of steps that a single stack frame may execute, if any.
+    pub(crate) max_steps_per_frame: Option<u64>,
+
+    /// The maximum number of times a loop header may be entered through a back edge of a single
+    /// stack frame, if any.
+    pub(crate) loop_bound: Option<usize>,
+
+    /// The maximum length of a C string whose nul terminator is searched for, if any.
+    pub(crate) max_c_str_len: Option<u64>,
+
     /// Environment variables set by `setenv`
     /// Miri does not expose env vars from the host to the emulated program
     pub(crate) env_vars: HashMap<Vec<u8>, MemoryPointer>,
+
+    /// Called whenever the evaluated program panics, before `EvalError::Panic` is returned.
+    pub(crate) panic_handler: Option<Rc<RefCell<FnMut(&PanicInfo)>>>,
+
+    /// Called for every branch of every fork.
+    pub(crate) branch_trace: Option<Rc<RefCell<FnMut(&BranchEvent)>>>,
+
+    /// The panic that ended the current path, if it was intercepted.
+    pub(crate) panic_info: Option<PanicInfo>,
+
+    /// Returns the constraints to assume on the bytes of every read from stdin.
+    pub(crate) stdin_constraints: Option<Rc<RefCell<FnMut(&[PrimVal]) -> Vec<Constraint>>>>,
+
+    /// The number of times the current path has forked.
+    pub(crate) branch_depth: usize,
+
+    /// The number of statements and terminators that the current path has evaluated.
+    pub(crate) executed_steps: u64,
+
+    /// The node of the path tree that the current path is at, if the tree is recorded.
+    pub(crate) path_node: usize,
+
+    /// The wall-clock time that the executor has spent stepping the current path.
+    pub(crate) elapsed: Duration,
+
+    /// The maximum number of targets that a symbolic function pointer is resolved to.
+    pub(crate) fn_ptr_target_limit: usize,
+
+    /// The vtables created so far, so that each type and trait share a single one.
+    pub(crate) vtables: HashMap<(Ty<'tcx>, ty::PolyTraitRef<'tcx>), MemoryPointer>,
+
+    /// Records the visited blocks and edges, if coverage is tracked.
+    pub(crate) coverage: Option<Rc<RefCell<CoverageCollector>>>,
+
+    /// The paths of the functions whose calls are skipped, see `ExecutionConfig::opaque_fns`.
+    pub(crate) opaque_fns: Rc<Vec<String>>,
+
+    /// The summaries of inline assembly, keyed by template. See `ExecutionConfig::asm_summary`.
+    pub(crate) asm_summaries: Rc<HashMap<String, AsmSummary>>,
+
+    /// Whether unsupported operations on symbolic values concretize their operands
+    /// instead of failing with `EvalError::UnsupportedSymbolicOperation`.
+    pub(crate) concretize_on_unsupported: bool,
+
+    /// Whether the overflow checks that rustc only inserts with debug assertions are
+    /// explored. Without them, the arithmetic wraps as in release builds.
+    pub(crate) debug_assertions: bool,
+
+    /// Whether a recursive call that repeats an active call of the same function fails
+    /// with `EvalError::NonProgressingRecursion`.
+    pub(crate) detect_nonprogressing_recursion: bool,
+
+    /// Whether statements that only combine concrete integers in locals are evaluated
+    /// directly, bypassing the symbolic machinery.
+    pub(crate) concrete_fast_path: bool,
+
+    /// The values that the current path has concretized, in order.
+    pub(crate) concretizations: Vec<Concretization>,
+}
+
+/// Describes a panic of the evaluated program.
+#[derive(Clone, Debug)]
+pub struct PanicInfo {
+    /// The panic message, if it is a string that does not need formatting.
+    pub message: Option<String>,
+
+    /// The file, line and column passed to the panic machinery.
+    pub location: Option<(String, u32, u32)>,
+
+    /// The span of the call that started the panic.
+    pub span: codemap::Span,
+}
+
+/// Describes one branch of a fork, as passed to the branch trace.
+#[derive(Clone, Debug)]
+pub struct BranchEvent {
+    /// The function that forked, printed as its `Instance`.
+    pub function: String,
+
+    /// The MIR location of the fork. `statement` equals the number of statements of
+    /// the block if the terminator forked.
+    pub block: usize,
+    pub statement: usize,
+
+    pub kind: BranchKind,
+
+    /// The index of this branch among the feasible branches of the fork.
+    pub branch: usize,
+
+    /// The block this branch continues at, or `None` if it continues within the
+    /// current block or ends in an error.
+    pub target: Option<usize>,
+
+    /// The constraints that the branch adds to the path.
+    pub constraints: Vec<Constraint>,
+}
+
+/// What kind of code forked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BranchKind {
+    /// A `SwitchInt` on a boolean, or an `Assert`.
+    Conditional,
+
+    /// A `SwitchInt` on an integer or an enum discriminant.
+    SwitchInt,
+
+    /// An assignment reading through a symbolic pointer.
+    SymbolicPointer,
+
+    /// Any other fork, such as a call of an intrinsic with symbolic arguments, or the
+    /// discriminant of a symbolic enum.
+    Other,
+}
+
+/// The value that a summary of inline assembly assigns to one of its outputs.
+#[derive(Clone, Copy, Debug)]
+pub enum AsmOutput {
+    Value(PrimVal),
+
+    /// A fresh symbolic value of the type of the output.
+    Symbolic,
+}
+
+/// Returns the outputs of an `asm!` block, given the values of its inputs.
+pub(crate) type AsmSummary = Rc<RefCell<FnMut(&[PrimVal]) -> Vec<AsmOutput>>>;
+
+/// A symbolic value that was pinned to a single value so that an unsupported
+/// operation on it could be evaluated. Paths that would take other values are lost.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Concretization {
+    /// The operation, as in `EvalError::UnsupportedSymbolicOperation`.
+    pub op: String,
+    pub ty: String,
+
+    /// The value chosen by the solver, as raw bytes.
+    pub value: u128,
 }
 
 impl <'a, 'tcx: 'a> Clone for EvalContext<'a, 'tcx> {
@@ -51,7 +218,32 @@ impl <'a, 'tcx: 'a> Clone for EvalContext<'a, 'tcx> {
             stack: self.stack.clone(),
             stack_limit: self.stack_limit,
             steps_remaining: self.steps_remaining,
+            start_time: self.start_time,
+            time_limit: self.time_limit,
+            time_check_interval: self.time_check_interval,
+            steps_until_time_check: self.steps_until_time_check,
+            max_steps_per_frame: self.max_steps_per_frame,
+            loop_bound: self.loop_bound,
+            max_c_str_len: self.max_c_str_len,
             env_vars: self.env_vars.clone(),
+            panic_handler: self.panic_handler.clone(),
+            branch_trace: self.branch_trace.clone(),
+            panic_info: self.panic_info.clone(),
+            stdin_constraints: self.stdin_constraints.clone(),
+            branch_depth: self.branch_depth,
+            executed_steps: self.executed_steps,
+            path_node: self.path_node,
+            elapsed: self.elapsed,
+            fn_ptr_target_limit: self.fn_ptr_target_limit,
+            vtables: self.vtables.clone(),
+            coverage: self.coverage.clone(),
+            opaque_fns: self.opaque_fns.clone(),
+            asm_summaries: self.asm_summaries.clone(),
+            concretize_on_unsupported: self.concretize_on_unsupported,
+            debug_assertions: self.debug_assertions,
+            detect_nonprogressing_recursion: self.detect_nonprogressing_recursion,
+            concrete_fast_path: self.concrete_fast_path,
+            concretizations: self.concretizations.clone(),
         }
     }
 }
@@ -98,6 +290,24 @@ pub struct Frame<'tcx> {
 
     /// The index of the currently evaluated statment.
     pub stmt: usize,
+
+    /// The number of steps executed in this frame, not counting those of its callees.
+    pub steps: u64,
+
+    /// The dominator tree of `mir`, used to recognize back edges. Only computed when a loop bound
+    /// is configured.
+    pub dominators: Option<Rc<Dominators<mir::BasicBlock>>>,
+
+    /// The number of times each loop header has been entered through a back edge.
+    pub loop_visits: HashMap<mir::BasicBlock, usize>,
+
+    /// The arguments the frame was entered with, recorded before its first step. `None` if any of
+    /// them refers to memory, since the callee may then make progress through that memory.
+    pub entry_args: Option<Vec<Value>>,
+
+    /// The number of symbolic variables and path constraints when the frame took its first
+    /// step. A callee that reads input or forks has made progress even with the same arguments.
+    pub entry_path: (usize, usize),
 }
 
 impl <'tcx> Clone for Frame<'tcx> {
@@ -111,6 +321,11 @@ impl <'tcx> Clone for Frame<'tcx> {
             locals: self.locals.clone(),
             block: self.block.clone(),
             stmt: self.stmt,
+            steps: self.steps,
+            dominators: self.dominators.clone(),
+            loop_visits: self.loop_visits.clone(),
+            entry_args: self.entry_args.clone(),
+            entry_path: self.entry_path,
         }
     }
 }
@@ -135,6 +350,21 @@ pub struct ResourceLimits {
     pub memory_size: u64,
     pub step_limit: u64,
     pub stack_limit: usize,
+    /// Time budget for each constraint solver query, in milliseconds.
+    pub solver_timeout_ms: Option<u64>,
+    /// The maximum number of functions that a function pointer read from a
+    /// symbolic address may resolve to.
+    pub fn_ptr_target_limit: usize,
+    /// Wall-clock budget for the whole exploration, in milliseconds.
+    pub time_limit_ms: Option<u64>,
+    /// The number of steps between two checks of `time_limit_ms`.
+    pub time_check_interval: u64,
+    /// The maximum number of steps that a single stack frame may execute.
+    pub max_steps_per_frame: Option<u64>,
+    /// The maximum number of iterations of a loop within a single stack frame.
+    pub loop_bound: Option<usize>,
+    /// The maximum length of a C string with a symbolic terminator.
+    pub max_c_str_len: Option<u64>,
 }
 
 impl Default for ResourceLimits {
@@ -143,6 +373,13 @@ impl Default for ResourceLimits {
             memory_size: 100 * 1024 * 1024, // 100 MB
             step_limit: 1_000_000,
             stack_limit: 100,
+            solver_timeout_ms: None,
+            fn_ptr_target_limit: 16,
+            time_limit_ms: None,
+            time_check_interval: 1000,
+            max_steps_per_frame: None,
+            loop_bound: None,
+            max_c_str_len: None,
         }
     }
 }
@@ -201,7 +438,7 @@ impl<'a, 'tcx> LayoutOf<Ty<'tcx>> for &'a EvalContext<'a, 'tcx> {
 
     fn layout_of(self, ty: Ty<'tcx>) -> Self::TyLayout {
         self.tcx.layout_of(::rustc::ty::ParamEnv::empty().and(ty))
-            .map_err(|layout| EvalError::Layout(layout).into())
+            .map_err(|layout| EvalError::Layout(LayoutErrorCause(layout)).into())
     }
 }
 
@@ -217,14 +454,144 @@ impl<'c, 'b, 'a, 'tcx> LayoutOf<Ty<'tcx>>
 
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>, limits: ResourceLimits) -> Self {
+        let start_time = Instant::now();
+        let time_limit = limits.time_limit_ms.map(Duration::from_millis);
+        let time_check_interval = ::std::cmp::max(limits.time_check_interval, 1);
+        let mut memory = Memory::new(&tcx.data_layout, limits.memory_size);
+        memory.constraints.set_solver_timeout(limits.solver_timeout_ms);
+        memory.constraints.set_deadline(time_limit.map(|limit| start_time + limit));
         EvalContext {
             tcx,
-            memory: Memory::new(&tcx.data_layout, limits.memory_size),
+            memory,
             globals: HashMap::new(),
             stack: Vec::new(),
             stack_limit: limits.stack_limit,
             steps_remaining: limits.step_limit,
+            start_time,
+            time_limit,
+            time_check_interval,
+            steps_until_time_check: time_check_interval,
+            max_steps_per_frame: limits.max_steps_per_frame,
+            loop_bound: limits.loop_bound,
+            max_c_str_len: limits.max_c_str_len,
             env_vars: HashMap::new(),
+            panic_handler: None,
+            branch_trace: None,
+            panic_info: None,
+            stdin_constraints: None,
+            branch_depth: 0,
+            executed_steps: 0,
+            path_node: 0,
+            elapsed: Duration::from_secs(0),
+            fn_ptr_target_limit: limits.fn_ptr_target_limit,
+            vtables: HashMap::new(),
+            coverage: None,
+            opaque_fns: Rc::new(Vec::new()),
+            asm_summaries: Rc::new(HashMap::new()),
+            concretize_on_unsupported: false,
+            debug_assertions: true,
+            detect_nonprogressing_recursion: false,
+            concrete_fast_path: true,
+            concretizations: Vec::new(),
+        }
+    }
+
+    /// Registers `handler` to be called with the details of every panic of the
+    /// evaluated program. The panic is then reported as `EvalError::Panic` as usual.
+    pub fn set_panic_handler<F>(&mut self, handler: F)
+        where F: FnMut(&PanicInfo) + 'static
+    {
+        self.panic_handler = Some(Rc::new(RefCell::new(handler)));
+    }
+
+    /// Registers `trace` to be called with every branch of every fork.
+    pub fn set_branch_trace<F>(&mut self, trace: F)
+        where F: FnMut(&BranchEvent) + 'static
+    {
+        self.branch_trace = Some(Rc::new(RefCell::new(trace)));
+    }
+
+    /// Registers `constraints` to be called with the bytes of every read from stdin, each
+    /// as a `U8`. The constraints it returns are added with `assume_constraint`.
+    pub fn set_stdin_constraints<F>(&mut self, constraints: F)
+        where F: FnMut(&[PrimVal]) -> Vec<Constraint> + 'static
+    {
+        self.stdin_constraints = Some(Rc::new(RefCell::new(constraints)));
+    }
+
+    /// Adds `constraint` to the path constraints, e.g. to keep the symbolic inputs within
+    /// the domain of interest. Returns false if the constraints can no longer be satisfied,
+    /// in which case the path should be abandoned.
+    pub fn assume_constraint(&mut self, constraint: Constraint) -> bool {
+        self.memory.constraints.push_constraint(constraint);
+        self.memory.constraints.is_feasible_with(&[])
+    }
+
+    /// Replays the path that produced `model`, e.g. one returned by
+    /// `ExecutionComplete::model`, by pinning the symbolic inputs that are created from
+    /// now on to their values in `model`. Each branch then has a single feasible side, so
+    /// the path does not fork. Must be called before the program reads any input.
+    pub fn replay(&mut self, model: &Model) {
+        self.memory.constraints.set_replay_model(model.clone());
+    }
+
+    /// Asks whether the boolean `value` can be true at this point of the path, e.g. whether
+    /// a pointer can be null here. See `Memory::is_feasible`.
+    pub fn is_feasible(&self, value: &Value) -> EvalResult<'tcx, bool> {
+        self.memory.is_feasible(value)
+    }
+
+    /// The allocations that have not been freed so far. At the end of the program, these
+    /// are its leaks. See `Memory::unfreed_allocations`.
+    pub fn leaked_allocations(&self) -> Vec<AllocationReport> {
+        self.memory.unfreed_allocations()
+    }
+
+    /// Enumerates up to `limit` values that `val` can take at this point of the path,
+    /// without constraining the path. Also returns whether more values were feasible.
+    /// See `ConstraintContext::enumerate_values`.
+    pub fn concretize_all(
+        &self,
+        val: PrimVal,
+        kind: PrimValKind,
+        limit: usize,
+    ) -> EvalResult<'tcx, (Vec<u128>, bool)> {
+        match val {
+            PrimVal::Bytes(_) | PrimVal::Abstract(_) => {
+                Ok(self.memory.constraints.enumerate_values(val, kind, limit))
+            }
+            PrimVal::Ptr(_) => Err(EvalError::ReadPointerAsBytes),
+            PrimVal::Undef => Err(EvalError::ReadUndefBytes),
+        }
+    }
+
+    /// Pins `val` to a value that satisfies the path constraints, recording the
+    /// concretization. Returns `None` if `val` is not symbolic.
+    pub(crate) fn concretize(
+        &mut self,
+        val: PrimVal,
+        kind: PrimValKind,
+        op: &str,
+        ty: &str,
+    ) -> Option<PrimVal> {
+        if let PrimVal::Abstract(_) = val {
+            // The solver picks floats by their bits.
+            let kind = if kind.is_float() {
+                PrimValKind::from_uint_size(kind.num_bytes() as u64)
+            } else {
+                kind
+            };
+            let value = self.memory.constraints.get_witness_with(val, kind, &[])?;
+            self.memory.constraints.push_constraint(
+                Constraint::new_compare(mir::BinOp::Eq, kind, val, PrimVal::Bytes(value)));
+            self.concretizations.push(Concretization {
+                op: op.to_owned(),
+                ty: ty.to_owned(),
+                value,
+            });
+            Some(PrimVal::Bytes(value))
+        } else {
+            None
         }
     }
 
@@ -251,10 +618,249 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         &mut self.memory
     }
 
+    /// Renders the bytes of an allocation for debugging. See `Memory::dump_allocation`.
+    pub fn dump_allocation(&self, id: AllocId) -> String {
+        self.memory.dump_allocation(id)
+    }
+
+    /// The path condition accumulated so far on this execution path.
+    pub fn constraints(&self) -> &ConstraintContext {
+        &self.memory.constraints
+    }
+
+    /// The maximum number of stack frames of this path.
+    pub fn stack_limit(&self) -> usize {
+        self.stack_limit
+    }
+
+    /// The wall-clock budget of the exploration, if any.
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    /// The maximum number of times a loop header may be revisited in a single stack
+    /// frame, if any.
+    pub fn loop_bound(&self) -> Option<usize> {
+        self.loop_bound
+    }
+
+    /// Creates a value of type `ty` whose bytes are fresh symbolic variables, and
+    /// records it as the input `name`, so that it shows up in the input model of
+    /// every path. Bools are kept to 0 or 1 and chars to valid code points.
+    /// Primitive values are returned by value, anything else in a new allocation.
+    pub(crate) fn fresh_input(&mut self, name: String, ty: Ty<'tcx>) -> EvalResult<'tcx, Value> {
+        let shape = self.input_shape(ty)?;
+        let size = self.type_size(ty)?.expect("symbolic inputs are sized");
+        let mut leaves = Vec::new();
+        self.fresh_input_leaves(&shape, 0, &mut leaves);
+
+        let mut bytes = vec![SByte::Concrete(0); size as usize];
+        for &(offset, sbytes, leaf_size) in &leaves {
+            let offset = offset as usize;
+            bytes[offset..offset + leaf_size as usize].copy_from_slice(&sbytes[..leaf_size as usize]);
+        }
+        self.memory.constraints.record_input(SymbolicInput {
+            name,
+            ty: ty.to_string(),
+            shape: shape.clone(),
+            bytes,
+        });
+
+        match shape {
+            InputShape::Bool | InputShape::Char | InputShape::Int { .. } => {
+                Ok(Value::ByVal(PrimVal::Abstract(leaves[0].1)))
+            }
+            _ => {
+                let ptr = self.alloc_ptr(ty)?;
+                self.memory.get_mut(ptr.alloc_id)?.kind = AllocKind::Input;
+                for (offset, sbytes, leaf_size) in leaves {
+                    let dest = ptr.offset(offset, self.memory.layout)?;
+                    self.memory.write_primval(PrimVal::Ptr(dest), PrimVal::Abstract(sbytes), leaf_size)?;
+                }
+                Ok(Value::ByRef(ptr))
+            }
+        }
+    }
+
+    /// Creates a fresh symbolic value of type `ty`, laid out as the type's layout
+    /// requires, and registers it as an input of the exploration. Integers, bools,
+    /// chars, and tuples and arrays of them are supported. Aggregates are returned
+    /// `ByRef`, in an allocation that the caller owns.
+    pub fn mk_symbolic(&mut self, ty: Ty<'tcx>) -> EvalResult<'tcx, Value> {
+        let name = format!("symbolic{}", self.memory.constraints.num_inputs());
+        self.fresh_input(name, ty)
+    }
+
+    fn input_shape(&self, ty: Ty<'tcx>) -> EvalResult<'tcx, InputShape> {
+        use syntax::ast::{IntTy, UintTy};
+        let shape = match ty.sty {
+            ty::TyBool => InputShape::Bool,
+            ty::TyChar => InputShape::Char,
+            ty::TyInt(IntTy::I128) | ty::TyUint(UintTy::U128) => {
+                return Err(EvalError::Unimplemented(format!("symbolic input of type {}", ty)));
+            }
+            ty::TyInt(_) => InputShape::Int {
+                size: self.type_size(ty)?.expect("integers are sized"),
+                signed: true,
+            },
+            ty::TyUint(_) => InputShape::Int {
+                size: self.type_size(ty)?.expect("integers are sized"),
+                signed: false,
+            },
+            ty::TyTuple(ref types) => {
+                let mut fields = Vec::new();
+                for (idx, &field_ty) in types.iter().enumerate() {
+                    let offset = self.get_field_offset(ty, idx)?.bytes();
+                    fields.push((offset, self.input_shape(field_ty)?));
+                }
+                InputShape::Tuple(fields)
+            }
+            ty::TyArray(elem_ty, n) => InputShape::Array {
+                elem: Box::new(self.input_shape(elem_ty)?),
+                stride: self.type_size(elem_ty)?.expect("array elements are sized"),
+                len: n.val.unwrap_u64(),
+            },
+            _ => return Err(EvalError::Unimplemented(format!("symbolic input of type {}", ty))),
+        };
+        Ok(shape)
+    }
+
+    /// Allocates the variables of every primitive part of `shape`, pushing their
+    /// offset, bytes and size onto `leaves`.
+    fn fresh_input_leaves(
+        &mut self,
+        shape: &InputShape,
+        offset: u64,
+        leaves: &mut Vec<(u64, [SByte; 8], u64)>,
+    ) {
+        let mut sbytes = [SByte::Concrete(0); 8];
+        match *shape {
+            InputShape::Bool => {
+                sbytes[0] = self.memory.constraints.fresh_input_var(VarType::Bool);
+                leaves.push((offset, sbytes, 1));
+            }
+            InputShape::Char => {
+                let constraints = &mut self.memory.constraints;
+                for sbyte in &mut sbytes[..4] {
+                    *sbyte = constraints.fresh_input_var(VarType::BitVec8);
+                }
+                let c = PrimVal::Abstract(sbytes);
+                let below_surrogates = constraints.add_binop_constraint(
+                    mir::BinOp::Lt, c, PrimVal::Bytes(0xd800), PrimValKind::U32);
+                let above_surrogates = constraints.add_binop_constraint(
+                    mir::BinOp::Gt, c, PrimVal::Bytes(0xdfff), PrimValKind::U32);
+                let not_surrogate = constraints.add_binop_constraint(
+                    mir::BinOp::BitOr, below_surrogates, above_surrogates, PrimValKind::Bool);
+                constraints.push_constraint(Constraint::new_compare(
+                    mir::BinOp::Eq, PrimValKind::Bool, not_surrogate, PrimVal::from_bool(true)));
+                constraints.push_constraint(Constraint::new_compare(
+                    mir::BinOp::Lt, PrimValKind::U32, c, PrimVal::Bytes(0x110000)));
+                leaves.push((offset, sbytes, 4));
+            }
+            InputShape::Int { size, .. } => {
+                for sbyte in &mut sbytes[..size as usize] {
+                    *sbyte = self.memory.constraints.fresh_input_var(VarType::BitVec8);
+                }
+                leaves.push((offset, sbytes, size));
+            }
+            InputShape::Tuple(ref fields) => {
+                for &(field_offset, ref field) in fields {
+                    self.fresh_input_leaves(field, offset + field_offset, leaves);
+                }
+            }
+            InputShape::Array { ref elem, stride, len } => {
+                for idx in 0..len {
+                    self.fresh_input_leaves(elem, offset + idx * stride, leaves);
+                }
+            }
+        }
+    }
+
     pub fn stack(&self) -> &[Frame<'tcx>] {
         &self.stack
     }
 
+    /// The span in the evaluated crate that the current step comes from: the current
+    /// statement or terminator if the innermost frame is in the crate, or else the call
+    /// that left the crate, e.g. the `drop(b)` that ends up in `__rust_dealloc`.
+    pub(crate) fn local_span(&self) -> codemap::Span {
+        let local = self.stack.iter().rposition(|frame| frame.instance.def_id().is_local());
+        match local {
+            Some(idx) if idx + 1 < self.stack.len() => self.stack[idx + 1].span,
+            _ => {
+                let frame = self.frame();
+                let block = &frame.mir.basic_blocks()[frame.block];
+                if frame.stmt < block.statements.len() {
+                    block.statements[frame.stmt].source_info.span
+                } else {
+                    block.terminator().source_info.span
+                }
+            }
+        }
+    }
+
+    /// Whether the current frame is at the start of a block that several blocks jump to,
+    /// where paths that took different branches can be merged.
+    pub(crate) fn at_join_point(&self) -> bool {
+        match self.stack.last() {
+            Some(frame) => frame.stmt == 0 && frame.mir.predecessors_for(frame.block).len() > 1,
+            None => false,
+        }
+    }
+
+    /// Whether `self` and `other` are about to execute the same statement with the same
+    /// call stack.
+    pub(crate) fn same_location(&self, other: &EvalContext<'a, 'tcx>) -> bool {
+        self.stack.len() == other.stack.len() &&
+            self.stack.iter().zip(&other.stack).all(|(a, b)| {
+                a.instance == b.instance && a.block == b.block && a.stmt == b.stmt &&
+                    a.return_to_block == b.return_to_block && a.return_lvalue == b.return_lvalue
+            })
+    }
+
+    /// Merges `other`, a path at the same location, with this one. The merged path has the
+    /// values of this path where a fresh symbolic guard holds, and those of `other` otherwise.
+    /// Returns `None` if the paths differ in more than the values of locals and memory.
+    pub(crate) fn merged(&self, other: &EvalContext<'a, 'tcx>) -> Option<EvalContext<'a, 'tcx>> {
+        let same_globals = self.globals.len() == other.globals.len() &&
+            self.globals.iter().all(|(id, global)| match other.globals.get(id) {
+                Some(other_global) => global.value == other_global.value &&
+                    global.initialized == other_global.initialized &&
+                    global.mutable == other_global.mutable,
+                None => false,
+            });
+        if !self.same_location(other) || !same_globals || self.env_vars != other.env_vars ||
+            self.concretizations != other.concretizations {
+            return None;
+        }
+
+        let mut merged = self.clone();
+        let guard = merged.memory.constraints.merge(&other.memory.constraints)?;
+        if !merged.memory.merge(&other.memory, guard) {
+            return None;
+        }
+        for frame in 0..merged.stack.len() {
+            for idx in 0..merged.stack[frame].locals.len() {
+                let value = merged.stack[frame].locals[idx];
+                let other_value = other.stack[frame].locals[idx];
+                let kind = if value == other_value {
+                    None
+                } else {
+                    // +1 since we don't store the return value
+                    let ty = merged.stack[frame].mir.local_decls[mir::Local::new(idx + 1)].ty;
+                    let ty = merged.monomorphize(ty, merged.stack[frame].instance.substs);
+                    merged.ty_to_primval_kind(ty).ok()
+                };
+                merged.stack[frame].locals[idx] =
+                    value.merge(other_value, kind, guard, &mut merged.memory.constraints)?;
+            }
+            merged.stack[frame].steps = ::std::cmp::max(merged.stack[frame].steps, other.stack[frame].steps);
+        }
+        merged.steps_remaining = ::std::cmp::min(self.steps_remaining, other.steps_remaining);
+        merged.branch_depth = ::std::cmp::max(self.branch_depth, other.branch_depth);
+        Some(merged)
+    }
+
     /// Returns true if the current frame or any parent frame is part of a ctfe.
     ///
     /// Used to disable features in const eval, which do not have a rfc enabling
@@ -404,6 +1010,15 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let num_locals = mir.local_decls.len() - 1;
         let locals = vec![Value::ByVal(PrimVal::Undef); num_locals];
 
+        if let Some(ref coverage) = self.coverage {
+            coverage.borrow_mut().enter(instance.to_string(), mir);
+        }
+
+        let dominators = match self.loop_bound {
+            Some(_) => Some(Rc::new(mir.dominators())),
+            None => None,
+        };
+
         self.stack.push(Frame {
             mir,
             block: mir::START_BLOCK,
@@ -413,6 +1028,11 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             span,
             instance,
             stmt: 0,
+            steps: 0,
+            dominators,
+            loop_visits: HashMap::new(),
+            entry_args: None,
+            entry_path: (0, 0),
         });
 
         if self.stack.len() > self.stack_limit {
@@ -478,13 +1098,77 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         &mut self,
         rvalue: &mir::Rvalue<'tcx>,
         lvalue: &mir::Place<'tcx>,
-    ) -> EvalResult<'tcx> {
+    ) -> EvalResult<'tcx, Option<Vec<FinishStep<'tcx>>>> {
         let dest = self.eval_lvalue(lvalue)?;
         let dest_ty = self.lvalue_ty(lvalue);
 
         use rustc::mir::Rvalue::*;
         match *rvalue {
             Use(ref operand) => {
+                if let ty::TyFnPtr(_) = dest_ty.sty {
+                    if let Some(targets) = self.read_abstract_fn_ptr(operand)? {
+                        let branches = targets.into_iter().map(|(target, constraints)| {
+                            let variant = match target {
+                                Some(fn_ptr) => FinishStepVariant::NextStatement {
+                                    set_lvalue: Some((dest, fn_ptr, dest_ty)),
+                                },
+                                None => FinishStepVariant::Error(EvalError::ReadBytesAsPointer),
+                            };
+                            FinishStep { constraints, variant }
+                        }).collect();
+                        return Ok(Some(branches));
+                    }
+                }
+                let misalignment = self.abstract_read_misalignment(operand, dest_ty)?;
+                let defined = self.abstract_read_is_defined(operand, dest_ty)?;
+                if misalignment.is_some() || defined.is_some() {
+                    let mut branches = Vec::new();
+                    let mut aligned = Vec::new();
+                    if let Some((remainder, required)) = misalignment {
+                        let misaligned = Constraint::new_compare(
+                            mir::BinOp::Ne, PrimValKind::U64, remainder, PrimVal::Bytes(0));
+                        // No witness means the branch is infeasible, or that the solver
+                        // timed out, which ends the path once the step is done.
+                        if let Some(has) = self.memory.constraints.get_witness_with(
+                            remainder, PrimValKind::U64, &[misaligned])
+                        {
+                            branches.push(FinishStep {
+                                constraints: vec![misaligned],
+                                variant: FinishStepVariant::Error(
+                                    EvalError::AlignmentCheckFailed { has: has as u64, required }),
+                            });
+                        }
+                        aligned.push(Constraint::new_compare(
+                            mir::BinOp::Eq, PrimValKind::U64, remainder, PrimVal::Bytes(0)));
+                    }
+
+                    // Like a concrete read of undefined bytes, a read that may touch
+                    // undefined bytes only fails once the value is used.
+                    let value = self.eval_operand(operand)?;
+                    self.write_value(ValTy { value, ty: dest_ty }, dest)?;
+                    let cases = match defined {
+                        Some(defined) => vec![
+                            (Some(Constraint::new_compare(
+                                mir::BinOp::Eq, PrimValKind::Bool, defined, PrimVal::from_bool(true))),
+                             None),
+                            (Some(Constraint::new_compare(
+                                mir::BinOp::Eq, PrimValKind::Bool, defined, PrimVal::from_bool(false))),
+                             Some((dest, PrimVal::Undef, dest_ty))),
+                        ],
+                        None => vec![(None, None)],
+                    };
+                    for (is_defined, set_lvalue) in cases {
+                        let mut constraints = aligned.clone();
+                        constraints.extend(is_defined);
+                        if self.memory.constraints.is_feasible_with(&constraints) {
+                            branches.push(FinishStep {
+                                constraints,
+                                variant: FinishStepVariant::NextStatement { set_lvalue },
+                            });
+                        }
+                    }
+                    return Ok(Some(branches));
+                }
                 let value = self.eval_operand(operand)?;
                 self.write_value(ValTy { value, ty: dest_ty }, dest)?;
             }
@@ -558,8 +1242,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             Len(ref lvalue) => {
                 let src = self.eval_lvalue(lvalue)?;
                 let ty = self.lvalue_ty(lvalue);
-                let (_, len) = src.elem_ty_and_len(ty);
-                self.write_primval(dest, PrimVal::from_u128(len as u128), dest_ty)?;
+                let (_, len) = src.elem_ty_and_len_primval(ty);
+                self.write_primval(dest, len, dest_ty)?;
             }
 
             Ref(_, _, ref lvalue) => {
@@ -686,6 +1370,19 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     mask = mask | (0xffu128 << (idx * 8));
                 }
 
+                if let Some(candidates) = self.read_abstract_discriminant_value(lval, ty)? {
+                    let branches = candidates.into_iter().map(|(discr_val, constraints)| {
+                        let variant = match discr_val {
+                            Some(discr_val) => FinishStepVariant::NextStatement {
+                                set_lvalue: Some((dest, PrimVal::Bytes(mask & discr_val), dest_ty)),
+                            },
+                            None => FinishStepVariant::Error(EvalError::InvalidDiscriminant),
+                        };
+                        FinishStep { constraints, variant }
+                    }).collect();
+                    return Ok(Some(branches));
+                }
+
                 let discr_val = mask & self.read_discriminant_value(lval, ty)?;
                 if let ty::TyAdt(adt_def, _) = ty.sty {
                     trace!("Read discriminant {}, valid discriminants {:?}", discr_val, adt_def.discriminants(self.tcx).collect::<Vec<_>>());
@@ -708,7 +1405,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             self.dump_local(dest);
         }
 
-        Ok(())
+        Ok(None)
     }
 
     fn type_is_fat_ptr(&self, ty: Ty<'tcx>) -> bool {
@@ -873,6 +1570,242 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         Ok(discr_val)
     }
 
+    /// Reads the tag of `lvalue` if it is stored in symbolic bytes. Returns the raw tag,
+    /// its kind, pairs of (raw tag, discriminant value), and the discriminant value if none
+    /// of the tags match, which is `None` if such a tag does not belong to any variant.
+    /// Returns `Ok(None)` if the tag is concrete.
+    fn read_abstract_discriminant_tags(
+        &mut self,
+        lvalue: Lvalue<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, Option<(PrimVal, PrimValKind, Vec<(u128, u128)>, Option<u128>)>> {
+        let layout = self.type_layout(ty)?;
+        if let layout::Variants::Single { .. } = layout.variants {
+            return Ok(None);
+        }
+
+        let (discr_lvalue, discr) = self.lvalue_field(lvalue, mir::Field::new(0), layout)?;
+        let read_discr_lvalue = self.read_lvalue(discr_lvalue)?;
+        let raw_discr_primval = self.value_to_primval(read_discr_lvalue, discr.ty)?;
+        if raw_discr_primval.is_concrete() {
+            return Ok(None);
+        }
+        let discr_kind = self.ty_to_primval_kind(discr.ty)?;
+        let discr_size = discr.size.bytes();
+        let truncate = |v: u128| if discr_size >= 16 { v } else { v & ((1u128 << (discr_size * 8)) - 1) };
+
+        // Pairs of (raw tag, discriminant value), and the value to use if none of the tags match.
+        let mut tags = Vec::new();
+        let otherwise = match layout.variants {
+            layout::Variants::Single { .. } => bug!(),
+            layout::Variants::Tagged { .. } => {
+                let adt_def = ty.ty_adt_def().expect("tagged layout for a non-ADT type");
+                for discr_val in adt_def.discriminants(self.tcx) {
+                    tags.push((truncate(discr_val.val), discr_val.val));
+                }
+                None
+            }
+            layout::Variants::NicheFilling {
+                dataful_variant,
+                ref niche_variants,
+                niche_start,
+                ..
+            } => {
+                for variant in niche_variants.start..(niche_variants.end + 1) {
+                    let tag = (variant as u128)
+                        .wrapping_sub(niche_variants.start as u128)
+                        .wrapping_add(niche_start);
+                    tags.push((truncate(tag), variant as u128));
+                }
+                Some(dataful_variant as u128)
+            }
+        };
+
+        Ok(Some((raw_discr_primval, discr_kind, tags, otherwise)))
+    }
+
+    /// Reads the discriminant of `lvalue` if it is stored in symbolic bytes. Returns
+    /// every feasible discriminant value along with the constraints under which it
+    /// is read; a value of `None` means that the tag does not belong to any variant.
+    /// Returns `Ok(None)` if the discriminant is concrete.
+    fn read_abstract_discriminant_value(
+        &mut self,
+        lvalue: Lvalue<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, Option<Vec<(Option<u128>, Vec<Constraint>)>>> {
+        let (raw_discr_primval, discr_kind, tags, otherwise) =
+            match self.read_abstract_discriminant_tags(lvalue, ty)? {
+                Some(tags) => tags,
+                None => return Ok(None),
+            };
+
+        let mut result = Vec::new();
+        let mut otherwise_constraints = Vec::new();
+        for (tag, discr_val) in tags {
+            let eq_constraint = Constraint::new_compare(
+                mir::BinOp::Eq, discr_kind, raw_discr_primval, PrimVal::Bytes(tag));
+            otherwise_constraints.push(Constraint::new_compare(
+                mir::BinOp::Ne, discr_kind, raw_discr_primval, PrimVal::Bytes(tag)));
+            if self.memory.constraints.is_feasible_with(&[eq_constraint]) {
+                result.push((Some(discr_val), vec![eq_constraint]));
+            }
+        }
+        if self.memory.constraints.is_feasible_with(&otherwise_constraints) {
+            result.push((otherwise, otherwise_constraints));
+        }
+
+        Ok(Some(result))
+    }
+
+    /// Reads the discriminant of `lvalue` as a single symbolic `u64` if its tag is stored
+    /// in symbolic bytes. Returns the value along with the constraints under which the tag
+    /// belongs to a variant, and the constraints under which it does not, if that is
+    /// feasible. Returns `Ok(None)` if the discriminant is concrete.
+    pub(crate) fn read_abstract_discriminant_primval(
+        &mut self,
+        lvalue: Lvalue<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, Option<(PrimVal, Vec<Constraint>, Option<Vec<Constraint>>)>> {
+        let (raw_discr_primval, discr_kind, tags, otherwise) =
+            match self.read_abstract_discriminant_tags(lvalue, ty)? {
+                Some(tags) => tags,
+                None => return Ok(None),
+            };
+
+        let mut is_tags = Vec::new();
+        for &(tag, _) in &tags {
+            is_tags.push(self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Eq, raw_discr_primval, PrimVal::Bytes(tag), discr_kind));
+        }
+
+        // Without a fallback the last variant is the value of the innermost `else`,
+        // as the tag is known to match one of the variants there.
+        let (mut value, valid_constraints, invalid) = match otherwise {
+            Some(otherwise) => (PrimVal::Bytes(otherwise), Vec::new(), None),
+            None => {
+                let last = tags.last().expect("tagged layout without variants").1;
+                let mut any_tag = PrimVal::from_bool(false);
+                for &is_tag in &is_tags {
+                    any_tag = self.memory.constraints.add_binop_constraint(
+                        mir::BinOp::BitOr, any_tag, is_tag, PrimValKind::Bool);
+                }
+                let valid = vec![Constraint::new_compare(
+                    mir::BinOp::Eq, PrimValKind::Bool, any_tag, PrimVal::from_bool(true))];
+                let invalid = vec![Constraint::new_compare(
+                    mir::BinOp::Eq, PrimValKind::Bool, any_tag, PrimVal::from_bool(false))];
+                let invalid = if self.memory.constraints.is_feasible_with(&invalid) {
+                    Some(invalid)
+                } else {
+                    None
+                };
+                (PrimVal::Bytes(last), valid, invalid)
+            }
+        };
+        for (&(_, discr_val), &is_tag) in tags.iter().zip(&is_tags).rev() {
+            value = self.memory.constraints.add_if_then_else(
+                is_tag, PrimValKind::U64, PrimVal::Bytes(discr_val as u64 as u128), value);
+        }
+
+        Ok(Some((value, valid_constraints, invalid)))
+    }
+
+    /// If `operand` reads a primitive from a symbolic address of an allocation with
+    /// undefined bytes, returns a boolean that is true when the read avoids them.
+    fn abstract_read_is_defined(
+        &mut self,
+        operand: &mir::Operand<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, Option<PrimVal>> {
+        match ty.sty {
+            ty::TyBool | ty::TyChar | ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) => {}
+            _ => return Ok(None),
+        }
+        let lvalue = match *operand {
+            mir::Operand::Copy(ref lvalue) |
+            mir::Operand::Move(ref lvalue) => self.eval_lvalue(lvalue)?,
+            mir::Operand::Constant(_) => return Ok(None),
+        };
+        match lvalue {
+            Lvalue::Ptr { ptr: PrimVal::Ptr(ptr), .. } if !ptr.has_concrete_offset() => {
+                let size = self.type_size(ty)?.expect("primitive types are sized");
+                self.memory.abstract_read_is_defined(ptr, size)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// If `operand` reads a primitive from a symbolic address, returns the address modulo
+    /// the alignment of the primitive, together with that alignment.
+    fn abstract_read_misalignment(
+        &mut self,
+        operand: &mir::Operand<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, Option<(PrimVal, u64)>> {
+        match ty.sty {
+            ty::TyBool | ty::TyChar | ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) => {}
+            _ => return Ok(None),
+        }
+        let lvalue = match *operand {
+            mir::Operand::Copy(ref lvalue) |
+            mir::Operand::Move(ref lvalue) => self.eval_lvalue(lvalue)?,
+            mir::Operand::Constant(_) => return Ok(None),
+        };
+        match lvalue {
+            Lvalue::Ptr { ptr: PrimVal::Ptr(ptr), .. } if !ptr.has_concrete_offset() => {
+                let align = self.type_align(ty)?;
+                Ok(self.memory.abstract_misalignment(ptr, align)?.map(|rem| (rem, align)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// If `operand` reads a function pointer from a symbolic address, returns the
+    /// pointers stored at each feasible address together with the constraints that
+    /// select it. A `None` target stands for the addresses that hold no pointer.
+    fn read_abstract_fn_ptr(
+        &mut self,
+        operand: &mir::Operand<'tcx>,
+    ) -> EvalResult<'tcx, Option<Vec<(Option<PrimVal>, Vec<Constraint>)>>> {
+        let lvalue = match *operand {
+            mir::Operand::Copy(ref lvalue) |
+            mir::Operand::Move(ref lvalue) => self.eval_lvalue(lvalue)?,
+            mir::Operand::Constant(_) => return Ok(None),
+        };
+        let ptr = match lvalue {
+            Lvalue::Ptr { ptr: PrimVal::Ptr(ptr), .. } => ptr,
+            _ => return Ok(None),
+        };
+        let offset = match ptr.offset {
+            PointerOffset::Abstract(sbytes) => PrimVal::Abstract(sbytes),
+            PointerOffset::Concrete(_) => return Ok(None),
+        };
+
+        let slots: Vec<u64> = self.memory.get(ptr.alloc_id)?.relocations.keys().cloned().collect();
+        let mut result = Vec::new();
+        let mut otherwise_constraints = Vec::new();
+        for slot in slots {
+            let eq_constraint = Constraint::new_compare(
+                mir::BinOp::Eq, PrimValKind::U64, offset, PrimVal::Bytes(slot as u128));
+            otherwise_constraints.push(Constraint::new_compare(
+                mir::BinOp::Ne, PrimValKind::U64, offset, PrimVal::Bytes(slot as u128)));
+            if self.memory.constraints.is_feasible_with(&[eq_constraint]) {
+                if result.len() == self.fn_ptr_target_limit {
+                    return Err(EvalError::TooManyFunctionPointerTargets {
+                        targets: result.len() + 1,
+                        limit: self.fn_ptr_target_limit,
+                    });
+                }
+                let fn_ptr = self.memory.read_ptr(MemoryPointer::new(ptr.alloc_id, slot))?;
+                result.push((Some(fn_ptr), vec![eq_constraint]));
+            }
+        }
+        if self.memory.constraints.is_feasible_with(&otherwise_constraints) {
+            result.push((None, otherwise_constraints));
+        }
+
+        Ok(Some(result))
+    }
+
     pub(crate) fn write_discriminant_value(
         &mut self,
         dest_ty: Ty<'tcx>,
@@ -1315,8 +2248,20 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 }
             }
 
-            ty::TyFloat(FloatTy::F32) => PrimVal::from_f32(self.memory.read_f32(ptr)?),
-            ty::TyFloat(FloatTy::F64) => PrimVal::from_f64(self.memory.read_f64(ptr)?),
+            ty::TyFloat(FloatTy::F32) => {
+                if !self.memory.points_to_concrete(ptr, 4)? {
+                    self.memory.read_abstract(PrimVal::Ptr(ptr), 4)?
+                } else {
+                    PrimVal::from_f32(self.memory.read_f32(ptr)?)
+                }
+            }
+            ty::TyFloat(FloatTy::F64) => {
+                if !self.memory.points_to_concrete(ptr, 8)? {
+                    self.memory.read_abstract(PrimVal::Ptr(ptr), 8)?
+                } else {
+                    PrimVal::from_f64(self.memory.read_f64(ptr)?)
+                }
+            }
 
             ty::TyFnPtr(_) => self.memory.read_ptr(ptr)?,
             ty::TyRef(_, ref tam) |
@@ -1539,7 +2484,7 @@ impl<'tcx> Frame<'tcx> {
         Ok(self.locals[local.index() - 1])
     }
 
-    fn set_local(&mut self, local: mir::Local, value: Value) {
+    pub(crate) fn set_local(&mut self, local: mir::Local, value: Value) {
         // Subtract 1 because we don't store a value for the ReturnPointer, the local with index 0.
         self.locals[local.index() - 1] = value;
     }
@@ -1,25 +1,67 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
 
 use rustc::hir::def_id::DefId;
 use rustc::hir::map::definitions::DefPathData;
 use rustc::mir;
 use rustc::ty::{self, TyCtxt, Ty};
-use syntax::codemap::{DUMMY_SP};
+use syntax::codemap::{DUMMY_SP, Span};
 
 use constraints::Constraint;
-use error::{StaticEvalError, EvalError};
+use coverage::{CoverageCollector, CoverageReport};
+use error::{StaticEvalError, EvalError, EvalResult};
+use input::ConcreteValue;
 use lvalue::{Lvalue};
-use eval_context::{EvalContext, Frame, ResourceLimits, StackPopCleanup};
-use value::{PrimVal};
+use memory::AllocationReport;
+use path_tree::{PathOutcome, PathTree};
+use eval_context::{AsmOutput, AsmSummary, BranchEvent, Concretization, EvalContext, Frame, PanicInfo, ResourceLimits, StackPopCleanup};
+use solver::{CachingBackend, IncrementalBackend, Model, SolverBackend};
+use strategy::{Frontier, PathStrategy};
+use value::{PrimVal, PrimValKind};
 
 pub struct Executor<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     queue: VecDeque<EvalContext<'a, 'tcx>>,
+    completed: VecDeque<ExecutionComplete>,
+    /// The number of paths handed out by `Explore`.
+    reported: usize,
     config: ExecutionConfig,
 }
 
+/// Iterator over the paths of an `Executor`, returned by `Executor::explore`.
+///
+/// Paths are only explored as far as needed to produce the next item, so
+/// dropping the iterator stops the exploration.
+pub struct Explore<'a, 'tcx: 'a> {
+    executor: Executor<'a, 'tcx>,
+}
+
+impl<'a, 'tcx: 'a> Iterator for Explore<'a, 'tcx> {
+    type Item = ExecutionComplete;
+
+    fn next(&mut self) -> Option<ExecutionComplete> {
+        loop {
+            if let Some(max) = self.executor.config.max_paths {
+                if self.executor.reported >= max {
+                    if !self.executor.completed.is_empty() || !self.executor.queue.is_empty() {
+                        self.executor.config.truncated.set(true);
+                    }
+                    return None;
+                }
+            }
+            if let Some(complete) = self.executor.completed.pop_front() {
+                self.executor.reported += 1;
+                return Some(complete);
+            }
+            if !self.executor.step_path() {
+                return None;
+            }
+        }
+    }
+}
+
 pub struct FinishStep<'tcx> {
     pub constraints: Vec<Constraint>,
     pub variant: FinishStepVariant<'tcx>,
@@ -30,20 +72,85 @@ pub enum FinishStepVariant<'tcx> {
         goto_block: mir::BasicBlock,
         set_lvalue: Option<(Lvalue<'tcx>, PrimVal, Ty<'tcx>)>,
     },
+    /// Used when a statement forks. The forking statement has already been
+    /// stepped past, so execution resumes at the following statement.
+    NextStatement {
+        set_lvalue: Option<(Lvalue<'tcx>, PrimVal, Ty<'tcx>)>,
+    },
     Error(EvalError<'tcx>),
 }
 
 #[derive(Clone)]
 pub struct ExecutionConfig {
     consumer: Option<Rc<RefCell<FnMut(ExecutionComplete) -> bool>>>,
+    panic_handler: Option<Rc<RefCell<FnMut(&PanicInfo)>>>,
+    branch_trace: Option<Rc<RefCell<FnMut(&BranchEvent)>>>,
+    stdin_constraints: Option<Rc<RefCell<FnMut(&[PrimVal]) -> Vec<Constraint>>>>,
+    solver_backend: Rc<SolverBackend>,
+    path_strategy: Option<Rc<RefCell<PathStrategy>>>,
     emit_error: bool,
+    memory_size: Option<u64>,
+    stack_limit: Option<usize>,
+    max_branch_depth: Option<usize>,
+    fn_ptr_target_limit: Option<usize>,
+    time_limit_ms: Option<u64>,
+    time_check_interval: Option<u64>,
+    max_steps_per_frame: Option<u64>,
+    loop_bound: Option<usize>,
+    max_c_str_len: Option<u64>,
+    max_paths: Option<usize>,
+    truncated: Rc<Cell<bool>>,
+    state_merging: bool,
+    concretize_on_unsupported: bool,
+    debug_assertions: bool,
+    detect_nonprogressing_recursion: bool,
+    concrete_fast_path: bool,
+    report_pruned: bool,
+    coverage: Option<Rc<RefCell<CoverageCollector>>>,
+    path_tree: Option<Rc<RefCell<PathTree>>>,
+    track_accesses: bool,
+    opaque_fns: Rc<Vec<String>>,
+    asm_summaries: Rc<HashMap<String, AsmSummary>>,
+    replay: Option<Model>,
+    target_pointer_width: Option<u32>,
+    pub(crate) entry_function: Option<String>,
 }
 
 impl ExecutionConfig {
     pub fn new() -> Self {
         ExecutionConfig {
             consumer: None,
+            panic_handler: None,
+            branch_trace: None,
+            stdin_constraints: None,
+            solver_backend: Rc::new(CachingBackend::new(IncrementalBackend::new())),
+            path_strategy: None,
             emit_error: false,
+            memory_size: None,
+            stack_limit: None,
+            max_branch_depth: None,
+            fn_ptr_target_limit: None,
+            time_limit_ms: None,
+            time_check_interval: None,
+            max_steps_per_frame: None,
+            loop_bound: None,
+            max_c_str_len: None,
+            max_paths: None,
+            truncated: Rc::new(Cell::new(false)),
+            state_merging: false,
+            concretize_on_unsupported: false,
+            debug_assertions: true,
+            detect_nonprogressing_recursion: false,
+            concrete_fast_path: true,
+            report_pruned: false,
+            coverage: None,
+            path_tree: None,
+            track_accesses: false,
+            opaque_fns: Rc::new(Vec::new()),
+            asm_summaries: Rc::new(HashMap::new()),
+            replay: None,
+            target_pointer_width: None,
+            entry_function: None,
         }
     }
 
@@ -52,6 +159,240 @@ impl ExecutionConfig {
         self
     }
 
+    /// Caps the memory that each execution path may allocate, in bytes. Overrides
+    /// the `memory_size` attribute of the evaluated crate.
+    pub fn memory_size<'a>(&'a mut self, size: u64) -> &'a mut Self {
+        assert!(size > 0, "memory size must be nonzero");
+        self.memory_size = Some(size);
+        self
+    }
+
+    /// Abandons a path with `StackFrameLimitReached` once its call stack is more than
+    /// `frames` frames deep. Overrides the `stack_limit` attribute of the evaluated crate.
+    pub fn stack_limit<'a>(&'a mut self, frames: usize) -> &'a mut Self {
+        assert!(frames > 0, "stack limit must be nonzero");
+        self.stack_limit = Some(frames);
+        self
+    }
+
+    /// Abandons a path with `BranchDepthLimitReached` once it has forked more than `depth`
+    /// times. Other paths are still explored.
+    pub fn max_branch_depth<'a>(&'a mut self, depth: usize) -> &'a mut Self {
+        self.max_branch_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many functions a function pointer read from a symbolic address may
+    /// resolve to. Paths that exceed the limit end with `TooManyFunctionPointerTargets`.
+    pub fn fn_ptr_target_limit<'a>(&'a mut self, limit: usize) -> &'a mut Self {
+        self.fn_ptr_target_limit = Some(limit);
+        self
+    }
+
+    /// Ends every path with `ExecutionTimeLimitReached` once the exploration has run
+    /// for `limit_ms` milliseconds. Solver queries are cut short at the same deadline.
+    pub fn time_limit_ms<'a>(&'a mut self, limit_ms: u64) -> &'a mut Self {
+        self.time_limit_ms = Some(limit_ms);
+        self
+    }
+
+    /// Checks the time limit every `steps` steps. Defaults to 1000.
+    pub fn time_check_interval<'a>(&'a mut self, steps: u64) -> &'a mut Self {
+        assert!(steps > 0, "time check interval must be nonzero");
+        self.time_check_interval = Some(steps);
+        self
+    }
+
+    /// Abandons a path with `FrameStepLimitReached` once a single stack frame has executed
+    /// more than `steps` steps. Steps taken in callees count towards their own frames.
+    pub fn max_steps_per_frame<'a>(&'a mut self, steps: u64) -> &'a mut Self {
+        self.max_steps_per_frame = Some(steps);
+        self
+    }
+
+    /// Abandons a path with `LoopBoundReached` once a loop header has been revisited more than
+    /// `bound` times in a single stack frame. A revisit is a jump along a back edge, i.e. to a
+    /// block dominating the one jumped from.
+    pub fn loop_bound<'a>(&'a mut self, bound: usize) -> &'a mut Self {
+        self.loop_bound = Some(bound);
+        self
+    }
+
+    /// Only explores the lengths of at most `len` bytes for a C string whose nul terminator
+    /// is symbolic, e.g. in `strlen`. Longer lengths are dropped, and a string that may not
+    /// end within `len` bytes fails with `UnterminatedCString`. Defaults to no limit.
+    pub fn max_c_str_len<'a>(&'a mut self, len: u64) -> &'a mut Self {
+        self.max_c_str_len = Some(len);
+        self
+    }
+
+    /// Stops the exploration once `paths` paths have been passed to the consumer. Whether
+    /// paths were left unexplored is reported by `truncated`.
+    pub fn max_paths<'a>(&'a mut self, paths: usize) -> &'a mut Self {
+        self.max_paths = Some(paths);
+        self
+    }
+
+    /// Whether the last exploration stopped at `max_paths` while there were paths left, so
+    /// that the results and the coverage are incomplete.
+    pub fn truncated(&self) -> bool {
+        self.truncated.get()
+    }
+
+    /// Merges paths that reach the same block with the same call stack into a single path,
+    /// whose values are chosen by the path constraints. This explores fewer paths, but
+    /// makes the constraints harder to solve. Merging is off by default.
+    pub fn enable_state_merging<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.state_merging = enable;
+        self
+    }
+
+    /// When an operation is not supported on a symbolic operand, pins the operand to a
+    /// value the solver chooses and evaluates the operation concretely, instead of ending
+    /// the path with `StaticEvalError::UnsupportedSymbolicOperation`. Paths that need other
+    /// values are missed; `ExecutionComplete::concretizations` lists what was pinned.
+    pub fn concretize_on_unsupported<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.concretize_on_unsupported = enable;
+        self
+    }
+
+    /// Whether the program is compiled with debug assertions, which is the default. Without
+    /// them, `debug_assert!` is a no-op and arithmetic wraps on overflow instead of
+    /// panicking, as in release builds. Bounds and division checks are always explored.
+    pub fn debug_assertions<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.debug_assertions = enable;
+        self
+    }
+
+    pub(crate) fn debug_assertions_enabled(&self) -> bool {
+        self.debug_assertions
+    }
+
+    /// Ends a path with `NonProgressingRecursion` when a function calls itself, directly or
+    /// not, with the same arguments as an active call, and nothing was read from the input
+    /// and no branch was taken in between. Off by default, since a recursion that makes
+    /// progress only through memory it is not passed, e.g. a counter in a static, is
+    /// reported as well.
+    pub fn detect_nonprogressing_recursion<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.detect_nonprogressing_recursion = enable;
+        self
+    }
+
+    /// Evaluates statements that only copy or combine concrete integers held in locals
+    /// directly, instead of through the machinery for symbolic values. Any other
+    /// statement is evaluated as usual. On by default; the results are the same either way.
+    pub fn concrete_fast_path<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.concrete_fast_path = enable;
+        self
+    }
+
+    /// Also passes the paths that are dropped because no branch is feasible to the
+    /// consumer, e.g. after an `assume` that cannot hold. Their `result` is `Ok(())`
+    /// and their `outcome` is `ExecutionResult::Pruned`. Off by default.
+    pub fn report_pruned_paths<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.report_pruned = enable;
+        self
+    }
+
+    /// Records which basic blocks and edges the explored paths visit, so that
+    /// `coverage` can report them. Tracking is off by default.
+    pub fn track_coverage<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.coverage = if enable {
+            Some(Rc::new(RefCell::new(CoverageCollector::default())))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// The coverage of the paths explored so far, if `track_coverage` is enabled.
+    pub fn coverage(&self) -> Option<CoverageReport> {
+        self.coverage.as_ref().map(|c| c.borrow().report())
+    }
+
+    /// Records where the explored paths fork and how they end, so that `path_tree` can
+    /// report them. Recording is off by default.
+    pub fn track_path_tree<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.path_tree = if enable {
+            Some(Rc::new(RefCell::new(PathTree::default())))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// The tree of the paths explored so far, if `track_path_tree` is enabled.
+    pub fn path_tree(&self) -> Option<PathTree> {
+        self.path_tree.as_ref().map(|t| t.borrow().clone())
+    }
+
+    /// Counts the reads and writes of every allocation, which
+    /// `ExecutionComplete::leaks` then reports for the allocations that a completed
+    /// path never freed.
+    pub fn track_allocation_accesses<'a>(&'a mut self, enable: bool) -> &'a mut Self {
+        self.track_accesses = enable;
+        self
+    }
+
+    /// Starts execution at the function with the path `name`, e.g. `parser::parse`,
+    /// instead of `main`. Its arguments become symbolic inputs, which
+    /// `ExecutionComplete::input_model` reports.
+    pub fn entry_function<'a>(&'a mut self, name: &str) -> &'a mut Self {
+        self.entry_function = Some(name.to_string());
+        self
+    }
+
+    /// Skips calls to the functions with the given paths, e.g. `std::fmt::format`, instead
+    /// of stepping into them. A skipped call returns a fresh symbolic value of its return
+    /// type, which `ExecutionComplete::input_model` reports, and has no other effects.
+    pub fn opaque_fns<'a>(&'a mut self, paths: Vec<String>) -> &'a mut Self {
+        self.opaque_fns = Rc::new(paths);
+        self
+    }
+
+    /// Analyzes the program for a target with pointers of `bits` bits, which must be
+    /// 32 or 64, instead of the host. This changes the width of `usize` and `isize`, the
+    /// layout of fat pointers and vtables, and the bounds of pointer arithmetic. The
+    /// sysroot needs the standard library for the corresponding target.
+    pub fn target_pointer_width<'a>(&'a mut self, bits: u32) -> &'a mut Self {
+        assert!(bits == 32 || bits == 64, "unsupported pointer width: {}", bits);
+        self.target_pointer_width = Some(bits);
+        self
+    }
+
+    /// The target triple that implements the configured pointer width, if any. This is
+    /// the host triple with its architecture replaced, e.g. `i686-pc-windows-msvc` for a
+    /// 32-bit analysis on `x86_64-pc-windows-msvc`.
+    pub(crate) fn target_triple(&self) -> Option<String> {
+        self.target_pointer_width.map(|bits| {
+            let host = ::rustc::session::config::host_triple();
+            let rest = host.find('-').map_or("", |i| &host[i..]);
+            let arch = match bits {
+                32 => "i686",
+                _ => "x86_64",
+            };
+            format!("{}{}", arch, rest)
+        })
+    }
+
+    /// Evaluates the inline assembly with the template `asm`, e.g. `rdtsc`, by calling
+    /// `summary` with the values of its inputs instead of failing with
+    /// `EvalError::InlineAsm`. The summary returns the values of the outputs, in order.
+    pub fn asm_summary<'a, F>(&'a mut self, asm: &str, summary: F) -> &'a mut Self
+        where F: FnMut(&[PrimVal]) -> Vec<AsmOutput> + 'static
+    {
+        Rc::make_mut(&mut self.asm_summaries).insert(asm.to_string(), Rc::new(RefCell::new(summary)));
+        self
+    }
+
+    /// Only explores the path that produced `model`, as returned by
+    /// `ExecutionComplete::model` for a previous run of the same program. This checks
+    /// that a reported witness reproduces its outcome. See `EvalContext::replay`.
+    pub fn replay<'a>(&'a mut self, model: Model) -> &'a mut Self {
+        self.replay = Some(model);
+        self
+    }
+
     /// The consumer returns `true` if it wants the executor to continue.
     pub fn consumer<'a, F>(
         &'a mut self, consumer: F)
@@ -62,161 +403,731 @@ impl ExecutionConfig {
         self
     }
 
+    /// The panic handler is called with the details of every panic that is reached,
+    /// before the panic is passed to the consumer as `StaticEvalError::Panic`.
+    pub fn panic_handler<'a, F>(
+        &'a mut self, handler: F)
+        -> &'a mut Self
+        where F: FnMut(&PanicInfo) + 'static
+    {
+        self.panic_handler = Some(Rc::new(RefCell::new(handler)));
+        self
+    }
+
+    /// The branch trace is called with every branch of every fork, before the branches
+    /// are explored.
+    pub fn branch_trace<'a, F>(
+        &'a mut self, trace: F)
+        -> &'a mut Self
+        where F: FnMut(&BranchEvent) + 'static
+    {
+        self.branch_trace = Some(Rc::new(RefCell::new(trace)));
+        self
+    }
+
+    /// Restricts the bytes read from stdin. `constraints` is called with the bytes of
+    /// every read, each as a `U8`, and returns constraints that the path must satisfy.
+    /// Paths on which they cannot hold are dropped without being reported.
+    pub fn stdin_constraints<'a, F>(
+        &'a mut self, constraints: F)
+        -> &'a mut Self
+        where F: FnMut(&[PrimVal]) -> Vec<Constraint> + 'static
+    {
+        self.stdin_constraints = Some(Rc::new(RefCell::new(constraints)));
+        self
+    }
+
+    /// Sets the solver that decides the path constraints. Defaults to a
+    /// `CachingBackend` around an `IncrementalBackend`.
+    pub fn solver_backend<'a, B>(&'a mut self, backend: B) -> &'a mut Self
+        where B: SolverBackend + 'static
+    {
+        self.solver_backend = Rc::new(backend);
+        self
+    }
+
+    /// Sets the order in which the queued paths are explored. Defaults to `DepthFirst`.
+    pub fn path_strategy<'a, S>(&'a mut self, strategy: S) -> &'a mut Self
+        where S: PathStrategy + 'static
+    {
+        self.path_strategy = Some(Rc::new(RefCell::new(strategy)));
+        self
+    }
+
+    /// Creates the context that explores the function `def_id` with this configuration,
+    /// whose arguments become symbolic inputs. Every setting applies, including the
+    /// limits, which override those set by the `miri` attributes of the crate, and the
+    /// solver backend. This is the context that `run` and `analyze_fn` start from; see
+    /// `compile` for a way to get `tcx` and `def_id`. Fails if the function returns a
+    /// value, or takes an argument that cannot be symbolic.
+    pub fn build<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId)
+        -> EvalResult<'tcx, EvalContext<'a, 'tcx>>
+    {
+        let mut ecx = self.context(tcx);
+        push_entry_frame(&mut ecx, def_id)?;
+        Ok(ecx)
+    }
+
+    /// The configured context, before the entry function is called.
+    fn context<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>) -> EvalContext<'a, 'tcx> {
+        let limits = self.resource_limits(::driver::resource_limits_from_attributes(tcx));
+        let mut ecx = EvalContext::new(tcx, limits);
+        ecx.panic_handler = self.panic_handler.clone();
+        ecx.branch_trace = self.branch_trace.clone();
+        ecx.stdin_constraints = self.stdin_constraints.clone();
+        ecx.coverage = self.coverage.clone();
+        ecx.opaque_fns = self.opaque_fns.clone();
+        ecx.asm_summaries = self.asm_summaries.clone();
+        ecx.concretize_on_unsupported = self.concretize_on_unsupported;
+        ecx.debug_assertions = self.debug_assertions;
+        ecx.detect_nonprogressing_recursion = self.detect_nonprogressing_recursion;
+        ecx.concrete_fast_path = self.concrete_fast_path;
+        if self.track_accesses {
+            ecx.memory.track_accesses();
+        }
+        ecx.memory.constraints.set_backend(self.solver_backend.clone());
+        if let Some(ref model) = self.replay {
+            ecx.replay(model);
+        }
+        ecx
+    }
+
+    pub(crate) fn resource_limits(&self, mut limits: ResourceLimits) -> ResourceLimits {
+        if let Some(size) = self.memory_size {
+            limits.memory_size = size;
+        }
+        if let Some(frames) = self.stack_limit {
+            limits.stack_limit = frames;
+        }
+        if let Some(limit) = self.fn_ptr_target_limit {
+            limits.fn_ptr_target_limit = limit;
+        }
+        if let Some(limit_ms) = self.time_limit_ms {
+            limits.time_limit_ms = Some(limit_ms);
+        }
+        if let Some(steps) = self.time_check_interval {
+            limits.time_check_interval = steps;
+        }
+        if let Some(steps) = self.max_steps_per_frame {
+            limits.max_steps_per_frame = Some(steps);
+        }
+        if let Some(bound) = self.loop_bound {
+            limits.loop_bound = Some(bound);
+        }
+        if let Some(len) = self.max_c_str_len {
+            limits.max_c_str_len = Some(len);
+        }
+        limits
+    }
+
+    /// Explores the program, passing every completed path to the consumer until
+    /// it asks to stop.
     pub fn run(&self, args: Vec<String>) {
-        ::driver::main_helper(args, self.clone());
+        let consumer = self.consumer.clone();
+        self.explore(args, move |paths| {
+            for complete in paths {
+                let go_on = match consumer {
+                    Some(ref f) => (&mut *f.borrow_mut())(complete),
+                    None => true,
+                };
+                if !go_on {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Like `run`, but hands the completed paths to `explorer` as a lazy iterator
+    /// instead of using the consumer. Paths are explored on demand, so the exploration
+    /// ends as soon as `explorer` stops pulling from the iterator.
+    pub fn explore<F>(&self, args: Vec<String>, mut explorer: F)
+        where F: FnMut(&mut Iterator<Item = ExecutionComplete>) + 'static
+    {
+        let config = self.clone();
+        self.compile(args, move |tcx, def_id| {
+            let executor = Executor::new(tcx, def_id, config.clone());
+            explorer(&mut executor.explore());
+        });
+    }
+
+    /// Explores the program like `run` and returns the paths that panicked or failed with
+    /// an error, each with the inputs that lead there. Set `entry_function` to explore a
+    /// single function with symbolic arguments. See `analyze_fn`.
+    pub fn analyze(&self, args: Vec<String>) -> Vec<ExecutionResult> {
+        let failures = Rc::new(RefCell::new(Vec::new()));
+        let failures1 = failures.clone();
+        let config = self.clone();
+        self.compile(args, move |tcx, def_id| {
+            *failures1.borrow_mut() = analyze_fn(tcx, def_id, &config);
+        });
+        let failures = ::std::mem::replace(&mut *failures.borrow_mut(), Vec::new());
+        failures
+    }
+
+    /// Compiles the crate and calls `entry` with the function to explore, which is
+    /// `entry_function` or else `main`. This is for tools that build their own context
+    /// with `build`, or call `analyze_fn`.
+    pub fn compile<F>(&self, args: Vec<String>, entry: F)
+        where F: for<'b, 'tcx> FnMut(TyCtxt<'b, 'tcx, 'tcx>, DefId) + 'static
+    {
+        self.truncated.set(false);
+        ::driver::main_helper(args, self.clone(), Rc::new(RefCell::new(entry)));
+    }
+}
+
+/// Explores every path of the function `def_id`, whose arguments become symbolic inputs, and
+/// returns the paths that panicked or failed with an error, each with the inputs that lead
+/// there. The function must return `()`. The limits of `config` apply on top of those set by
+/// the `miri` attributes of the crate, as in `ExecutionConfig::build`.
+///
+/// This is for tools that already drive rustc; `ExecutionConfig::analyze` compiles the
+/// crate as well.
+pub fn analyze_fn<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    def_id: DefId,
+    config: &ExecutionConfig,
+) -> Vec<ExecutionResult> {
+    config.truncated.set(false);
+    let executor = Executor::new(tcx, def_id, config.clone());
+    failed_paths(&mut executor.explore())
+}
+
+fn failed_paths(paths: &mut Iterator<Item = ExecutionComplete>) -> Vec<ExecutionResult> {
+    paths.map(|complete| complete.outcome()).filter(|outcome| match *outcome {
+        ExecutionResult::Panicked { .. } | ExecutionResult::Errored { .. } => true,
+        ExecutionResult::Completed(_) | ExecutionResult::Pruned => false,
+    }).collect()
+}
+
+/// The inputs that drive execution down a path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InputModel {
+    /// The bytes read from stdin.
+    pub stdin: Vec<u8>,
+
+    /// The typed symbolic inputs, as `ExecutionComplete::input_model` reports them.
+    pub inputs: Vec<(String, ConcreteValue)>,
+
+    /// The Rust type of each of `inputs`, e.g. `(u8, bool)`.
+    pub types: Vec<String>,
+}
+
+impl InputModel {
+    /// Formats the model as Rust statements that bind each input to its value, e.g.
+    /// `let x: u32 = 4294967295;`, so that it can be pasted into a reproducer. The bytes
+    /// read from stdin, if any, are bound to `stdin`.
+    pub fn to_rust_source(&self) -> String {
+        let mut source = String::new();
+        if !self.stdin.is_empty() {
+            let bytes: Vec<String> = self.stdin.iter().map(|b| b.to_string()).collect();
+            source.push_str(&format!(
+                "let stdin: [u8; {}] = [{}];\n", self.stdin.len(), bytes.join(", ")));
+        }
+        for (&(ref name, ref value), ty) in self.inputs.iter().zip(&self.types) {
+            source.push_str(&format!("let {}: {} = {};\n", name, ty, value));
+        }
+        source
     }
 }
 
+/// The cost of exploring a path, as returned by `ExecutionComplete::stats`. A path shares the
+/// costs of the prefix it has in common with the paths it was forked from.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PathStats {
+    /// The MIR statements and terminators evaluated.
+    pub steps: u64,
+    /// The forks that the path went through.
+    pub forks: usize,
+    /// The queries sent to the solver backend.
+    pub solver_queries: u64,
+    /// The wall-clock time spent stepping the path.
+    pub elapsed: Duration,
+}
+
+/// How a path ended, as returned by `ExecutionComplete::outcome`.
+#[derive(Clone, Debug)]
+pub enum ExecutionResult {
+    Completed(InputModel),
+
+    /// The program panicked. `info` holds the message and location of the panic, and is
+    /// recorded whether or not a panic handler is set.
+    Panicked { info: Option<PanicInfo>, model: InputModel },
+
+    Errored { err: StaticEvalError, model: InputModel },
+
+    /// No branch of the path was feasible. Only reported if `report_pruned_paths`
+    /// is enabled.
+    Pruned,
+}
+
 #[derive(Debug)]
 pub struct ExecutionComplete {
     pub input: Vec<u8>,
+    /// How the path ended. Pruned paths, which are only reported if `report_pruned_paths`
+    /// is enabled, also come back as `Ok(())`; use `outcome` to tell them apart.
     pub result: Result<(), StaticEvalError>,
+    input_model: Vec<(String, ConcreteValue)>,
+    input_types: Vec<String>,
+    concretizations: Vec<Concretization>,
+    panic_info: Option<PanicInfo>,
+    pruned: bool,
+    leaks: Vec<AllocationReport>,
+    location: Option<(String, u32, u32)>,
+    freed_at: Option<(String, u32, u32)>,
+    model: Option<Model>,
+    stats: PathStats,
+}
+
+impl ExecutionComplete {
+    fn new(ecx: &EvalContext, result: Result<(), StaticEvalError>) -> Self {
+        // Before the queries for the model below.
+        let stats = PathStats {
+            steps: ecx.executed_steps,
+            forks: ecx.branch_depth,
+            solver_queries: ecx.memory.constraints.solver_queries(),
+            elapsed: ecx.elapsed,
+        };
+        ExecutionComplete {
+            input: ecx.memory.constraints.get_satisfying_values(),
+            result,
+            input_model: ecx.memory.constraints.get_input_model(),
+            input_types: ecx.memory.constraints.get_input_types(),
+            concretizations: ecx.concretizations.clone(),
+            panic_info: ecx.panic_info.clone(),
+            pruned: false,
+            leaks: Vec::new(),
+            location: None,
+            freed_at: None,
+            model: ecx.memory.constraints.get_model(),
+            stats,
+        }
+    }
+
+    fn pruned(ecx: &EvalContext) -> Self {
+        ExecutionComplete {
+            pruned: true,
+            ..ExecutionComplete::new(ecx, Ok(()))
+        }
+    }
+
+    /// Classifies how the path ended.
+    pub fn outcome(&self) -> ExecutionResult {
+        if self.pruned {
+            return ExecutionResult::Pruned;
+        }
+        let model = InputModel {
+            stdin: self.input.clone(),
+            inputs: self.input_model.clone(),
+            types: self.input_types.clone(),
+        };
+        match self.result {
+            Ok(()) => ExecutionResult::Completed(model),
+            Err(StaticEvalError::Panic) => ExecutionResult::Panicked {
+                info: self.panic_info.clone(),
+                model,
+            },
+            Err(ref err) => ExecutionResult::Errored { err: err.clone(), model },
+        }
+    }
+
+    /// The typed symbolic inputs of the path, such as the arguments of the entry
+    /// function, with values that drive execution down the path.
+    pub fn input_model(&self) -> &[(String, ConcreteValue)] {
+        &self.input_model
+    }
+
+    /// The assignment of all symbolic variables that produced this path, to pass to
+    /// `ExecutionConfig::replay`. `None` if the solver gave up.
+    pub fn model(&self) -> Option<&Model> {
+        self.model.as_ref()
+    }
+
+    /// The file, line and column of the error that ended the path, if any. This is the
+    /// span that the error records, or else the statement or terminator that failed.
+    /// Panics are located at the call that started them.
+    ///
+    /// This lives here rather than on `StaticEvalError` because most errors are raised
+    /// without a span, deep in the memory model, and only the executor knows which
+    /// statement of the path was running. Resolving the span also needs the codemap of
+    /// the compiled crate, which `StaticEvalError::from` does not have.
+    pub fn location(&self) -> Option<&(String, u32, u32)> {
+        self.location.as_ref()
+    }
+
+    /// The file, line and column where the program freed the allocation, if the path
+    /// ended with `StaticEvalError::UseAfterFree`.
+    pub fn freed_at(&self) -> Option<&(String, u32, u32)> {
+        self.freed_at.as_ref()
+    }
+
+    /// The allocations that the program did not free, if the path completed without
+    /// errors. Their access counts are only recorded with `track_allocation_accesses`.
+    pub fn leaks(&self) -> &[AllocationReport] {
+        &self.leaks
+    }
+
+    /// How much work the exploration of the path took.
+    pub fn stats(&self) -> &PathStats {
+        &self.stats
+    }
+
+    /// The symbolic values that were pinned on the path, if `concretize_on_unsupported`
+    /// is enabled. The path is only one of those that the program could take.
+    pub fn concretizations(&self) -> &[Concretization] {
+        &self.concretizations
+    }
 }
 
 impl <'a, 'tcx: 'a> Executor<'a, 'tcx> {
     pub fn new(
         tcx: TyCtxt<'a, 'tcx, 'tcx>,
         def_id: DefId,
-        limits: ResourceLimits,
         config: ExecutionConfig,
     )
         -> Self
     {
-
+        let mut ecx = config.context(tcx);
+        let entry = push_entry_frame(&mut ecx, def_id);
         let mut result = Executor {
             tcx: tcx,
             queue: VecDeque::new(),
+            completed: VecDeque::new(),
+            reported: 0,
             config: config,
         };
 
-        let mut ecx = EvalContext::new(tcx, limits);
-        let instance = ty::Instance::mono(tcx, def_id);
-        let mir = ecx.load_mir(instance.def).expect("main function's MIR not found");
-
-        if !mir.return_ty().is_nil() || mir.arg_count > 0 {
-            let msg = "seer does not support main functions without `fn()` type signatures";
-            tcx.sess.err(&EvalError::Unimplemented(String::from(msg)).to_string());
-            unimplemented!()
+        if let Some(ref tree) = result.config.path_tree {
+            ecx.path_node = tree.borrow_mut().add_node(None, Vec::new());
         }
 
-        ecx.push_stack_frame(
-            instance,
-            DUMMY_SP,
-            &mir,
-            Lvalue::undef(),
-            StackPopCleanup::None,
-        ).expect("could not allocate first stack frame");
-
-        result.push_eval_context(ecx);
+        match entry {
+            Ok(()) => result.push_eval_context(ecx),
+            // The entry function cannot be explored, e.g. because one of its arguments
+            // has a type that cannot be symbolic. That is the only path.
+            Err(e) => result.report_error(&ecx, e),
+        }
 
         result
     }
 
     pub fn push_eval_context(&mut self, ecx: EvalContext<'a, 'tcx>) {
-        // Push onto the front so that we go depth-first.
+        // Push onto the front so that we go depth-first by default.
         // Pushing onto the back consumes more memory and may be less
         // cache-friendly.
         self.queue.push_front(ecx);
     }
 
     fn pop_eval_context(&mut self) -> Option<EvalContext<'a, 'tcx>> {
-        self.queue.pop_front()
+        let idx = match self.config.path_strategy {
+            Some(ref strategy) if !self.queue.is_empty() => {
+                let idx = strategy.borrow_mut().pick(&Frontier { queue: &self.queue });
+                assert!(idx < self.queue.len(), "the path strategy picked a path outside the frontier");
+                idx
+            }
+            _ => 0,
+        };
+        self.queue.remove(idx)
     }
 
-    // return true if we should continue with other executions
-    fn report_error(&mut self, ecx: &EvalContext, e: EvalError) -> bool {
+    /// Merges `ecx`, which is at a join point, into a queued path at the same location. If
+    /// there is none, `ecx` waits at the back of the queue, so that the paths in front of it
+    /// get a chance to catch up.
+    fn merge_or_park(&mut self, ecx: EvalContext<'a, 'tcx>) {
+        for idx in 0..self.queue.len() {
+            let merged = self.queue[idx].merged(&ecx);
+            if let Some(merged) = merged {
+                self.queue[idx] = merged;
+                return;
+            }
+        }
+        self.queue.push_back(ecx);
+    }
+
+    fn report_error(&mut self, ecx: &EvalContext, e: EvalError) {
+        let node_location = self.tree_location(ecx);
+        self.report_error_at(ecx, e, current_span(ecx), node_location);
+    }
+
+    /// Like `report_error`, for an error raised at `span`, which `node_location` describes
+    /// in the path tree, rather than at the statement that `ecx` is at.
+    fn report_error_at(
+        &mut self,
+        ecx: &EvalContext,
+        e: EvalError,
+        span: Option<Span>,
+        node_location: Option<String>,
+    ) {
         if self.config.emit_error {
-            report(self.tcx, &ecx, e.clone());
+            report(self.tcx, span, &ecx, e.clone());
+        }
+
+        let outcome = match e {
+            EvalError::Panic => PathOutcome::Panicked,
+            _ => PathOutcome::Errored,
+        };
+        if let Some(ref tree) = self.config.path_tree {
+            tree.borrow_mut().finish(ecx.path_node, node_location, outcome);
         }
 
-        match self.config.consumer {
-            Some(ref f) => {
-                (&mut *f.borrow_mut())(ExecutionComplete {
-                    input: ecx.memory.constraints.get_satisfying_values(),
-                    result: Err(e.into())
-                })
+        let span = e.span().or(span);
+        let freed_at = match e {
+            EvalError::UseAfterFree { freed_at } => Some(source_location(self.tcx, freed_at)),
+            _ => None,
+        };
+        let result = Err(static_error(ecx, e));
+        let mut complete = ExecutionComplete::new(ecx, result);
+        complete.location = match ecx.panic_info {
+            Some(PanicInfo { location: Some(ref location), .. }) => Some(location.clone()),
+            _ => span.map(|span| source_location(self.tcx, span)),
+        };
+        complete.freed_at = freed_at;
+        self.completed.push_back(complete);
+    }
+
+    /// Reports the accesses of the last step of `ecx` that fail for some of the values
+    /// they depend on, e.g. those out of bounds for some of the lengths of a
+    /// symbolic-length allocation, each on a copy of `ecx` constrained to those values,
+    /// and constrains `ecx` itself to the values where they succeed. The step ran at
+    /// `span`, which `node_location` describes in the path tree.
+    fn fork_deferred_failures(
+        &mut self,
+        ecx: &mut EvalContext<'a, 'tcx>,
+        span: Option<Span>,
+        node_location: Option<String>,
+    ) {
+        for (fails, holds, e) in ecx.memory.take_deferred_failures() {
+            // The constraints of an earlier failure of the same step may rule this one out.
+            if !ecx.memory.constraints.is_feasible_with(&[fails]) {
+                continue;
             }
-            None => true,
+            let mut failed = ecx.clone();
+            failed.memory.constraints.push_constraint(fails);
+            ecx.memory.constraints.push_constraint(holds);
+            if let Some(ref tree) = self.config.path_tree {
+                let mut tree = tree.borrow_mut();
+                let parent = ecx.path_node;
+                tree.set_location(parent, node_location.clone());
+                failed.path_node = tree.add_node(Some(parent), vec![format!("{:?}", fails)]);
+                ecx.path_node = tree.add_node(Some(parent), vec![format!("{:?}", holds)]);
+            }
+            failed.branch_depth += 1;
+            ecx.branch_depth += 1;
+            self.report_error_at(&failed, e, span, node_location.clone());
+        }
+    }
+
+    /// The location of `ecx` to record in the path tree, if there is one.
+    fn tree_location(&self, ecx: &EvalContext) -> Option<String> {
+        match self.config.path_tree {
+            Some(_) => mir_location(ecx),
+            None => None,
         }
     }
 
-    pub fn run(&mut self) {
-        'main_loop: while let Some(mut ecx) = self.pop_eval_context() {
-            match ecx.step() {
+    /// Records in the path tree, if any, that the path of `ecx` ended with `outcome`.
+    fn finish_path_node(&self, ecx: &EvalContext, outcome: PathOutcome) {
+        if let Some(ref tree) = self.config.path_tree {
+            tree.borrow_mut().finish(ecx.path_node, mir_location(ecx), outcome);
+        }
+    }
+
+    /// Returns an iterator over the completed paths, which explores the program lazily.
+    pub fn explore(self) -> Explore<'a, 'tcx> {
+        Explore { executor: self }
+    }
+
+    /// Steps the next queued path until it forks or completes. Returns `false` once
+    /// there are no paths left to explore.
+    fn step_path(&mut self) -> bool {
+        let mut ecx = match self.pop_eval_context() {
+            Some(ecx) => ecx,
+            None => return false,
+        };
+        let start = Instant::now();
+        // Keep stepping the same context for as long as it does not fork.
+        // Straight-line code then runs without a round trip through the
+        // queue on every statement.
+        let step_result = loop {
+            // Where the step runs, for the failures that it defers.
+            let span = current_span(&ecx);
+            let node_location = self.tree_location(&ecx);
+            let result = ecx.step();
+            self.fork_deferred_failures(&mut ecx, span, node_location);
+            if let Some(elapsed_ms) = ecx.memory.constraints.take_solver_timeout() {
+                // The solver could not decide which branches are feasible,
+                // so abandon this path and move on to the next one.
+                if ecx.time_limit_exceeded() {
+                    // The query was cut short by the deadline of the whole exploration.
+                    break Err(EvalError::ExecutionTimeLimitReached);
+                }
+                break Err(EvalError::SolverTimeout { elapsed_ms });
+            }
+            match result {
                 Ok((true, None)) => {
-                    self.push_eval_context(ecx)
+                    if self.config.state_merging && !self.queue.is_empty() && ecx.at_join_point() {
+                        ecx.elapsed += start.elapsed();
+                        self.merge_or_park(ecx);
+                        return true;
+                    }
                 }
-                Ok((true, Some(branches))) => {
-                    if branches.is_empty() {
-                        // no feasible branch. should throw error
-                        unimplemented!()
-                    } else {
-                        let iter = ::std::iter::repeat(ecx).zip(branches.into_iter());
-                        for (mut cx, finish_step) in iter {
-                            let FinishStep {constraints, variant} = finish_step;
-                            let mut no_errors: bool = true;
-                            for constraint in constraints {
-                                cx.memory.constraints.push_constraint(constraint);
-                                match variant {
-                                    FinishStepVariant::Continue { goto_block, set_lvalue} => {
-                                        if let Some((lvalue, prim, ty)) = set_lvalue {
-                                            if let Err(_) = cx.write_primval(lvalue, prim, ty) {
-                                                unimplemented!()
-                                            }
-                                        }
-                                        cx.goto_block(goto_block);
+                other => break other,
+            }
+        };
+        ecx.elapsed += start.elapsed();
+        match step_result {
+            Ok((true, None)) => unreachable!(),
+            Ok((true, Some(branches))) => {
+                if branches.is_empty() {
+                    // No feasible branch, e.g. because of an `assume` that cannot
+                    // hold. The path is dropped without being reported.
+                    self.finish_path_node(&ecx, PathOutcome::Pruned);
+                    if self.config.report_pruned {
+                        self.completed.push_back(ExecutionComplete::pruned(&ecx));
+                    }
+                } else {
+                    let forked = branches.len() > 1;
+                    let tree = if forked { self.config.path_tree.clone() } else { None };
+                    let parent = ecx.path_node;
+                    if let Some(ref tree) = tree {
+                        tree.borrow_mut().set_location(parent, mir_location(&ecx));
+                    }
+                    let iter = ::std::iter::repeat(ecx).zip(branches.into_iter());
+                    for (mut cx, finish_step) in iter {
+                        let FinishStep {constraints, variant} = finish_step;
+                        let mut no_errors: bool = true;
+                        if let Some(ref tree) = tree {
+                            let labels = constraints.iter().map(|c| format!("{:?}", c)).collect();
+                            cx.path_node = tree.borrow_mut().add_node(Some(parent), labels);
+                        }
+                        for constraint in constraints {
+                            cx.memory.constraints.push_constraint(constraint);
+                        }
+                        if forked {
+                            cx.branch_depth += 1;
+                        }
+                        let variant = match (variant, self.config.max_branch_depth) {
+                            (FinishStepVariant::Error(e), _) => FinishStepVariant::Error(e),
+                            (_, Some(max)) if cx.branch_depth > max =>
+                                FinishStepVariant::Error(EvalError::BranchDepthLimitReached),
+                            (variant, _) => variant,
+                        };
+                        match variant {
+                            FinishStepVariant::Continue { goto_block, set_lvalue} => {
+                                if let Some((lvalue, prim, ty)) = set_lvalue {
+                                    if let Err(_) = cx.write_primval(lvalue, prim, ty) {
+                                        unimplemented!()
                                     }
-                                    FinishStepVariant::Error(ref e) => {
-                                        if !self.report_error(&cx, e.clone()) {
-                                            break 'main_loop;
-                                        }
-                                        no_errors = false;
+                                }
+                                cx.goto_block(goto_block);
+                            }
+                            FinishStepVariant::NextStatement { set_lvalue } => {
+                                if let Some((lvalue, prim, ty)) = set_lvalue {
+                                    if let Err(_) = cx.write_primval(lvalue, prim, ty) {
+                                        unimplemented!()
                                     }
                                 }
                             }
-                            if no_errors {
-                                // only continue along branches without errors
-                                self.push_eval_context(cx);
+                            FinishStepVariant::Error(ref e) => {
+                                self.report_error(&cx, e.clone());
+                                no_errors = false;
                             }
                         }
-                    }
-                }
-                Ok((false, _)) => {
-                    let go_on = match self.config.consumer {
-                        Some(ref f) => {
-                            (&mut *f.borrow_mut())(ExecutionComplete {
-                                input: ecx.memory.constraints.get_satisfying_values(),
-                                result: Ok(())
-                            })
+                        if no_errors {
+                            // only continue along branches without errors
+                            self.push_eval_context(cx);
                         }
-                        None => true,
-                    };
-                    let leaks = ecx.memory.leak_report();
-                    if leaks != 0 {
-                        self.tcx.sess.err("the evaluated program leaked memory");
-                    }
-                    if !go_on {
-                        break 'main_loop;
                     }
                 }
-                Err(e) => {
-                    if !self.report_error(&ecx, e) {
-                        break;
-                    }
+            }
+            Ok((false, _)) => {
+                self.finish_path_node(&ecx, PathOutcome::Completed);
+                let mut complete = ExecutionComplete::new(&ecx, Ok(()));
+                complete.leaks = ecx.leaked_allocations();
+                self.completed.push_back(complete);
+                let leaks = ecx.memory.leak_report();
+                if leaks != 0 {
+                    self.tcx.sess.err("the evaluated program leaked memory");
                 }
             }
+            Err(e) => self.report_error(&ecx, e),
+        }
+        true
+    }
+}
+
+/// Converts `e` into a `StaticEvalError`, using the path constraints of `ecx`
+/// to concretize any symbolic values that the static error records.
+fn static_error(ecx: &EvalContext, e: EvalError) -> StaticEvalError {
+    let mut result: StaticEvalError = e.into();
+    if let StaticEvalError::PointerOutOfBounds { ptr, ref mut offset_witness, .. } = result {
+        if offset_witness.is_none() {
+            *offset_witness = ecx.memory.constraints.get_witness(
+                ptr.offset.as_primval(), PrimValKind::U64).map(|v| v as u64);
         }
     }
+    result
 }
 
+/// Calls the function `def_id` in `ecx`, with fresh symbolic inputs as its arguments.
+fn push_entry_frame<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx>, def_id: DefId) -> EvalResult<'tcx, ()> {
+    let instance = ty::Instance::mono(ecx.tcx, def_id);
+    let mir = ecx.load_mir(instance.def)?;
+
+    if !mir.return_ty().is_nil() {
+        let msg = "seer does not support entry functions that return a value";
+        return Err(EvalError::Unimplemented(String::from(msg)));
+    }
+
+    ecx.push_stack_frame(
+        instance,
+        DUMMY_SP,
+        &mir,
+        Lvalue::undef(),
+        StackPopCleanup::None,
+    )?;
+
+    // The arguments of the entry function are symbolic inputs.
+    for arg in mir.args_iter() {
+        let decl = &mir.local_decls[arg];
+        let name = match decl.name {
+            Some(name) => name.to_string(),
+            None => format!("arg{}", arg.index()),
+        };
+        let value = ecx.fresh_input(name, decl.ty)?;
+        ecx.stack[0].set_local(arg, value);
+    }
+    Ok(())
+}
 
-fn report(tcx: TyCtxt, ecx: &EvalContext, e: EvalError) {
-    let frame = ecx.stack().last().expect("stackframe was empty");
+/// The span of the statement or terminator that the top frame of `ecx` evaluates.
+fn current_span(ecx: &EvalContext) -> Option<Span> {
+    let frame = ecx.stack().last()?;
     let block = &frame.mir.basic_blocks()[frame.block];
-    let span = if frame.stmt < block.statements.len() {
+    Some(if frame.stmt < block.statements.len() {
         block.statements[frame.stmt].source_info.span
     } else {
         block.terminator().source_info.span
+    })
+}
+
+/// The statement or terminator that the top frame of `ecx` evaluates, e.g. `main bb1[0]`.
+fn mir_location(ecx: &EvalContext) -> Option<String> {
+    let frame = ecx.stack().last()?;
+    Some(format!("{} {:?}[{}]", frame.instance, frame.block, frame.stmt))
+}
+
+/// Resolves `span` to the file, line and column where it starts. Lines and columns
+/// count from 1, as in panic locations.
+fn source_location(tcx: TyCtxt, span: Span) -> (String, u32, u32) {
+    let loc = tcx.sess.codemap().lookup_char_pos(span.lo());
+    (loc.file.name.to_string(), loc.line as u32, loc.col.0 as u32 + 1)
+}
+
+fn report(tcx: TyCtxt, span: Option<Span>, ecx: &EvalContext, e: EvalError) {
+    let span = match span {
+        Some(span) => span,
+        // The entry function could not be called.
+        None => return tcx.sess.err(&e.to_string()),
     };
     let mut err = tcx.sess.struct_span_err(span, &e.to_string());
     for &Frame { instance, span, .. } in ecx.stack().iter().rev() {
@@ -0,0 +1,131 @@
+//! Typed symbolic inputs, and their reconstruction from a satisfying assignment.
+
+use std::fmt;
+
+use memory::SByte;
+use solver::Model;
+use value::{PrimVal, PrimValKind};
+
+/// How the bytes of a symbolic input make up a value of its type.
+#[derive(Clone, Debug)]
+pub(crate) enum InputShape {
+    Bool,
+    Char,
+    Int { size: u64, signed: bool },
+
+    /// The fields of a tuple, at their byte offsets.
+    Tuple(Vec<(u64, InputShape)>),
+
+    Array { elem: Box<InputShape>, stride: u64, len: u64 },
+}
+
+/// A symbolic input of the evaluated program, recorded where it was created.
+#[derive(Clone, Debug)]
+pub(crate) struct SymbolicInput {
+    pub(crate) name: String,
+
+    /// The Rust type of the input, as printed by rustc.
+    pub(crate) ty: String,
+
+    pub(crate) shape: InputShape,
+
+    /// The bytes of the input, as laid out in memory. Padding is left concrete.
+    pub(crate) bytes: Vec<SByte>,
+}
+
+impl SymbolicInput {
+    pub(crate) fn reconstruct(&self, model: &Model) -> ConcreteValue {
+        self.shape.reconstruct(&self.bytes, model)
+    }
+}
+
+/// The value of a symbolic input on some path, as returned by
+/// `ExecutionComplete::input_model`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConcreteValue {
+    Bool(bool),
+    Char(char),
+    Int(i128),
+    Uint(u128),
+    Tuple(Vec<ConcreteValue>),
+    Array(Vec<ConcreteValue>),
+}
+
+impl InputShape {
+    fn reconstruct(&self, bytes: &[SByte], model: &Model) -> ConcreteValue {
+        match *self {
+            InputShape::Bool => ConcreteValue::Bool(read_uint(&bytes[..1], model) != 0),
+            InputShape::Char => {
+                // Char inputs are constrained to be valid, so this only falls back
+                // if the variables are unassigned.
+                let c = read_uint(&bytes[..4], model) as u32;
+                ConcreteValue::Char(::std::char::from_u32(c).unwrap_or('\0'))
+            }
+            InputShape::Int { size, signed } => {
+                let n = read_uint(&bytes[..size as usize], model);
+                if signed {
+                    let shift = 128 - 8 * size;
+                    ConcreteValue::Int(((n << shift) as i128) >> shift)
+                } else {
+                    ConcreteValue::Uint(n)
+                }
+            }
+            InputShape::Tuple(ref fields) => {
+                ConcreteValue::Tuple(fields.iter().map(|&(offset, ref field)| {
+                    field.reconstruct(&bytes[offset as usize..], model)
+                }).collect())
+            }
+            InputShape::Array { ref elem, stride, len } => {
+                ConcreteValue::Array((0..len).map(|idx| {
+                    elem.reconstruct(&bytes[(idx * stride) as usize..], model)
+                }).collect())
+            }
+        }
+    }
+}
+
+/// Evaluates the little-endian integer `bytes` under `model`.
+fn read_uint(bytes: &[SByte], model: &Model) -> u128 {
+    let mut result = 0;
+    for (idx, &sbyte) in bytes.iter().enumerate() {
+        let mut sbytes = [SByte::Concrete(0); 8];
+        sbytes[0] = sbyte;
+        let byte = model.eval(PrimVal::Abstract(sbytes), PrimValKind::U8).unwrap_or(0);
+        result |= byte << (8 * idx);
+    }
+    result
+}
+
+impl fmt::Display for ConcreteValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConcreteValue::Bool(b) => write!(f, "{}", b),
+            ConcreteValue::Char(c) => write!(f, "{:?}", c),
+            ConcreteValue::Int(n) => write!(f, "{}", n),
+            ConcreteValue::Uint(n) => write!(f, "{}", n),
+            ConcreteValue::Tuple(ref fields) => {
+                write!(f, "(")?;
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", field)?;
+                }
+                if fields.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            ConcreteValue::Array(ref elems) => {
+                write!(f, "[")?;
+                for (idx, elem) in elems.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", elem)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
@@ -20,22 +20,48 @@ extern crate syntax;
 
 // From crates.io.
 extern crate seer_z3 as z3;
+extern crate z3_sys;
 extern crate byteorder;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
 
 mod cast;
 mod constraints;
+mod coverage;
 mod error;
 mod eval_context;
 mod executor;
+mod input;
 mod lvalue;
 mod memory;
 mod operator;
+mod path_tree;
+#[cfg(feature = "serialize")]
+mod serialize;
+mod solver;
 mod step;
+mod strategy;
 mod terminator;
 mod traits;
 mod value;
 mod driver;
 
+pub use constraints::{
+    Constraint,
+    ConstraintContext,
+    NumericIntrinsic,
+    VarOrigin,
+    VarType,
+};
+
+pub use coverage::{
+    CoverageReport,
+    FunctionCoverage,
+};
+
 pub use error::{
     EvalError,
     EvalResult,
@@ -43,26 +69,66 @@ pub use error::{
 };
 
 pub use eval_context::{
+    AsmOutput,
+    BranchEvent,
+    BranchKind,
+    Concretization,
     EvalContext,
     Frame,
+    PanicInfo,
     ResourceLimits,
     StackPopCleanup,
 };
 
 pub use executor::{
+    analyze_fn,
     ExecutionComplete,
     ExecutionConfig,
+    ExecutionResult,
+    InputModel,
+    PathStats,
 };
 
+pub use input::ConcreteValue;
+
 pub use lvalue::{
     Lvalue,
     LvalueExtra,
 };
 
 pub use memory::{
+    AccessStats,
     AllocId,
+    AllocKind,
+    AllocationReport,
     Memory,
     MemoryPointer,
+    MemorySnapshot,
+    SByte,
+};
+
+pub use path_tree::{
+    PathNode,
+    PathOutcome,
+    PathTree,
+};
+
+pub use solver::{
+    CachingBackend,
+    ConstraintSet,
+    IncrementalBackend,
+    Model,
+    SatResult,
+    SolverBackend,
+    Z3Backend,
+};
+
+pub use strategy::{
+    BreadthFirst,
+    DepthFirst,
+    Frontier,
+    PathStrategy,
+    RandomPath,
 };
 
 pub use value::{
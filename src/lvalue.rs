@@ -3,9 +3,10 @@ use rustc::ty::layout::{HasDataLayout, TyLayout};
 use rustc::ty::{self, Ty};
 use rustc_data_structures::indexed_vec::Idx;
 
+use constraints::Constraint;
 use error::{EvalError, EvalResult};
 use eval_context::{EvalContext};
-use memory::{MemoryPointer};
+use memory::{MemoryPointer, PointerOffset};
 use value::{PrimVal, PrimValKind, Value};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -105,6 +106,17 @@ impl<'tcx> Lvalue<'tcx> {
             _ => bug!("elem_ty_and_len expected array or slice, got {:?}", ty),
         }
     }
+
+    /// Like `elem_ty_and_len`, but the length of a slice may be symbolic.
+    pub(super) fn elem_ty_and_len_primval(self, ty: Ty<'tcx>) -> (Ty<'tcx>, PrimVal) {
+        match (&ty.sty, self) {
+            (&ty::TySlice(elem), Lvalue::Ptr { extra: LvalueExtra::Length(len), .. }) => (elem, len),
+            _ => {
+                let (elem, len) = self.elem_ty_and_len(ty);
+                (elem, PrimVal::from_u128(len as u128))
+            }
+        }
+    }
 }
 
 impl<'tcx> Global<'tcx> {
@@ -124,8 +136,17 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub fn try_read_lvalue(&mut self, lvalue: &mir::Place<'tcx>) -> EvalResult<'tcx, Option<Value>> {
         use rustc::mir::Place::*;
         match *lvalue {
-            // Might allow this in the future, right now there's no way to do this from Rust code anyway
-            Local(mir::RETURN_PLACE) => Err(EvalError::ReadFromReturnPointer),
+            // The return value may be read back once it has been written to the caller's
+            // destination. Only a read before any write is an error.
+            Local(mir::RETURN_PLACE) => match self.frame().return_lvalue {
+                Lvalue::Ptr { ptr: PrimVal::Undef, .. } => Err(EvalError::ReadFromReturnPointer),
+                Lvalue::Local { frame, local } => match self.stack[frame].get_local(local)? {
+                    Value::ByVal(PrimVal::Undef) => Err(EvalError::ReadFromReturnPointer),
+                    value => Ok(Some(value)),
+                },
+                // Read through memory, which catches undefined bytes.
+                Lvalue::Ptr { .. } | Lvalue::Global(_) => Ok(None),
+            },
             // Directly reading a local will always succeed
             Local(local) => self.frame().get_local(local).map(Some),
             // Directly reading a static will always succeed
@@ -281,7 +302,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             _ => offset.bytes(),
         };
 
-        let ptr = base_ptr.to_ptr()?.offset(offset, (&self).data_layout())?;
+        let ptr = self.offset_lvalue_ptr(base_ptr.to_ptr()?, offset)?;
         // if we were unaligned, stay unaligned
         // no matter what we were, if we are packed, we must not be aligned anymore
         //ptr.aligned &= !base_layout.is_packed();
@@ -333,20 +354,24 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let base = self.force_allocation(base)?;
         let (base_ptr, _) = base.to_ptr_and_extra();
 
-        let (elem_ty, len) = base.elem_ty_and_len(outer_ty);
+        // The bounds were checked by an `Assert` before the projection, which forks on
+        // symbolic indices and lengths.
+        let (elem_ty, len) = base.elem_ty_and_len_primval(outer_ty);
         let elem_size = self.type_size(elem_ty)?.expect(
             "slice element must be sized",
         );
         if idx.is_concrete() {
             let n = idx.to_u64()?;
-            assert!(
-                n < len,
-                "Tried to access element {} of array/slice with length {}",
-                n,
-                len
-            );
+            if let PrimVal::Bytes(len) = len {
+                assert!(
+                    n < len as u64,
+                    "Tried to access element {} of array/slice with length {}",
+                    n,
+                    len
+                );
+            }
             let ptr_primval = match (base_ptr.to_ptr(), elem_size) {
-                (Ok(p), _) => PrimVal::Ptr(p.offset(n * elem_size, (&self).data_layout())?),
+                (Ok(p), _) => PrimVal::Ptr(self.offset_lvalue_ptr(p, n * elem_size)?),
                 (Err(_), 0) => base_ptr,
                 (Err(e), _) => return Err(e),
             };
@@ -380,6 +405,28 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         }
     }
 
+    /// Offsets a pointer into an aggregate by a constant amount. A pointer that was
+    /// obtained through a symbolic index keeps its offset symbolic, so that projections
+    /// like `arr[i].y` can be read.
+    pub(super) fn offset_lvalue_ptr(
+        &mut self,
+        ptr: MemoryPointer,
+        offset: u64,
+    ) -> EvalResult<'tcx, MemoryPointer> {
+        match ptr.offset {
+            PointerOffset::Concrete(_) => ptr.offset(offset, (&self).data_layout()),
+            PointerOffset::Abstract(_) if offset == 0 => Ok(ptr),
+            PointerOffset::Abstract(_) => {
+                let offset = self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::Add,
+                    ptr.offset.as_primval(),
+                    PrimVal::Bytes(offset as u128),
+                    PrimValKind::U64);
+                Ok(MemoryPointer::with_primval_offset(ptr.alloc_id, offset))
+            }
+        }
+    }
+
     pub(super) fn lvalue_downcast(
         &mut self,
         base: Lvalue<'tcx>,
@@ -421,7 +468,21 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
                 trace!("deref to {} on {:?}", pointee_type, val);
 
-                return self.val_to_lvalue(val, pointee_type);
+                let lvalue = self.val_to_lvalue(val, pointee_type)?;
+                if let Lvalue::Ptr { ptr: address @ PrimVal::Abstract(_), .. } = lvalue {
+                    // The pointer is a symbolic integer rather than a pointer into an
+                    // allocation. The executor forks off the path where it is null.
+                    let kind = PrimValKind::from_uint_size(self.memory.pointer_size());
+                    let null = Constraint::new_compare(mir::BinOp::Eq, kind, address, PrimVal::Bytes(0));
+                    let non_null = Constraint::new_compare(mir::BinOp::Ne, kind, address, PrimVal::Bytes(0));
+                    if self.memory.constraints.is_feasible_with(&[null]) {
+                        if !self.memory.constraints.is_feasible_with(&[non_null]) {
+                            return Err(EvalError::InvalidNullPointerUsage);
+                        }
+                        self.memory.defer_failure(null, non_null, EvalError::InvalidNullPointerUsage);
+                    }
+                }
+                return Ok(lvalue);
             }
 
             Index(local) => {
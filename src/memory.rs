@@ -1,11 +1,14 @@
 use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian, BigEndian};
 use std::collections::{btree_map, BTreeMap, HashMap, HashSet, VecDeque, BTreeSet};
-use std::{fmt, iter, ptr, mem, io};
+use std::{cmp, fmt, iter, ptr, mem, io};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 use rustc::{ty, mir};
 use rustc::ty::layout::{self, TargetDataLayout};
+use syntax::codemap::Span;
 
-use constraints::ConstraintContext;
+use constraints::{Constraint, ConstraintContext};
 use error::{EvalError, EvalResult};
 use value::{self, PrimVal, PrimValKind, Value};
 
@@ -13,7 +16,14 @@ use value::{self, PrimVal, PrimValKind, Value};
 // Allocations and pointers
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Identifies an allocation in error reports and dumps.
+///
+/// Ids are handed out in the order in which a path creates its allocations, and a forked
+/// path continues counting from the state it was forked from. Ids therefore only depend on
+/// the program and on the path being explored, not on the order in which the executor
+/// visits paths, so repeated runs with the same input report the same ids.
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct AllocId(pub u64);
 
 impl fmt::Display for AllocId {
@@ -22,10 +32,12 @@ impl fmt::Display for AllocId {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct AbstractVariable(pub u32);
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum SByte {
     Concrete(u8),
     Abstract(AbstractVariable),
@@ -47,29 +59,108 @@ pub struct Allocation {
     /// The alignment of the allocation to detect unaligned reads.
     pub align: u64,
 
-    /// Whether the allocation may be modified.
+    /// The length of the allocation, if it is symbolic. `bytes` then provides storage
+    /// for the largest length that the path constraints allow.
+    pub abstract_size: Option<[SByte; 8]>,
+
+    /// The number of bytes that count towards the memory usage. For a symbolic length
+    /// this is the smallest length that the path constraints allow, rather than the
+    /// length of the storage.
+    pub usage: u64,
+
+    /// Where the memory comes from, and so whether it may be modified or deallocated.
     /// Use the `mark_static_initalized` method of `Memory` to ensure that an error occurs, if the memory of this
     /// allocation is modified or deallocated in the future.
-    pub static_kind: StaticKind,
+    pub kind: AllocKind,
+}
+
+/// How often the evaluated program accessed an allocation, as recorded by `Memory`
+/// once `track_accesses` was called.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AccessStats {
+    pub reads: u64,
+    pub writes: u64,
+    pub freed: bool,
+}
+
+/// An allocation that was still live when the path ended, as returned by
+/// `Memory::unfreed_allocations`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AllocationReport {
+    pub id: AllocId,
+    pub kind: AllocKind,
+    pub size: u64,
+    /// All zero unless accesses are tracked.
+    pub stats: AccessStats,
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
-pub enum StaticKind {
-    /// may be deallocated without breaking miri's invariants
-    NotStatic,
+pub enum AllocKind {
+    /// A local or temporary of a stack frame, or other memory of the interpreter
+    Stack,
+    /// returned by the allocator to the evaluated program
+    Heap,
+    /// holds a symbolic input, such as an argument of the entry function
+    Input,
     /// may be modified, but never deallocated
-    Mutable,
+    MutableStatic,
     /// may neither be modified nor deallocated
-    Immutable,
+    ImmutableStatic,
+}
+
+impl AllocKind {
+    pub fn is_static(self) -> bool {
+        match self {
+            AllocKind::MutableStatic | AllocKind::ImmutableStatic => true,
+            AllocKind::Stack | AllocKind::Heap | AllocKind::Input => false,
+        }
+    }
+}
+
+impl Allocation {
+    /// Joins `other`, the same allocation on a merged path, into this one, so that each byte
+    /// is the one of this path where `guard` holds. Returns `false` if the allocations differ
+    /// in anything but the values of their bytes.
+    pub(crate) fn merge(
+        &mut self,
+        other: &Allocation,
+        guard: PrimVal,
+        constraints: &mut ConstraintContext,
+    ) -> bool {
+        if self.bytes.len() != other.bytes.len() || self.relocations != other.relocations ||
+            self.undef_mask != other.undef_mask || self.align != other.align ||
+            self.abstract_size != other.abstract_size || self.usage != other.usage ||
+            self.kind != other.kind
+        {
+            return false;
+        }
+        for (byte, &other_byte) in self.bytes.iter_mut().zip(&other.bytes) {
+            if *byte != other_byte {
+                let mut then_branch = [SByte::Concrete(0); 8];
+                then_branch[0] = *byte;
+                let mut else_branch = [SByte::Concrete(0); 8];
+                else_branch[0] = other_byte;
+                match constraints.add_if_then_else(
+                    guard, PrimValKind::U8, PrimVal::Abstract(then_branch), PrimVal::Abstract(else_branch))
+                {
+                    PrimVal::Abstract(sbytes) => *byte = sbytes[0],
+                    _ => bug!("add_if_then_else() returned a concrete value"),
+                }
+            }
+        }
+        true
+    }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct MemoryPointer {
     pub alloc_id: AllocId,
     pub offset: PointerOffset,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum PointerOffset {
     /// Offset in bytes.
     Concrete(u64),
@@ -171,7 +262,8 @@ impl MemoryPointer {
 #[derive(Clone)]
 pub struct Memory<'a, 'tcx> {
     /// Actual memory allocations (arbitrary bytes, may contain pointers into other allocations).
-    alloc_map: HashMap<AllocId, Allocation>,
+    /// They are shared with the clones and snapshots of this memory until they are modified.
+    alloc_map: HashMap<AllocId, Rc<Allocation>>,
 
     rustc_allocations: HashMap<mir::interpret::AllocId, AllocId>,
 
@@ -217,6 +309,19 @@ pub struct Memory<'a, 'tcx> {
     /// allocations for string and bytestring literals.
     literal_alloc_cache: HashMap<Vec<u8>, AllocId>,
 
+    /// The accesses to each allocation, if they are tracked. Reads only borrow the
+    /// memory immutably, hence the `RefCell`.
+    access_stats: Option<RefCell<HashMap<AllocId, AccessStats>>>,
+
+    /// Where the evaluated program freed its deallocated allocations, to report uses
+    /// after free.
+    freed: HashMap<AllocId, Span>,
+
+    /// Accesses that fail only for some of the values of a symbolic length or address, as
+    /// the constraint under which they fail, the one under which they succeed, and the
+    /// error. See `take_deferred_failures`.
+    deferred_failures: RefCell<Vec<(Constraint, Constraint, EvalError<'tcx>)>>,
+
     pub constraints: ConstraintContext,
 }
 
@@ -234,6 +339,9 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             packed: BTreeSet::new(),
             static_alloc: HashSet::new(),
             literal_alloc_cache: HashMap::new(),
+            access_stats: None,
+            freed: HashMap::new(),
+            deferred_failures: RefCell::new(Vec::new()),
             constraints: ConstraintContext::new(),
         }
     }
@@ -244,17 +352,24 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         ((val % max_ptr_plus_1) as u64, val >= max_ptr_plus_1)
     }
 
-    pub fn allocations(&self) -> ::std::collections::hash_map::Iter<AllocId, Allocation> {
-        self.alloc_map.iter()
+    pub fn allocations<'b>(&'b self) -> Box<Iterator<Item = (&'b AllocId, &'b Allocation)> + 'b> {
+        Box::new(self.alloc_map.iter().map(|(id, alloc)| (id, &**alloc)))
+    }
+
+    /// Reserves the id for a new allocation. All allocations take their ids from
+    /// here, which keeps the numbering deterministic (see `AllocId`).
+    fn next_alloc_id(&mut self) -> AllocId {
+        let id = self.next_id;
+        self.next_id.0 += 1;
+        id
     }
 
     pub fn create_fn_alloc(&mut self, instance: ty::Instance<'tcx>) -> MemoryPointer {
         if let Some(&alloc_id) = self.function_alloc_cache.get(&instance) {
             return MemoryPointer::new(alloc_id, 0);
         }
-        let id = self.next_id;
+        let id = self.next_alloc_id();
         debug!("creating fn ptr: {}", id);
-        self.next_id.0 += 1;
         self.functions.insert(id, instance);
         self.function_alloc_cache.insert(instance, id);
         MemoryPointer::new(id, 0)
@@ -273,6 +388,12 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
     }
 
     pub fn allocate(&mut self, size: u64, align: u64) -> EvalResult<'tcx, MemoryPointer> {
+        self.allocate_with_kind(size, align, AllocKind::Stack)
+    }
+
+    pub fn allocate_with_kind(&mut self, size: u64, align: u64, kind: AllocKind)
+                              -> EvalResult<'tcx, MemoryPointer>
+    {
         assert_ne!(align, 0);
         assert!(align.is_power_of_two());
 
@@ -290,14 +411,67 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             relocations: BTreeMap::new(),
             undef_mask: UndefMask::new(size),
             align,
-            static_kind: StaticKind::NotStatic,
+            abstract_size: None,
+            usage: size,
+            kind,
         };
-        let id = self.next_id;
-        self.next_id.0 += 1;
-        self.alloc_map.insert(id, alloc);
+        let id = self.next_alloc_id();
+        self.alloc_map.insert(id, Rc::new(alloc));
         Ok(MemoryPointer::new(id, 0))
     }
 
+    /// Allocates a region whose length is the abstract `size`. Storage is reserved
+    /// for the largest length allowed by the path constraints together with
+    /// `constraints`, which must ensure that `size` is nonzero and at most
+    /// `free_memory()`. If `zeroed` is set, all of that storage starts out as zeros.
+    /// Only the smallest allowed length counts towards the memory usage.
+    pub fn allocate_abstract(
+        &mut self,
+        size: [SByte; 8],
+        align: u64,
+        constraints: &[Constraint],
+        zeroed: bool,
+    ) -> EvalResult<'tcx, MemoryPointer> {
+        let free = self.free_memory();
+        let max_size = self.constraints.max_value_with(
+            PrimVal::Abstract(size), PrimValKind::U64, constraints, free);
+        let ptr = self.allocate_with_kind(max_size, align, AllocKind::Heap)?;
+        if zeroed {
+            // Before the abstract size is set, so that the whole storage is in bounds.
+            self.write_repeat(ptr, 0, max_size)?;
+        }
+        self.set_abstract_size(ptr, size, constraints)?;
+        Ok(ptr)
+    }
+
+    /// Gives the allocation of `ptr` the symbolic length `size`, and charges the memory
+    /// usage with the smallest length that `constraints` allow instead of its storage.
+    fn set_abstract_size(
+        &mut self,
+        ptr: MemoryPointer,
+        size: [SByte; 8],
+        constraints: &[Constraint],
+    ) -> EvalResult<'tcx> {
+        let storage = self.get(ptr.alloc_id)?.usage;
+        let min_size = self.constraints.min_value_with(
+            PrimVal::Abstract(size), PrimValKind::U64, constraints, storage);
+        self.memory_usage -= storage - min_size;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        alloc.abstract_size = Some(size);
+        alloc.usage = min_size;
+        Ok(())
+    }
+
+    /// Maximum number of virtual bytes that may be allocated.
+    pub fn memory_size(&self) -> u64 {
+        self.memory_size
+    }
+
+    /// Number of virtual bytes that may still be allocated.
+    pub fn free_memory(&self) -> u64 {
+        self.memory_size - self.memory_usage
+    }
+
     pub fn get_rustc_allocation(&mut self, tcx: &ty::TyCtxt<'a, 'tcx, 'tcx>, ptr: mir::interpret::MemoryPointer)
                                 -> EvalResult<'tcx, MemoryPointer>
     {
@@ -312,16 +486,18 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 unimplemented!()
             } else if let Some(alloc) = tcx.interpret_interner.get_alloc(ptr.alloc_id) {
                 let size = alloc.bytes.len() as u64;
-                let static_kind = match alloc.runtime_mutability {
-                    ::syntax::ast::Mutability::Mutable =>  StaticKind::Mutable,
-                    ::syntax::ast::Mutability::Immutable =>  StaticKind::Immutable,
+                let kind = match alloc.runtime_mutability {
+                    ::syntax::ast::Mutability::Mutable =>  AllocKind::MutableStatic,
+                    ::syntax::ast::Mutability::Immutable =>  AllocKind::ImmutableStatic,
                 };
                 let mut new_alloc = Allocation {
                     bytes: vec![],
                     relocations: BTreeMap::new(),
                     undef_mask: UndefMask::new(size),
                     align : alloc.align.abi(),
-                    static_kind,
+                    abstract_size: None,
+                    usage: 0,
+                    kind,
                 };
 
                 for &b in &alloc.bytes {
@@ -336,9 +512,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                     unimplemented!()
                 }
 
-                let id = self.next_id;
-                self.next_id.0 += 1;
-                self.alloc_map.insert(id, new_alloc);
+                let id = self.next_alloc_id();
+                self.alloc_map.insert(id, Rc::new(new_alloc));
                 self.rustc_allocations.insert(ptr.alloc_id, id);
                 Ok(MemoryPointer::new(id, ptr.offset))
             } else {
@@ -360,21 +535,24 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         if ptr_offset != 0 {
             return Err(EvalError::Unimplemented(format!("bad pointer offset: {}", ptr_offset)));
         }
-        if self.get(ptr.alloc_id).ok().map_or(false, |alloc| alloc.static_kind != StaticKind::NotStatic) {
+        if self.get(ptr.alloc_id).ok().map_or(false, |alloc| alloc.kind.is_static()) {
             return Err(EvalError::ReallocatedStaticMemory);
         }
 
-        let size = self.get(ptr.alloc_id)?.bytes.len() as u64;
+        // The storage of an allocation with a symbolic size holds its largest size, so
+        // resizing the storage keeps every byte that the old size may cover.
+        let (size, usage) = {
+            let alloc = self.get(ptr.alloc_id)?;
+            (alloc.bytes.len() as u64, alloc.usage)
+        };
 
         if new_size > size {
             let amount = new_size - size;
-            self.memory_usage += amount;
             let alloc = self.get_mut(ptr.alloc_id)?;
             assert_eq!(amount as usize as u64, amount);
             alloc.bytes.extend(iter::repeat(SByte::Concrete(0)).take(amount as usize));
             alloc.undef_mask.grow(amount, false);
         } else if size > new_size {
-            self.memory_usage -= size - new_size;
             self.clear_relocations(ptr.offset(new_size, self.layout)?, size - new_size)?;
             let alloc = self.get_mut(ptr.alloc_id)?;
             // `as usize` is fine here, since it is smaller than `size`, which came from a usize
@@ -382,10 +560,32 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             alloc.bytes.shrink_to_fit();
             alloc.undef_mask.truncate(new_size);
         }
+        self.memory_usage = self.memory_usage - usage + new_size;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        alloc.abstract_size = None;
+        alloc.usage = new_size;
 
         Ok(MemoryPointer::new(ptr.alloc_id, 0))
     }
 
+    /// Like `reallocate`, but to a symbolic `new_size`. The storage grows or shrinks to the
+    /// largest size that `constraints` allow, as for `allocate_abstract`. The allocation
+    /// keeps its id, so its bytes, symbolic or not, and the pointers into it stay valid.
+    pub fn reallocate_abstract(
+        &mut self,
+        ptr: MemoryPointer,
+        new_size: [SByte; 8],
+        align: u64,
+        constraints: &[Constraint],
+    ) -> EvalResult<'tcx, MemoryPointer> {
+        let available = self.free_memory() + self.get(ptr.alloc_id)?.usage;
+        let max_size = self.constraints.max_value_with(
+            PrimVal::Abstract(new_size), PrimValKind::U64, constraints, available);
+        let ptr = self.reallocate(ptr, max_size, align)?;
+        self.set_abstract_size(ptr, new_size, constraints)?;
+        Ok(ptr)
+    }
+
     // TODO(solson): See comment on `reallocate`.
     pub fn deallocate(&mut self, ptr: MemoryPointer) -> EvalResult<'tcx> {
         let ptr_offset = match ptr.offset {
@@ -397,12 +597,15 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             // TODO(solson): Report error about non-__rust_allocate'd pointer.
             return Err(EvalError::Unimplemented(format!("bad pointer offset: {}", ptr_offset)));
         }
-        if self.get(ptr.alloc_id).ok().map_or(false, |alloc| alloc.static_kind != StaticKind::NotStatic) {
+        if self.get(ptr.alloc_id).ok().map_or(false, |alloc| alloc.kind.is_static()) {
             return Err(EvalError::DeallocatedStaticMemory);
         }
 
         if let Some(alloc) = self.alloc_map.remove(&ptr.alloc_id) {
-            self.memory_usage -= alloc.bytes.len() as u64;
+            self.memory_usage -= alloc.usage;
+            if let Some(ref stats) = self.access_stats {
+                stats.borrow_mut().entry(ptr.alloc_id).or_insert_with(Default::default).freed = true;
+            }
         } else {
             debug!("deallocated a pointer twice: {}", ptr.alloc_id);
             // TODO(solson): Report error about erroneous free. This is blocked on properly tracking
@@ -414,6 +617,14 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         Ok(())
     }
 
+    /// Like `deallocate`, but records that the evaluated program freed the allocation at
+    /// `span`, so that later accesses fail with `UseAfterFree`.
+    pub fn deallocate_at(&mut self, ptr: MemoryPointer, span: Span) -> EvalResult<'tcx> {
+        self.deallocate(ptr)?;
+        self.freed.insert(ptr.alloc_id, span);
+        Ok(())
+    }
+
     pub fn pointer_size(&self) -> u64 {
         self.layout.pointer_size.bytes()
     }
@@ -477,9 +688,52 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         if ptr_offset > allocation_size {
             return Err(EvalError::PointerOutOfBounds { ptr, access, allocation_size });
         }
+        if let Some(size) = alloc.abstract_size {
+            let offset = PrimVal::Bytes(ptr_offset as u128);
+            let past_end = Constraint::new_compare(
+                mir::BinOp::Gt, PrimValKind::U64, offset, PrimVal::Abstract(size));
+            if self.constraints.is_feasible_with(&[past_end]) {
+                let err = EvalError::PointerOutOfBounds { ptr, access, allocation_size };
+                // `past_end` is feasible, so the offset is at least one.
+                let in_bounds = Constraint::new_compare(
+                    mir::BinOp::Gt, PrimValKind::U64, PrimVal::Abstract(size),
+                    PrimVal::Bytes(ptr_offset as u128 - 1));
+                if !self.constraints.is_feasible_with(&[in_bounds]) {
+                    return Err(err);
+                }
+                // Only some of the allowed lengths are too short. The access goes ahead,
+                // and the executor forks off the path where it fails.
+                self.defer_failure(past_end, in_bounds, err);
+            }
+        }
         Ok(())
     }
 
+    /// Records that the current access fails with `err` under `fails`, and goes ahead
+    /// under `holds`, for the executor to fork on. See `take_deferred_failures`.
+    pub(crate) fn defer_failure(&self, fails: Constraint, holds: Constraint, err: EvalError<'tcx>) {
+        let mut failures = self.deferred_failures.borrow_mut();
+        if failures.iter().all(|&(c, _, _)| c != fails) {
+            failures.push((fails, holds, err));
+        }
+    }
+
+    /// Returns the accesses since the last call that fail for some, but not all, of the
+    /// values they depend on, e.g. the lengths of a symbolic-length allocation. Each comes
+    /// with the constraint under which it fails, the one under which it succeeds, and the
+    /// error to report.
+    pub(crate) fn take_deferred_failures(&self) -> Vec<(Constraint, Constraint, EvalError<'tcx>)> {
+        mem::replace(&mut *self.deferred_failures.borrow_mut(), Vec::new())
+    }
+
+    /// Moves `ptr` by `delta` bytes within its allocation, where either may be symbolic.
+    /// Negative deltas wrap around, so the result is not bounds-checked.
+    pub(crate) fn abstract_offset(&mut self, ptr: MemoryPointer, delta: PrimVal) -> MemoryPointer {
+        let offset = self.constraints.add_binop_constraint(
+            mir::BinOp::Add, ptr.offset.as_primval(), delta, PrimValKind::U64);
+        MemoryPointer::with_primval_offset(ptr.alloc_id, offset)
+    }
+
     pub(crate) fn mark_packed(&mut self, ptr: MemoryPointer, len: u64) {
         let ptr_offset = match ptr.offset {
             PointerOffset::Concrete(offset) => offset,
@@ -498,6 +752,81 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
     }
 }
 
+/// State merging
+impl<'a, 'tcx> Memory<'a, 'tcx> {
+    /// Joins the allocations of `other`, the memory of a merged path, into this memory, so
+    /// that each byte is the one of this path where `guard` holds. Returns `false` if the
+    /// two paths do not have the same allocations.
+    pub(crate) fn merge(&mut self, other: &Memory<'a, 'tcx>, guard: PrimVal) -> bool {
+        if self.next_id != other.next_id || self.alloc_map.len() != other.alloc_map.len() ||
+            self.functions != other.functions || self.static_alloc != other.static_alloc ||
+            self.rustc_allocations != other.rustc_allocations
+        {
+            return false;
+        }
+        for (id, alloc) in self.alloc_map.iter_mut() {
+            let other_alloc = match other.alloc_map.get(id) {
+                Some(other_alloc) => other_alloc,
+                None => return false,
+            };
+            if Rc::ptr_eq(alloc, other_alloc) {
+                continue;
+            }
+            if !Rc::make_mut(alloc).merge(other_alloc, guard, &mut self.constraints) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The allocations of a `Memory` at some point, to return to with `Memory::restore`.
+/// Allocations are shared with the memory until they are written, so taking a snapshot
+/// copies no bytes, and only the allocations modified afterwards are duplicated.
+/// The path constraints are not part of the snapshot.
+#[derive(Clone)]
+pub struct MemorySnapshot<'tcx> {
+    alloc_map: HashMap<AllocId, Rc<Allocation>>,
+    rustc_allocations: HashMap<mir::interpret::AllocId, AllocId>,
+    next_id: AllocId,
+    static_alloc: HashSet<AllocId>,
+    memory_usage: u64,
+    functions: HashMap<AllocId, ty::Instance<'tcx>>,
+    function_alloc_cache: HashMap<ty::Instance<'tcx>, AllocId>,
+    literal_alloc_cache: HashMap<Vec<u8>, AllocId>,
+    freed: HashMap<AllocId, Span>,
+}
+
+/// Snapshots
+impl<'a, 'tcx> Memory<'a, 'tcx> {
+    pub fn snapshot(&self) -> MemorySnapshot<'tcx> {
+        MemorySnapshot {
+            alloc_map: self.alloc_map.clone(),
+            rustc_allocations: self.rustc_allocations.clone(),
+            next_id: self.next_id,
+            static_alloc: self.static_alloc.clone(),
+            memory_usage: self.memory_usage,
+            functions: self.functions.clone(),
+            function_alloc_cache: self.function_alloc_cache.clone(),
+            literal_alloc_cache: self.literal_alloc_cache.clone(),
+            freed: self.freed.clone(),
+        }
+    }
+
+    /// Undoes all allocations, deallocations and writes since `snapshot` was taken.
+    pub fn restore(&mut self, snapshot: MemorySnapshot<'tcx>) {
+        self.alloc_map = snapshot.alloc_map;
+        self.rustc_allocations = snapshot.rustc_allocations;
+        self.next_id = snapshot.next_id;
+        self.static_alloc = snapshot.static_alloc;
+        self.memory_usage = snapshot.memory_usage;
+        self.functions = snapshot.functions;
+        self.function_alloc_cache = snapshot.function_alloc_cache;
+        self.literal_alloc_cache = snapshot.literal_alloc_cache;
+        self.freed = snapshot.freed;
+    }
+}
+
 // The derived `Ord` impl sorts first by the first field, then, if the fields are the same
 // by the second field, and if those are the same, too, then by the third field.
 // This is exactly what we need for our purposes, since a range within an allocation
@@ -516,25 +845,29 @@ struct Entry {
 impl<'a, 'tcx> Memory<'a, 'tcx> {
     pub fn get(&self, id: AllocId) -> EvalResult<'tcx, &Allocation> {
         match self.alloc_map.get(&id) {
-            Some(alloc) => Ok(alloc),
-            None => match self.functions.get(&id) {
-                Some(_) => Err(EvalError::DerefFunctionPointer),
-                None => Err(EvalError::DanglingPointerDeref),
-            }
+            Some(alloc) => Ok(&**alloc),
+            None => Err(self.missing_allocation(id)),
         }
     }
 
     pub fn get_mut(&mut self, id: AllocId) -> EvalResult<'tcx, &mut Allocation> {
-        match self.alloc_map.get_mut(&id) {
-            Some(alloc) => match alloc.static_kind {
-                StaticKind::Mutable |
-                StaticKind::NotStatic => Ok(alloc),
-                StaticKind::Immutable => Err(EvalError::ModifiedConstantMemory),
-            },
-            None => match self.functions.get(&id) {
-                Some(_) => Err(EvalError::DerefFunctionPointer),
-                None => Err(EvalError::DanglingPointerDeref),
-            }
+        if !self.alloc_map.contains_key(&id) {
+            return Err(self.missing_allocation(id));
+        }
+        let alloc = self.alloc_map.get_mut(&id).expect("checked above");
+        match alloc.kind {
+            AllocKind::ImmutableStatic => Err(EvalError::ModifiedConstantMemory),
+            _ => Ok(Rc::make_mut(alloc)),
+        }
+    }
+
+    fn missing_allocation(&self, id: AllocId) -> EvalError<'tcx> {
+        if self.functions.contains_key(&id) {
+            EvalError::DerefFunctionPointer
+        } else if let Some(&freed_at) = self.freed.get(&id) {
+            EvalError::UseAfterFree { freed_at }
+        } else {
+            EvalError::DanglingPointerDeref
         }
     }
 
@@ -559,76 +892,124 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
 
     /// For debugging, print a list of allocations and all allocations they point to, recursively.
     pub fn dump_allocs(&self, mut allocs: Vec<AllocId>) {
-        use std::fmt::Write;
         allocs.sort();
         allocs.dedup();
         let mut allocs_to_print = VecDeque::from(allocs);
         let mut allocs_seen = HashSet::new();
 
         while let Some(id) = allocs_to_print.pop_front() {
-            let mut msg = format!("Alloc {:<5} ", format!("{}:", id));
-            let prefix_len = msg.len();
-            let mut relocations = vec![];
-
-            let alloc = match (self.alloc_map.get(&id), self.functions.get(&id)) {
-                (Some(a), None) => a,
-                (None, Some(instance)) => {
-                    trace!("{} {}", msg, instance);
-                    continue;
-                },
-                (None, None) => {
-                    trace!("{} (deallocated)", msg);
-                    continue;
-                },
-                (Some(_), Some(_)) => bug!("miri invariant broken: an allocation id exists that points to both a function and a memory location"),
-            };
-
-            for i in 0..(alloc.bytes.len() as u64) {
-                if let Some(&target_id) = alloc.relocations.get(&i) {
+            trace!("{}", self.dump_allocation(id));
+            if let Some(alloc) = self.alloc_map.get(&id) {
+                for &target_id in alloc.relocations.values() {
                     if allocs_seen.insert(target_id) {
                         allocs_to_print.push_back(target_id);
                     }
-                    relocations.push((i, target_id));
-                }
-                if alloc.undef_mask.is_range_defined(i, i + 1) {
-                    // this `as usize` is fine, since `i` came from a `usize`
-                    write!(msg, "{:?} ", alloc.bytes[i as usize]).unwrap();
-                } else {
-                    msg.push_str("__ ");
                 }
             }
+        }
+    }
 
-            let immutable = match alloc.static_kind {
-                StaticKind::Mutable => " (static mut)",
-                StaticKind::Immutable => " (immutable)",
-                StaticKind::NotStatic => "",
-            };
-            trace!("{}({} bytes, alignment {}){}", msg, alloc.bytes.len(), alloc.align, immutable);
-
-            if !relocations.is_empty() {
-                msg.clear();
-                write!(msg, "{:1$}", "", prefix_len).unwrap(); // Print spaces.
-                let mut pos = 0;
-                let relocation_width = (self.pointer_size() - 1) * 3;
-                for (i, target_id) in relocations {
-                    // this `as usize` is fine, since we can't print more chars than `usize::MAX`
-                    write!(msg, "{:1$}", "", ((i - pos) * 3) as usize).unwrap();
-                    let target = format!("({})", target_id);
-                    // this `as usize` is fine, since we can't print more chars than `usize::MAX`
-                    write!(msg, "└{0:─^1$}┘ ", target, relocation_width as usize).unwrap();
-                    pos = i + self.pointer_size();
+    /// Renders the bytes of an allocation on a single line. Concrete bytes are shown in
+    /// hex, undefined bytes as `__` and symbolic bytes as `$` followed by the id of their
+    /// variable. A pointer is shown as `[alloc N: ...]` around the bytes of its offset.
+    pub fn dump_allocation(&self, id: AllocId) -> String {
+        use std::fmt::Write;
+        let mut msg = format!("Alloc {}: ", id);
+
+        let alloc = match (self.alloc_map.get(&id), self.functions.get(&id)) {
+            (Some(a), None) => a,
+            (None, Some(instance)) => {
+                write!(msg, "{}", instance).unwrap();
+                return msg;
+            }
+            (None, None) => {
+                msg.push_str("(deallocated)");
+                return msg;
+            }
+            (Some(_), Some(_)) => bug!("miri invariant broken: an allocation id exists that points to both a function and a memory location"),
+        };
+
+        let mut pointer_last_byte = None;
+        for i in 0..(alloc.bytes.len() as u64) {
+            if let Some(&target_id) = alloc.relocations.get(&i) {
+                write!(msg, "[alloc {}: ", target_id).unwrap();
+                pointer_last_byte = Some(i + self.pointer_size() - 1);
+            }
+            if !alloc.undef_mask.is_range_defined(i, i + 1) {
+                msg.push_str("__");
+            } else {
+                // this `as usize` is fine, since `i` came from a `usize`
+                match alloc.bytes[i as usize] {
+                    SByte::Concrete(b) => write!(msg, "{:02x}", b).unwrap(),
+                    SByte::Abstract(AbstractVariable(var)) => write!(msg, "${}", var).unwrap(),
                 }
-                trace!("{}", msg);
             }
+            if pointer_last_byte == Some(i) {
+                msg.push(']');
+                pointer_last_byte = None;
+            }
+            msg.push(' ');
+        }
+
+        let immutable = match alloc.kind {
+            AllocKind::MutableStatic => " (static mut)",
+            AllocKind::ImmutableStatic => " (immutable)",
+            AllocKind::Stack | AllocKind::Heap | AllocKind::Input => "",
+        };
+        write!(msg, "({} bytes, alignment {}){}", alloc.bytes.len(), alloc.align, immutable).unwrap();
+        msg
+    }
+
+    /// Starts counting the reads and writes of every allocation, and remembering which
+    /// allocations were freed.
+    pub fn track_accesses(&mut self) {
+        if self.access_stats.is_none() {
+            self.access_stats = Some(RefCell::new(HashMap::new()));
         }
     }
 
+    /// The accesses to the allocation `id` so far, if accesses are tracked.
+    pub fn access_stats(&self, id: AllocId) -> Option<AccessStats> {
+        self.access_stats.as_ref().map(|stats| {
+            stats.borrow().get(&id).cloned().unwrap_or_default()
+        })
+    }
+
+    fn record_access(&self, id: AllocId, write: bool) {
+        if let Some(ref stats) = self.access_stats {
+            let mut stats = stats.borrow_mut();
+            let entry = stats.entry(id).or_insert_with(Default::default);
+            if write {
+                entry.writes += 1;
+            } else {
+                entry.reads += 1;
+            }
+        }
+    }
+
+    /// The allocations that are neither static nor freed, sorted by id. At the end of
+    /// the program these are the leaks that `leak_report` counts.
+    pub fn unfreed_allocations(&self) -> Vec<AllocationReport> {
+        let mut leaks: Vec<_> = self.alloc_map
+            .iter()
+            .filter(|&(_, alloc)| !alloc.kind.is_static())
+            .map(|(&id, alloc)| AllocationReport {
+                id,
+                kind: alloc.kind,
+                size: alloc.bytes.len() as u64,
+                stats: self.access_stats(id).unwrap_or_default(),
+            })
+            .collect();
+        leaks.sort_by_key(|leak| leak.id);
+        leaks
+    }
+
     pub fn leak_report(&self) -> usize {
         trace!("### LEAK REPORT ###");
         let leaks: Vec<_> = self.alloc_map
             .iter()
             .filter_map(|(&key, val)| {
-                if val.static_kind == StaticKind::NotStatic {
+                if !val.kind.is_static() {
                     Some(key)
                 } else {
                     None
@@ -655,6 +1036,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         self.check_bounds(ptr.offset(size, self.layout)?, true)?;
 
         let alloc = self.get(ptr.alloc_id)?;
+        self.record_access(ptr.alloc_id, false);
 
         let ptr_offset = match ptr.offset {
             PointerOffset::Concrete(offset) => offset,
@@ -675,6 +1057,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         }
         self.check_align(ptr, align, size)?;
         self.check_bounds(ptr.offset(size, self.layout)?, true)?; // if ptr.offset is in bounds, then so is ptr (because offset checks for overflow)
+        self.record_access(ptr.alloc_id, true);
         let alloc = self.get_mut(ptr.alloc_id)?;
 
         assert_eq!(size as usize as u64, size);
@@ -754,12 +1137,15 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         trace!("mark_static_initialized {:?}, mutable: {:?}", alloc_id, mutable);
         // do not use `self.get_mut(alloc_id)` here, because we might have already marked a
         // sub-element or have circular pointers (e.g. `Rc`-cycles)
-        let relocations = match self.alloc_map.get_mut(&alloc_id) {
-            Some(&mut Allocation { ref mut relocations, static_kind: ref mut kind @ StaticKind::NotStatic, .. }) => {
+        let relocations = match self.alloc_map.get_mut(&alloc_id).map(Rc::make_mut) {
+            Some(&mut Allocation { ref mut relocations, ref mut kind, .. }) => {
+                if kind.is_static() {
+                    return Ok(());
+                }
                 *kind = if mutable {
-                    StaticKind::Mutable
+                    AllocKind::MutableStatic
                 } else {
-                    StaticKind::Immutable
+                    AllocKind::ImmutableStatic
                 };
                 // take out the relocations vector to free the borrow on self, so we can call
                 // mark recursively
@@ -773,10 +1159,23 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             self.mark_inner_allocation(alloc, mutable)?;
         }
         // put back the relocations
-        self.alloc_map.get_mut(&alloc_id).expect("checked above").relocations = relocations;
+        Rc::make_mut(self.alloc_map.get_mut(&alloc_id).expect("checked above")).relocations = relocations;
         Ok(())
     }
 
+    /// Exchanges the `size` bytes at `a` with those at `b`, which may overlap as for `ptr::swap`.
+    /// Symbolic bytes and relocations move along with the bytes.
+    pub fn swap(&mut self, a: PrimVal, b: PrimVal, size: u64, align: u64) -> EvalResult<'tcx> {
+        if size == 0 || a == b {
+            return Ok(());
+        }
+        let tmp = self.allocate(size, align)?;
+        self.copy(a, PrimVal::Ptr(tmp), size, align)?;
+        self.copy(b, a, size, align)?;
+        self.copy(PrimVal::Ptr(tmp), b, size, align)?;
+        self.deallocate(tmp)
+    }
+
     pub fn copy(&mut self, src: PrimVal, dest: PrimVal, size: u64, align: u64) -> EvalResult<'tcx> {
         if size == 0 {
             return Ok(());
@@ -833,6 +1232,73 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         Ok(())
     }
 
+    /// Copies the first `size` bytes from `src` to `dest`, where `size` is symbolic and
+    /// between `min_size` and `max_size`. The remaining bytes up to `max_size` keep their
+    /// old values.
+    pub fn copy_abstract_size(
+        &mut self,
+        src: MemoryPointer,
+        dest: MemoryPointer,
+        size: PrimVal,
+        min_size: u64,
+        max_size: u64,
+        align: u64,
+    ) -> EvalResult<'tcx> {
+        if max_size == 0 {
+            return Ok(());
+        }
+        let (src_offset, dest_offset) = match (src.offset, dest.offset) {
+            (PointerOffset::Concrete(src_offset), PointerOffset::Concrete(dest_offset)) =>
+                (src_offset, dest_offset),
+            _ => return Err(EvalError::UnsupportedSymbolicOperation {
+                op: "copy with a symbolic size".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
+        };
+        if self.relocations(src, max_size)?.count() != 0 || self.relocations(dest, max_size)?.count() != 0 {
+            return Err(EvalError::UnsupportedSymbolicOperation {
+                op: "copying pointers".to_owned(),
+                ty: "copy size".to_owned(),
+            });
+        }
+
+        let src_bytes = self.get_bytes_unchecked(src, max_size, align)?.to_vec();
+        let dest_bytes = self.get_bytes_unchecked(dest, max_size, align)?.to_vec();
+        let mut bytes = Vec::with_capacity(src_bytes.len());
+        for (idx, (&src_byte, &dest_byte)) in src_bytes.iter().zip(dest_bytes.iter()).enumerate() {
+            if src_byte == dest_byte {
+                bytes.push(src_byte);
+                continue;
+            }
+            let copied = self.constraints.add_binop_constraint(
+                mir::BinOp::Lt, PrimVal::Bytes(idx as u128), size, PrimValKind::U64);
+            let mut then_bytes = [SByte::Concrete(0); 8];
+            then_bytes[0] = src_byte;
+            let mut else_bytes = [SByte::Concrete(0); 8];
+            else_bytes[0] = dest_byte;
+            match self.constraints.add_if_then_else(
+                copied, PrimValKind::U8, PrimVal::Abstract(then_bytes), PrimVal::Abstract(else_bytes))
+            {
+                PrimVal::Abstract(sbytes) => bytes.push(sbytes[0]),
+                _ => bug!("symbolic if-then-else is concrete"),
+            }
+        }
+
+        // The undef mask cannot depend on the path constraints. The first `min_size` bytes
+        // are always copied, so they take on the definedness of the source. The others
+        // may or may not be copied, so they are only defined if they are on both sides.
+        let undefined: Vec<u64> = {
+            let src_alloc = self.get(src.alloc_id)?;
+            (min_size..max_size).filter(|&idx| !src_alloc.undef_mask.get(src_offset + idx)).collect()
+        };
+        self.copy_undef_mask(src, dest, min_size)?;
+        for idx in undefined {
+            self.get_mut(dest.alloc_id)?.undef_mask.set(dest_offset + idx, false);
+        }
+        self.get_bytes_unchecked_mut(dest, max_size, align)?.copy_from_slice(&bytes);
+        Ok(())
+    }
+
     fn abstract_copy(&mut self, src: MemoryPointer, dest: MemoryPointer, size: u64, _align: u64)
                      -> EvalResult<'tcx>
     {
@@ -882,22 +1348,113 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         Ok(arr)
     }
 
-    pub fn read_c_str(&self, _ptr: MemoryPointer) -> EvalResult<'tcx, &[u8]> {
-        unimplemented!()
-        /*
+    /// Reads the null-terminated string at `ptr`, without the terminator. Fails with
+    /// `UnsupportedSymbolicOperation` if any of its bytes is symbolic, which includes
+    /// strings whose length is symbolic.
+    pub fn read_c_str(&self, ptr: MemoryPointer) -> EvalResult<'tcx, Vec<u8>> {
+        let unsupported = || EvalError::UnsupportedSymbolicOperation {
+            op: "reading a C string".to_owned(),
+            ty: "string".to_owned(),
+        };
+        let mut lengths = self.c_str_lengths(ptr, None)?;
+        if lengths.len() != 1 || !lengths[0].1.is_empty() {
+            return Err(unsupported());
+        }
+        let len = lengths.pop().unwrap().0?;
+        self.read_bytes(PrimVal::Ptr(ptr), len)?.iter().map(|&sbyte| match sbyte {
+            SByte::Concrete(b) => Ok(b),
+            SByte::Abstract(_) => Err(unsupported()),
+        }).collect()
+    }
+
+    /// Finds every feasible length of the null-terminated string at `ptr`.
+    ///
+    /// Each entry pairs a length with the constraints under which the string has
+    /// that length: every preceding byte is nonzero and the byte at that position
+    /// is zero. At most `max_len` bytes before the terminator are searched; `None`
+    /// searches to the end of the allocation. If the string may still be unterminated
+    /// when the search ends, the last entry is `UnterminatedCString`, under the
+    /// constraints that all the searched bytes are nonzero.
+    pub fn c_str_lengths(&self, ptr: MemoryPointer, max_len: Option<u64>)
+                         -> EvalResult<'tcx, Vec<(EvalResult<'tcx, u64>, Vec<Constraint>)>>
+    {
+        self.check_bounds(ptr, true)?;
+        let offset = self.concrete_c_str_offset(ptr)?;
+        let mut end = self.get(ptr.alloc_id)?.bytes.len() as u64;
+        if let Some(max_len) = max_len {
+            end = cmp::min(end, offset.saturating_add(max_len).saturating_add(1));
+        }
+
+        let lengths = self.nul_positions(ptr, end - offset)?;
+        if lengths.iter().all(|&(ref len, _)| len.is_err()) {
+            return Err(EvalError::UnterminatedCString(ptr));
+        }
+        Ok(lengths)
+    }
+
+    /// Finds every feasible position of the first nul byte among the `len` bytes at `ptr`,
+    /// as `CStr::from_bytes_with_nul` looks for it. Each state comes with the constraints
+    /// under which it is reached: every preceding byte is nonzero and the byte at the
+    /// position is zero. If there may be no nul byte at all, the last state is
+    /// `UnterminatedCString`, under the constraints that all `len` bytes are nonzero.
+    pub fn nul_positions(&self, ptr: MemoryPointer, len: u64)
+                         -> EvalResult<'tcx, Vec<(EvalResult<'tcx, u64>, Vec<Constraint>)>>
+    {
+        let offset = self.concrete_c_str_offset(ptr)?;
         let alloc = self.get(ptr.alloc_id)?;
-        assert_eq!(ptr.offset as usize as u64, ptr.offset);
-        let offset = ptr.offset as usize;
-        match alloc.bytes[offset..].iter().position(|&c| c == SByte::Concrete(0)) {
-            Some(size) => {
-                if self.relocations(ptr, (size + 1) as u64)?.count() != 0 {
-                    return Err(EvalError::ReadPointerAsBytes);
+        let allocation_size = alloc.bytes.len() as u64;
+        let end = match offset.checked_add(len) {
+            Some(end) if end <= allocation_size => end,
+            _ => return Err(EvalError::PointerOutOfBounds { ptr, access: true, allocation_size }),
+        };
+        if self.relocations(ptr, len)?.count() != 0 {
+            return Err(EvalError::ReadPointerAsBytes);
+        }
+
+        let mut nonzero_prefix = Vec::new();
+        let mut positions = Vec::new();
+        for (pos, &sbyte) in alloc.bytes[offset as usize..end as usize].iter().enumerate() {
+            let pos = pos as u64;
+            match sbyte {
+                SByte::Concrete(0) => {
+                    self.check_defined(ptr, pos + 1)?;
+                    positions.push((Ok(pos), nonzero_prefix));
+                    return Ok(positions);
                 }
-                self.check_defined(ptr, (size + 1) as u64)?;
-                Ok(&alloc.bytes[offset..offset + size])
-            },
-            None => Err(EvalError::UnterminatedCString(ptr)),
-        } */
+                SByte::Concrete(_) => {}
+                SByte::Abstract(_) => {
+                    let mut sbytes = [SByte::Concrete(0); 8];
+                    sbytes[0] = sbyte;
+                    let byte = PrimVal::Abstract(sbytes);
+
+                    let mut terminated = nonzero_prefix.clone();
+                    terminated.push(
+                        Constraint::new_compare(mir::BinOp::Eq, PrimValKind::U8, byte, PrimVal::Bytes(0)));
+                    if self.constraints.is_feasible_with(&terminated) {
+                        positions.push((Ok(pos), terminated));
+                    }
+                    nonzero_prefix.push(
+                        Constraint::new_compare(mir::BinOp::Ne, PrimValKind::U8, byte, PrimVal::Bytes(0)));
+                }
+            }
+        }
+
+        if self.constraints.is_feasible_with(&nonzero_prefix) {
+            positions.push((Err(EvalError::UnterminatedCString(ptr)), nonzero_prefix));
+        }
+        Ok(positions)
+    }
+
+    fn concrete_c_str_offset(&self, ptr: MemoryPointer) -> EvalResult<'tcx, u64> {
+        match ptr.offset {
+            PointerOffset::Concrete(offset) => Ok(offset),
+            PointerOffset::Abstract(_) => {
+                Err(EvalError::UnsupportedSymbolicOperation {
+                    op: "reading a null-terminated string".into(),
+                    ty: "pointer offset".into(),
+                })
+            }
+        }
     }
 
     pub fn read_bytes(&self, ptr: PrimVal, size: u64)
@@ -924,6 +1481,44 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         Ok(PrimVal::Abstract(result_sbytes))
     }
 
+    /// Refers to the `len` bytes at `ptr` without reading them, so that symbolic bytes are
+    /// not combined into `PrimVal`s. A symbolic `len` must be in bounds for every value that
+    /// the constraints allow, which takes a single solver query rather than one per byte.
+    pub fn read_region(&mut self, ptr: MemoryPointer, len: PrimVal) -> EvalResult<'tcx, Value> {
+        if len.is_concrete() && ptr.has_concrete_offset() {
+            let len = len.to_u64()?;
+            if len > 0 {
+                self.check_bounds(ptr.offset(len, self.layout)?, true)?;
+            }
+            return Ok(Value::ByRef(ptr));
+        }
+
+        let (allocation_size, abstract_size) = {
+            let alloc = self.get(ptr.alloc_id)?;
+            (alloc.bytes.len() as u64, alloc.abstract_size)
+        };
+        let end = self.abstract_offset(ptr, len).offset.as_primval();
+        let past_end = self.constraints.add_binop_constraint(
+            mir::BinOp::Gt, end, PrimVal::Bytes(allocation_size as u128), PrimValKind::U64);
+        // The end wraps around for lengths that are too large for the address space.
+        let wrapped = self.constraints.add_binop_constraint(
+            mir::BinOp::Lt, end, ptr.offset.as_primval(), PrimValKind::U64);
+        let mut out_of_bounds = self.constraints.add_binop_constraint(
+            mir::BinOp::BitOr, past_end, wrapped, PrimValKind::Bool);
+        if let Some(size) = abstract_size {
+            let past_abstract_end = self.constraints.add_binop_constraint(
+                mir::BinOp::Gt, end, PrimVal::Abstract(size), PrimValKind::U64);
+            out_of_bounds = self.constraints.add_binop_constraint(
+                mir::BinOp::BitOr, out_of_bounds, past_abstract_end, PrimValKind::Bool);
+        }
+        let out_of_bounds = Constraint::new_compare(
+            mir::BinOp::Eq, PrimValKind::Bool, out_of_bounds, PrimVal::from_bool(true));
+        if self.constraints.is_feasible_with(&[out_of_bounds]) {
+            return Err(EvalError::PointerOutOfBounds { ptr, access: true, allocation_size });
+        }
+        Ok(Value::ByRef(ptr))
+    }
+
     pub fn write_bytes(&mut self, ptr: MemoryPointer, src: &[u8]) -> EvalResult<'tcx> {
         let bytes = self.get_bytes_mut(ptr, src.len() as u64, 1)?;
         for idx in 0..bytes.len() {
@@ -933,8 +1528,65 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
     }
 
     pub fn write_repeat(&mut self, ptr: MemoryPointer, val: u8, count: u64) -> EvalResult<'tcx> {
+        self.write_repeat_sbyte(ptr, SByte::Concrete(val), count)
+    }
+
+    /// Like `write_repeat`, but `val` may be symbolic.
+    pub fn write_repeat_sbyte(&mut self, ptr: MemoryPointer, val: SByte, count: u64) -> EvalResult<'tcx> {
         let bytes = self.get_bytes_mut(ptr, count, 1)?;
-        for b in bytes { *b = SByte::Concrete(val); }
+        for b in bytes { *b = val; }
+        Ok(())
+    }
+
+    /// Like `write_repeat_sbyte`, but only the first `count` bytes are written, where `count`
+    /// is symbolic and between `min_count` and `max_count`. Each byte of the range becomes
+    /// `val` where the path constraints put it below `count`, and otherwise keeps its value.
+    pub fn write_repeat_abstract_count(
+        &mut self,
+        ptr: MemoryPointer,
+        val: SByte,
+        count: PrimVal,
+        min_count: u64,
+        max_count: u64,
+    ) -> EvalResult<'tcx> {
+        if max_count == 0 {
+            return Ok(());
+        }
+        if self.relocations(ptr, max_count)?.count() != 0 {
+            return Err(EvalError::UnsupportedSymbolicOperation {
+                op: "overwriting pointers".to_owned(),
+                ty: "write count".to_owned(),
+            });
+        }
+
+        let old_bytes = self.get_bytes_unchecked(ptr, max_count, 1)?.to_vec();
+        let mut bytes = Vec::with_capacity(old_bytes.len());
+        for (idx, &old_byte) in old_bytes.iter().enumerate() {
+            if old_byte == val {
+                bytes.push(val);
+                continue;
+            }
+            let written = self.constraints.add_binop_constraint(
+                mir::BinOp::Lt, PrimVal::Bytes(idx as u128), count, PrimValKind::U64);
+            let mut then_bytes = [SByte::Concrete(0); 8];
+            then_bytes[0] = val;
+            let mut else_bytes = [SByte::Concrete(0); 8];
+            else_bytes[0] = old_byte;
+            match self.constraints.add_if_then_else(
+                written, PrimValKind::U8, PrimVal::Abstract(then_bytes), PrimVal::Abstract(else_bytes))
+            {
+                PrimVal::Abstract(sbytes) => bytes.push(sbytes[0]),
+                _ => bug!("symbolic if-then-else is concrete"),
+            }
+        }
+
+        // The undef mask cannot depend on the path constraints. The first `min_count` bytes
+        // are always written, so they become defined. The others may or may not be written,
+        // so they keep their definedness.
+        let (written, tail) = bytes.split_at(min_count as usize);
+        self.get_bytes_mut(ptr, min_count, 1)?.copy_from_slice(written);
+        let tail_ptr = ptr.offset(min_count, self.layout)?;
+        self.get_bytes_unchecked_mut(tail_ptr, max_count - min_count, 1)?.copy_from_slice(tail);
         Ok(())
     }
 
@@ -999,7 +1651,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             }
         } else {
             let mut sbytes = [SByte::Concrete(0); 8];
-            sbytes.copy_from_slice(self.get_bytes_unchecked(ptr, size, size)?);
+            sbytes[..size as usize].copy_from_slice(self.get_bytes_unchecked(ptr, size, size)?);
 
             match alloc.relocations.get(&ptr_offset) {
                 Some(&alloc_id) => Ok(PrimVal::Ptr(MemoryPointer::new_abstract(alloc_id, sbytes))),
@@ -1018,7 +1670,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             }
             (PointerOffset::Abstract(sbytes),
              PointerOffset::Concrete(dest_offset)) => {
-                self.write_primval(PrimVal::Ptr(dest), PrimVal::Abstract(sbytes), 8)?; // FIXME usize
+                let size = self.pointer_size();
+                self.write_primval(PrimVal::Ptr(dest), PrimVal::Abstract(sbytes), size)?;
                 self.get_mut(dest.alloc_id)?.relocations.insert(dest_offset, ptr.alloc_id);
                 Ok(())
             }
@@ -1151,6 +1804,22 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         }
     }
 
+    /// Asks the solver whether the boolean `value` can be true under the path constraints.
+    /// Nothing is forked and no constraint is added.
+    pub fn is_feasible(&self, value: &Value) -> EvalResult<'tcx, bool> {
+        let cond = match *value {
+            Value::ByVal(cond) => cond,
+            Value::ByRef(ptr) => self.read_bool(ptr)?,
+            Value::ByValPair(..) => return Err(EvalError::InvalidBool),
+        };
+        if cond.is_concrete() {
+            return cond.to_bool();
+        }
+        let holds = Constraint::new_compare(
+            mir::BinOp::Eq, PrimValKind::Bool, cond, PrimVal::from_bool(true));
+        Ok(self.constraints.is_feasible_with(&[holds]))
+    }
+
     /*
     pub fn write_bool(&mut self, ptr: MemoryPointer, b: bool) -> EvalResult<'tcx> {
         let align = self.layout.i1_align.abi();
@@ -1446,6 +2115,63 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         }
     }
 
+    /// For a read of `size` bytes at the abstract offset of `ptr`, returns a boolean
+    /// that is true exactly when none of the bytes read is undefined. Returns `None`
+    /// if the allocation is entirely defined.
+    pub fn abstract_read_is_defined(&mut self, ptr: MemoryPointer, size: u64)
+                                    -> EvalResult<'tcx, Option<PrimVal>>
+    {
+        let offset = match ptr.offset {
+            PointerOffset::Abstract(sbytes) => PrimVal::Abstract(sbytes),
+            PointerOffset::Concrete(_) => bug!("abstract_read_is_defined() on a concrete offset"),
+        };
+        let ranges = self.get(ptr.alloc_id)?.undef_mask.undef_ranges();
+        if ranges.is_empty() {
+            return Ok(None);
+        }
+
+        let end = self.constraints.add_binop_constraint(
+            mir::BinOp::Add, offset, PrimVal::Bytes(size as u128), PrimValKind::U64);
+        let mut defined = PrimVal::from_bool(true);
+        for (start, stop) in ranges {
+            // The read misses `start..stop` if it ends before it or begins after it.
+            let before = self.constraints.add_binop_constraint(
+                mir::BinOp::Le, end, PrimVal::Bytes(start as u128), PrimValKind::U64);
+            let after = self.constraints.add_binop_constraint(
+                mir::BinOp::Ge, offset, PrimVal::Bytes(stop as u128), PrimValKind::U64);
+            let misses = self.constraints.add_binop_constraint(
+                mir::BinOp::BitOr, before, after, PrimValKind::Bool);
+            defined = self.constraints.add_binop_constraint(
+                mir::BinOp::BitAnd, defined, misses, PrimValKind::Bool);
+        }
+        Ok(Some(defined))
+    }
+
+    /// For an access with alignment `align` at the abstract offset of `ptr`, returns the
+    /// offset modulo `align`, which is zero exactly when the access is aligned. Returns
+    /// `None` if the access cannot be misaligned, or if the allocation has packed regions,
+    /// which are not checked.
+    pub fn abstract_misalignment(&mut self, ptr: MemoryPointer, align: u64)
+                                 -> EvalResult<'tcx, Option<PrimVal>>
+    {
+        let offset = match ptr.offset {
+            PointerOffset::Abstract(sbytes) => PrimVal::Abstract(sbytes),
+            PointerOffset::Concrete(_) => bug!("abstract_misalignment() on a concrete offset"),
+        };
+        if align <= 1 || self.packed.iter().any(|entry| entry.alloc_id == ptr.alloc_id) {
+            return Ok(None);
+        }
+        let alloc_align = self.get(ptr.alloc_id)?.align;
+        if alloc_align < align {
+            return Err(EvalError::AlignmentCheckFailed {
+                has: alloc_align,
+                required: align,
+            });
+        }
+        Ok(Some(self.constraints.add_binop_constraint(
+            mir::BinOp::Rem, offset, PrimVal::Bytes(align as u128), PrimValKind::U64)))
+    }
+
     pub fn mark_definedness(
         &mut self,
         ptr: PrimVal,
@@ -1481,7 +2207,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
 type Block = u64;
 const BLOCK_SIZE: u64 = 64;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UndefMask {
     blocks: Vec<Block>,
     len: u64,
@@ -1506,6 +2232,26 @@ impl UndefMask {
         true
     }
 
+    /// The maximal end-exclusive ranges of undefined bytes, in ascending order.
+    fn undef_ranges(&self) -> Vec<(u64, u64)> {
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for i in 0..self.len {
+            match (self.get(i), start) {
+                (false, None) => start = Some(i),
+                (true, Some(s)) => {
+                    ranges.push((s, i));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            ranges.push((s, self.len));
+        }
+        ranges
+    }
+
     fn set_range(&mut self, start: u64, end: u64, new_state: bool) {
         let len = self.len;
         if end > len { self.grow(end - len, new_state); }
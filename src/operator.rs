@@ -70,6 +70,96 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     }
 }
 
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    /// Computes the smaller of `a` and `b`, or the larger one if `max` is set. Like
+    /// `minnum` and `maxnum`, floats ignore an operand that is NaN. On symbolic operands
+    /// the result is selected by the solver, so the path does not fork.
+    pub(super) fn min_max(
+        &mut self,
+        max: bool,
+        a: PrimVal,
+        b: PrimVal,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, PrimVal> {
+        let kind = self.ty_to_primval_kind(ty)?;
+        let op = if max { mir::BinOp::Gt } else { mir::BinOp::Lt };
+        let (pick_a, _) = self.binary_op(op, a, ty, b, ty)?;
+        let result = self.select(pick_a, a, b, kind)?;
+        if kind.is_float() {
+            // NaN is the only value that differs from itself.
+            let (b_is_nan, _) = self.binary_op(mir::BinOp::Ne, b, ty, b, ty)?;
+            self.select(b_is_nan, a, result, kind)
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Returns `then_val` if the boolean `cond` holds, and `else_val` otherwise.
+    fn select(
+        &mut self,
+        cond: PrimVal,
+        then_val: PrimVal,
+        else_val: PrimVal,
+        kind: PrimValKind,
+    ) -> EvalResult<'tcx, PrimVal> {
+        if cond.is_concrete() {
+            return Ok(if cond.to_bool()? { then_val } else { else_val });
+        }
+        // The selection only moves bits around, so floats are selected by their bits.
+        let kind = if kind.is_float() {
+            PrimValKind::from_uint_size(kind.num_bytes() as u64)
+        } else {
+            kind
+        };
+        Ok(self.memory.constraints.add_if_then_else(cond, kind, then_val, else_val))
+    }
+
+    /// Applies the binary operation `op`, which must be `Add` or `Sub`, to the arguments
+    /// and writes the result to the destination, clamped to the range of its type.
+    pub(super) fn intrinsic_saturating(
+        &mut self,
+        op: mir::BinOp,
+        left: &mir::Operand<'tcx>,
+        right: &mir::Operand<'tcx>,
+        dest: Lvalue<'tcx>,
+        dest_ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx> {
+        let kind = self.ty_to_primval_kind(dest_ty)?;
+        let right_val = self.eval_operand_to_primval(right)?;
+        let (val, overflowed) = self.binop_with_overflow(op, left, right)?;
+
+        let bits = kind.num_bytes() * 8;
+        let (min, max) = if kind.is_signed_int() {
+            (PrimVal::from_i128(-1i128 << (bits - 1)), PrimVal::from_i128(!(-1i128 << (bits - 1))))
+        } else {
+            (PrimVal::from_u128(0), PrimVal::from_u128(!0u128 >> (128 - bits)))
+        };
+        // Unsigned operations only overflow in one direction. Signed ones saturate
+        // towards the sign of `right` for additions, and away from it for subtractions.
+        let (positive_bound, negative_bound) = match op {
+            mir::BinOp::Add => (max, min),
+            mir::BinOp::Sub => (min, max),
+            _ => bug!("saturating intrinsic for {:?}", op),
+        };
+        let bound = if !kind.is_signed_int() {
+            positive_bound
+        } else if right_val.is_concrete() {
+            if right_val.to_i128()? < 0 { negative_bound } else { positive_bound }
+        } else {
+            let right_negative = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Lt, right_val, PrimVal::from_i128(0), kind);
+            self.memory.constraints.add_if_then_else(right_negative, kind, negative_bound, positive_bound)
+        };
+
+        let result = if overflowed.is_concrete() {
+            if overflowed.to_bool()? { bound } else { val }
+        } else {
+            self.memory.constraints.add_if_then_else(overflowed, kind, bound, val)
+        };
+        self.write_primval(dest, result, dest_ty)
+    }
+}
+
 macro_rules! overflow {
     ($op:ident, $l:expr, $r:expr) => ({
         let (val, overflowed) = $l.$op($r);
@@ -139,10 +229,46 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let usize = PrimValKind::from_uint_size(self.memory.pointer_size());
         let isize = PrimValKind::from_int_size(self.memory.pointer_size());
 
+        if left_kind == Ptr && right_kind == Ptr {
+            match (bin_op, left, right) {
+                // A pointer into an allocation is never null, nor at an address that
+                // was cast from an integer, even if its offset is symbolic.
+                (Eq, PrimVal::Ptr(_), PrimVal::Bytes(_)) | (Eq, PrimVal::Bytes(_), PrimVal::Ptr(_)) |
+                (Eq, PrimVal::Ptr(_), PrimVal::Abstract(_)) | (Eq, PrimVal::Abstract(_), PrimVal::Ptr(_)) => {
+                    return Ok((PrimVal::from_bool(false), PrimVal::from_bool(false)));
+                }
+                (Ne, PrimVal::Ptr(_), PrimVal::Bytes(_)) | (Ne, PrimVal::Bytes(_), PrimVal::Ptr(_)) |
+                (Ne, PrimVal::Ptr(_), PrimVal::Abstract(_)) | (Ne, PrimVal::Abstract(_), PrimVal::Ptr(_)) => {
+                    return Ok((PrimVal::from_bool(true), PrimVal::from_bool(false)));
+                }
+                // Addresses cast from symbolic integers compare like the integers, so that
+                // e.g. `is_null` forks on whether the integer is zero.
+                (_, PrimVal::Abstract(_), PrimVal::Bytes(_)) | (_, PrimVal::Bytes(_), PrimVal::Abstract(_)) |
+                (_, PrimVal::Abstract(_), PrimVal::Abstract(_)) => {
+                    let usize_ty = self.tcx.types.usize;
+                    return self.binary_op(bin_op, left, usize_ty, right, usize_ty);
+                }
+                _ => {}
+            }
+        }
+
         if !left.is_concrete() || !right.is_concrete() {
-            return self.abstract_binary_op(bin_op,
-                                           left, left_kind,
-                                           right, right_kind);
+            return match self.abstract_binary_op(bin_op, left, left_kind, right, right_kind) {
+                Err(EvalError::UnsupportedSymbolicOperation { op, ty }) => {
+                    if !self.concretize_on_unsupported {
+                        return Err(EvalError::UnsupportedSymbolicOperation { op, ty });
+                    }
+                    let concrete_left = self.concretize(left, left_kind, &op, &ty);
+                    let concrete_right = self.concretize(right, right_kind, &op, &ty);
+                    if concrete_left.is_none() && concrete_right.is_none() {
+                        return Err(EvalError::UnsupportedSymbolicOperation { op, ty });
+                    }
+                    self.binary_op(bin_op,
+                                   concrete_left.unwrap_or(left), left_ty,
+                                   concrete_right.unwrap_or(right), right_ty)
+                }
+                result => result,
+            };
         }
 
         match (left, right) {
@@ -294,6 +420,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             (Add, k) if k.is_int() => return int_arithmetic!(k, overflowing_add, l, r),
             (Sub, k) if k.is_int() => return int_arithmetic!(k, overflowing_sub, l, r),
             (Mul, k) if k.is_int() => return int_arithmetic!(k, overflowing_mul, l, r),
+            (Div, k) | (Rem, k) if k.is_int() && r == 0 => return Err(EvalError::DivisionByZero(None)),
             (Div, k) if k.is_int() => return int_arithmetic!(k, overflowing_div, l, r),
             (Rem, k) if k.is_int() => return int_arithmetic!(k, overflowing_rem, l, r),
 
@@ -360,18 +487,19 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         left: PrimVal,
         left_kind: PrimValKind,
         right: PrimVal,
-        mut right_kind: PrimValKind,
+        right_kind: PrimValKind,
     ) -> EvalResult<'tcx, (PrimVal, PrimVal)> {
 
         // These ops can have an RHS with a different numeric type.
         if bin_op == mir::BinOp::Shl || bin_op == mir::BinOp::Shr {
             match (left, right) {
-                (PrimVal::Abstract(abytes), PrimVal::Bytes(rn)) if rn % 8 == 0 => {
+                (PrimVal::Abstract(abytes), PrimVal::Bytes(rn))
+                    if rn % 8 == 0 && rn < left_kind.num_bytes() as u128 * 8 => {
                     let num_bytes = (rn / 8) as usize;
                     match bin_op {
                         mir::BinOp::Shl => {
                             let mut buffer = [SByte::Concrete(0); 8];
-                            for idx in num_bytes .. 8 {
+                            for idx in num_bytes .. left_kind.num_bytes() {
                                 buffer[idx] = abytes[idx - num_bytes];
                             }
                             return Ok((PrimVal::Abstract(buffer), PrimVal::from_bool(false)));
@@ -391,27 +519,53 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 _ => (),
             }
 
-            if right_kind.num_bytes() == left_kind.num_bytes() {
-                right_kind = left_kind;
-            } else if right_kind.num_bytes() < left_kind.num_bytes() {
-                right_kind = left_kind;
-            } else {
-                if let PrimVal::Bytes(n) = right {
-                    if n < 256 {
-                        // HACK
-                        right_kind = left_kind;
-                    } else {
-                        unimplemented!()
+            // Like the concrete shifts, mask the amount to the width of the left
+            // operand and report an overflow if the unmasked amount was too large.
+            let bits = left_kind.num_bytes() as u128 * 8;
+            let (amount, overflow) = match right {
+                PrimVal::Bytes(n) => (PrimVal::Bytes(n & (bits - 1)), PrimVal::from_bool(n >= bits)),
+                PrimVal::Abstract(mut sbytes) => {
+                    let amount_kind = PrimValKind::from_uint_size(right_kind.num_bytes() as u64);
+                    let overflow = self.memory.constraints.add_binop_constraint(
+                        mir::BinOp::Ge, right, PrimVal::Bytes(bits), amount_kind);
+                    // At most 64 bits, so the masked amount fits in the lowest byte.
+                    for idx in 1..8 {
+                        sbytes[idx] = SByte::Concrete(0);
                     }
-                } else {
-                    unimplemented!()
+                    let amount = self.memory.constraints.add_binop_constraint(
+                        mir::BinOp::BitAnd, PrimVal::Abstract(sbytes), PrimVal::Bytes(bits - 1), left_kind);
+                    (amount, overflow)
                 }
-            }
+                _ => {
+                    return Err(EvalError::UnsupportedSymbolicOperation {
+                        op: format!("{:?} by {:?}", bin_op, right),
+                        ty: format!("{:?}", left_kind),
+                    });
+                }
+            };
+            let res = self.memory.constraints.add_binop_constraint(bin_op, left, amount, left_kind);
+            return Ok((res, overflow));
         }
 
         if left_kind != right_kind {
-            let msg = format!("unimplemented binary op: {:?}, {:?}, {:?}", left, right, bin_op);
-            return Err(EvalError::Unimplemented(msg));
+            return Err(EvalError::UnsupportedSymbolicOperation {
+                op: format!("{:?} with a {:?} operand", bin_op, right_kind),
+                ty: format!("{:?}", left_kind),
+            });
+        }
+
+        if left_kind.is_float() {
+            match bin_op {
+                mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul | mir::BinOp::Div |
+                mir::BinOp::Eq | mir::BinOp::Ne |
+                mir::BinOp::Lt | mir::BinOp::Le | mir::BinOp::Gt | mir::BinOp::Ge => {}
+                _ => {
+                    return Err(EvalError::UnsupportedSymbolicOperation {
+                        op: format!("{:?}", bin_op),
+                        ty: format!("{:?}", left_kind),
+                    });
+                }
+            }
         }
 
         use value::PrimValKind::*;
@@ -483,6 +637,39 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 };
                 Ok((res, overflow))
             }
+            mir::BinOp::Mul => {
+                let res = self.memory.constraints.add_binop_constraint(bin_op, left, right, left_kind);
+                let overflow = match left_kind {
+                    U8 | U16 | U32 | U64 | U128 | I8 | I16 | I32 | I64 | I128 => {
+                        // The product overflowed iff dividing it by a nonzero left
+                        // does not give back right:
+                        // ```
+                        // left != 0 && res / left != right
+                        // ```
+                        let zero = PrimVal::from_u128(0);
+                        let left_nonzero = self.memory.constraints.add_binop_constraint(mir::BinOp::Ne, left, zero, left_kind);
+                        let quotient = self.memory.constraints.add_binop_constraint(mir::BinOp::Div, res, left, left_kind);
+                        let mismatch = self.memory.constraints.add_binop_constraint(mir::BinOp::Ne, quotient, right, left_kind);
+                        let overflow = self.memory.constraints.add_if_then_else(left_nonzero, Bool, mismatch, PrimVal::from_bool(false));
+                        if left_kind.is_signed_int() {
+                            // `MIN * -1` wraps to `MIN`, and so does `MIN / -1`,
+                            // so that case has to be checked separately.
+                            let bits = left_kind.num_bytes() * 8;
+                            let min = PrimVal::from_i128(-1i128 << (bits - 1));
+                            let minus_one = PrimVal::from_i128(-1);
+                            let left_minus_one = self.memory.constraints.add_binop_constraint(mir::BinOp::Eq, left, minus_one, left_kind);
+                            let right_min = self.memory.constraints.add_binop_constraint(mir::BinOp::Eq, right, min, left_kind);
+                            let negated_min = self.memory.constraints.add_if_then_else(left_minus_one, Bool, right_min, PrimVal::from_bool(false));
+                            self.memory.constraints.add_if_then_else(negated_min, Bool, PrimVal::from_bool(true), overflow)
+                        } else {
+                            overflow
+                        }
+                    }
+                    F32 | F64 => PrimVal::from_bool(false),
+                    Bool | Char | Ptr | FnPtr => unreachable!(),
+                };
+                Ok((res, overflow))
+            }
             _ => Ok((self.memory.constraints.add_binop_constraint(bin_op, left, right, left_kind), PrimVal::from_bool(false)))
         }
     }
@@ -498,15 +685,22 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         use rustc::mir::BinOp::*;
         use value::PrimValKind::*;
         if left.alloc_id != right.alloc_id {
-            if let Eq = bin_op {
-                unimplemented!()
-            } else {
-                unimplemented!()
+            // Pointers into different allocations are never equal, but can't be ordered.
+            match bin_op {
+                Eq => Ok((PrimVal::from_bool(false), PrimVal::from_bool(false))),
+                Ne => Ok((PrimVal::from_bool(true), PrimVal::from_bool(false))),
+                _ => Err(EvalError::InvalidPointerMath),
             }
         } else {
-            let result = self.memory.constraints.add_binop_constraint(
-                bin_op, left.offset.as_primval(), right.offset.as_primval(), U64);
-            Ok((result, PrimVal::from_bool(false)))
+            match bin_op {
+                // Within an allocation, pointers compare like their offsets.
+                Eq | Ne | Lt | Le | Gt | Ge | Sub => {
+                    let result = self.memory.constraints.add_binop_constraint(
+                        bin_op, left.offset.as_primval(), right.offset.as_primval(), U64);
+                    Ok((result, PrimVal::from_bool(false)))
+                }
+                _ => Err(EvalError::InvalidPointerMath),
+            }
         }
     }
 
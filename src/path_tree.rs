@@ -0,0 +1,93 @@
+//! Records the tree of forks that the explored paths went through.
+
+use std::fmt::Write;
+
+/// How the path that ends at a leaf of a `PathTree` finished.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathOutcome {
+    Completed,
+    Panicked,
+    Errored,
+    /// No branch of the path was feasible.
+    Pruned,
+}
+
+#[derive(Clone, Debug)]
+pub struct PathNode {
+    /// The node that the path forked from. `None` for the root.
+    pub parent: Option<usize>,
+    /// The constraints of the branch that leads from the parent to this node, as
+    /// printed by `Debug`.
+    pub constraints: Vec<String>,
+    /// The MIR location where the path forked again or failed, e.g. `main bb1[0]`.
+    /// `None` for paths that completed or are still queued.
+    pub location: Option<String>,
+    /// How the path ended, if the node is a leaf that has been explored.
+    pub outcome: Option<PathOutcome>,
+}
+
+/// The forks of all paths explored so far, as returned by `ExecutionConfig::path_tree`.
+/// Paths that do not fork stay at the same node, so every inner node is a fork.
+#[derive(Clone, Debug, Default)]
+pub struct PathTree {
+    /// The nodes in the order they were created. Node 0 is the start of the entry
+    /// function and the parent of a node always comes before the node.
+    pub nodes: Vec<PathNode>,
+}
+
+impl PathTree {
+    pub(crate) fn add_node(&mut self, parent: Option<usize>, constraints: Vec<String>) -> usize {
+        self.nodes.push(PathNode {
+            parent,
+            constraints,
+            location: None,
+            outcome: None,
+        });
+        self.nodes.len() - 1
+    }
+
+    pub(crate) fn set_location(&mut self, node: usize, location: Option<String>) {
+        self.nodes[node].location = location;
+    }
+
+    pub(crate) fn finish(&mut self, node: usize, location: Option<String>, outcome: PathOutcome) {
+        self.nodes[node].location = location;
+        self.nodes[node].outcome = Some(outcome);
+    }
+
+    /// Formats the tree as a graphviz digraph. Each node is labeled with its location,
+    /// each edge with the constraints of its branch. Explored leaves are drawn as boxes
+    /// that are colored by their outcome.
+    pub fn export_dot(&self) -> String {
+        let mut dot = String::from("digraph paths {\n");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let mut label = node.location.clone().unwrap_or_default();
+            let style = match node.outcome {
+                None => "",
+                Some(PathOutcome::Completed) => ", shape=box, color=green",
+                Some(PathOutcome::Panicked) => ", shape=box, color=red",
+                Some(PathOutcome::Errored) => ", shape=box, color=orange",
+                Some(PathOutcome::Pruned) => ", shape=box, color=gray, style=dashed",
+            };
+            if let Some(outcome) = node.outcome {
+                if !label.is_empty() {
+                    label.push_str("\n");
+                }
+                write!(label, "{:?}", outcome).unwrap();
+            }
+            writeln!(dot, "    n{} [label=\"{}\"{}];", idx, escape(&label), style).unwrap();
+        }
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                writeln!(dot, "    n{} -> n{} [label=\"{}\"];",
+                         parent, idx, escape(&node.constraints.join("\n"))).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
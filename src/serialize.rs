@@ -0,0 +1,134 @@
+//! Serde support for the rustc types that appear in `StaticEvalError`. Spans are stored as
+//! their byte positions, which are only meaningful together with the codemap of the session
+//! that produced them.
+
+use rustc::hir::def_id::{CrateNum, DefId, DefIndex};
+use rustc::mir::BinOp;
+use rustc_const_math::{ConstMathErr, Op};
+use rustc_data_structures::indexed_vec::Idx;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use syntax::ast::{IntTy, UintTy};
+use syntax::codemap::{BytePos, Span, NO_EXPANSION};
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "BinOp")]
+pub(crate) enum BinOpDef {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitXor,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Lt,
+    Le,
+    Ne,
+    Ge,
+    Gt,
+    Offset,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Op")]
+enum OpDef {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shr,
+    Shl,
+    Neg,
+    BitAnd,
+    BitOr,
+    BitXor,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "IntTy")]
+enum IntTyDef {
+    Isize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "UintTy")]
+enum UintTyDef {
+    Usize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ConstMathErr")]
+pub(crate) enum ConstMathErrDef {
+    NotInRange,
+    CmpBetweenUnequalTypes,
+    UnequalTypes(#[serde(with = "OpDef")] Op),
+    Overflow(#[serde(with = "OpDef")] Op),
+    ShiftNegative,
+    DivisionByZero,
+    RemainderByZero,
+    UnsignedNegation,
+    ULitOutOfRange(#[serde(with = "UintTyDef")] UintTy),
+    LitOutOfRange(#[serde(with = "IntTyDef")] IntTy),
+}
+
+pub(crate) mod span {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(span: &Span, serializer: S) -> Result<S::Ok, S::Error> {
+        (span.lo().0, span.hi().0).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Span, D::Error> {
+        let (lo, hi) = <(u32, u32)>::deserialize(deserializer)?;
+        Ok(Span::new(BytePos(lo), BytePos(hi), NO_EXPANSION))
+    }
+}
+
+pub(crate) mod option_span {
+    use super::*;
+
+    pub(crate) fn serialize<S>(span: &Option<Span>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        span.map(|span| (span.lo().0, span.hi().0)).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Span>, D::Error>
+        where D: Deserializer<'de>
+    {
+        let span = Option::<(u32, u32)>::deserialize(deserializer)?;
+        Ok(span.map(|(lo, hi)| Span::new(BytePos(lo), BytePos(hi), NO_EXPANSION)))
+    }
+}
+
+/// A `DefId` is stored as its crate number and index, so it can only be looked up again in
+/// the session it was created in.
+pub(crate) mod def_id {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(def_id: &DefId, serializer: S) -> Result<S::Ok, S::Error> {
+        (def_id.krate.index() as u32, def_id.index.as_raw_u32()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DefId, D::Error> {
+        let (krate, index) = <(u32, u32)>::deserialize(deserializer)?;
+        Ok(DefId {
+            krate: CrateNum::new(krate as usize),
+            index: DefIndex::from_raw_u32(index),
+        })
+    }
+}
@@ -0,0 +1,1197 @@
+//! The interface between the constraint context and the SMT solver deciding
+//! the path constraints, along with the default backend built on Z3.
+
+use rustc::mir;
+use z3;
+use z3_sys;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::num;
+use std::rc::Rc;
+use std::time::Instant;
+
+use constraints::{Constraint, NumericIntrinsic, VarOrigin, VarType};
+use memory::SByte;
+use value::{PrimVal, PrimValKind};
+
+/// The answer to a satisfiability query.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SatResult {
+    Sat,
+    Unsat,
+    /// The solver gave up, e.g. because it ran out of time.
+    Unknown,
+}
+
+/// A satisfiability query: the path constraints over a set of variables.
+#[derive(Clone, Copy, Debug)]
+pub struct ConstraintSet<'c> {
+    /// The sort and origin of each variable, indexed by variable id.
+    pub variables: &'c [(VarType, VarOrigin)],
+
+    /// The path constraints.
+    pub constraints: &'c [Constraint],
+
+    /// Constraints that are assumed for this query only.
+    pub extra_constraints: &'c [Constraint],
+
+    /// Time budget for the query, in milliseconds.
+    pub timeout_ms: Option<u64>,
+}
+
+impl<'c> ConstraintSet<'c> {
+    /// All the constraints of the query.
+    pub fn iter(&self) -> ::std::iter::Chain<::std::slice::Iter<'c, Constraint>, ::std::slice::Iter<'c, Constraint>> {
+        self.constraints.iter().chain(self.extra_constraints.iter())
+    }
+
+    /// Simplifies the query before it is handed to a backend: comparisons of concrete
+    /// values are folded, comparisons that trivially hold are dropped, and so are repeated
+    /// constraints. Returns the remaining constraints, which are satisfied by the same
+    /// assignments, or `None` if some comparison never holds.
+    pub fn simplified(&self) -> Option<Vec<Constraint>> {
+        let mut seen = HashSet::new();
+        let mut simplified = Vec::new();
+        for &c in self.iter() {
+            if let Constraint::Compare { op, kind, lhs, rhs } = c {
+                match fold_compare(op, kind, lhs, rhs) {
+                    Some(true) => continue,
+                    Some(false) => return None,
+                    None => {}
+                }
+            }
+            if seen.insert(c) {
+                simplified.push(c);
+            }
+        }
+        Some(simplified)
+    }
+
+    /// Renders the query as an SMT-LIB 2 script, for handing it to another solver
+    /// or keeping it around for debugging. Variable `n` is declared as `vn`, and
+    /// each constraint becomes one `assert`. The timeout is not included.
+    pub fn to_smtlib(&self) -> String {
+        let mut script = self.smtlib_assertions();
+        script.push_str("(check-sat)\n");
+        script
+    }
+
+    /// The declarations and assertions of `to_smtlib()`, without any commands.
+    fn smtlib_assertions(&self) -> String {
+        let mut script = String::new();
+        for (idx, &(var_type, _)) in self.variables.iter().enumerate() {
+            script.push_str(&smtlib_declaration(idx, var_type));
+        }
+        for c in self.iter() {
+            script.push_str(&format!("(assert {})\n", smtlib_constraint(*c)));
+        }
+        script
+    }
+}
+
+/// Decides the comparison `lhs op rhs` without the solver, if its operands are concrete
+/// or the same variables.
+fn fold_compare(op: mir::BinOp, kind: PrimValKind, lhs: PrimVal, rhs: PrimVal) -> Option<bool> {
+    use rustc::mir::BinOp::*;
+    let bits = match kind {
+        PrimValKind::Bool | PrimValKind::I8 | PrimValKind::U8 => 8,
+        PrimValKind::I16 | PrimValKind::U16 => 16,
+        PrimValKind::I32 | PrimValKind::U32 | PrimValKind::Char => 32,
+        PrimValKind::I64 | PrimValKind::U64 => 64,
+        PrimValKind::I128 | PrimValKind::U128 => 128,
+        // A NaN differs from itself.
+        _ => return None,
+    };
+    let ordering = match (lhs, rhs) {
+        (PrimVal::Bytes(l), PrimVal::Bytes(r)) => {
+            let shift = 128 - bits;
+            if kind.is_signed_int() {
+                (((l << shift) as i128) >> shift).cmp(&(((r << shift) as i128) >> shift))
+            } else {
+                (l << shift).cmp(&(r << shift))
+            }
+        }
+        (PrimVal::Abstract(l), PrimVal::Abstract(r)) if l == r => ::std::cmp::Ordering::Equal,
+        _ => return None,
+    };
+    match op {
+        Eq => Some(ordering == ::std::cmp::Ordering::Equal),
+        Ne => Some(ordering != ::std::cmp::Ordering::Equal),
+        Lt => Some(ordering == ::std::cmp::Ordering::Less),
+        Le => Some(ordering != ::std::cmp::Ordering::Greater),
+        Gt => Some(ordering == ::std::cmp::Ordering::Greater),
+        Ge => Some(ordering != ::std::cmp::Ordering::Less),
+        _ => None,
+    }
+}
+
+/// A satisfying assignment of a `ConstraintSet`.
+#[derive(Clone, Debug)]
+pub struct Model {
+    /// The value of each `Bool` (as 0 or 1) and `BitVec8` variable, indexed by
+    /// variable id. Array variables and unassigned variables are `None`.
+    pub values: Vec<Option<u8>>,
+}
+
+impl Model {
+    /// Evaluates `primval` under this assignment. Unassigned variables are taken to be zero.
+    pub fn eval(&self, primval: PrimVal, kind: PrimValKind) -> Option<u128> {
+        let sbytes = match primval {
+            PrimVal::Bytes(n) => return Some(n),
+            PrimVal::Abstract(sbytes) => sbytes,
+            PrimVal::Ptr(_) | PrimVal::Undef => return None,
+        };
+
+        let num_bytes = match kind {
+            PrimValKind::Bool => 1,
+            _ => kind.num_bytes(),
+        };
+        let mut result = 0;
+        for (idx, sbyte) in sbytes[..num_bytes].iter().enumerate() {
+            let byte = match *sbyte {
+                SByte::Concrete(b) => b,
+                SByte::Abstract(v) => self.values.get(v.0 as usize).and_then(|&b| b).unwrap_or(0),
+            };
+            result |= (byte as u128) << (8 * idx);
+        }
+        Some(result)
+    }
+}
+
+/// Decides satisfiability of path constraints.
+pub trait SolverBackend: fmt::Debug {
+    fn check_sat(&self, constraints: &ConstraintSet) -> SatResult;
+
+    /// Returns a satisfying assignment, or `None` if there is none or the
+    /// solver gave up.
+    fn get_model(&self, constraints: &ConstraintSet) -> Option<Model>;
+}
+
+/// Wraps a backend, remembering the answers to the queries it has already seen.
+///
+/// Sibling paths share the constraints of their common ancestor, so many queries
+/// are repeated. Queries are keyed by their constraints, sorted by hash so that a set
+/// pushed in a different order is still recognized. As the key holds every constraint
+/// of the query, an entry never goes stale. Models are also keyed by the number of
+/// variables, as they hold a value for each of them. Unknown results are not cached.
+#[derive(Debug)]
+pub struct CachingBackend<B> {
+    inner: B,
+    sat_results: RefCell<HashMap<Vec<Constraint>, SatResult>>,
+    models: RefCell<HashMap<(usize, Vec<Constraint>), Model>>,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+}
+
+impl<B: SolverBackend> CachingBackend<B> {
+    pub fn new(inner: B) -> Self {
+        CachingBackend {
+            inner,
+            sat_results: RefCell::new(HashMap::new()),
+            models: RefCell::new(HashMap::new()),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    /// Number of queries answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.get()
+    }
+
+    /// Number of queries passed on to the wrapped backend.
+    pub fn misses(&self) -> usize {
+        self.misses.get()
+    }
+
+    fn key(constraints: &ConstraintSet) -> Vec<Constraint> {
+        let mut key: Vec<Constraint> = constraints.iter().cloned().collect();
+        key.sort_by_key(|c| {
+            let mut hasher = DefaultHasher::new();
+            c.hash(&mut hasher);
+            hasher.finish()
+        });
+        key.dedup();
+        key
+    }
+}
+
+impl<B: SolverBackend> SolverBackend for CachingBackend<B> {
+    fn check_sat(&self, constraints: &ConstraintSet) -> SatResult {
+        let key = Self::key(constraints);
+        if let Some(&result) = self.sat_results.borrow().get(&key) {
+            self.hits.set(self.hits.get() + 1);
+            return result;
+        }
+        if self.models.borrow().contains_key(&(constraints.variables.len(), key.clone())) {
+            self.hits.set(self.hits.get() + 1);
+            return SatResult::Sat;
+        }
+
+        self.misses.set(self.misses.get() + 1);
+        let result = self.inner.check_sat(constraints);
+        if result != SatResult::Unknown {
+            self.sat_results.borrow_mut().insert(key, result);
+        }
+        result
+    }
+
+    fn get_model(&self, constraints: &ConstraintSet) -> Option<Model> {
+        let key = Self::key(constraints);
+        if let Some(&SatResult::Unsat) = self.sat_results.borrow().get(&key) {
+            self.hits.set(self.hits.get() + 1);
+            return None;
+        }
+        let key = (constraints.variables.len(), key);
+        if let Some(model) = self.models.borrow().get(&key) {
+            self.hits.set(self.hits.get() + 1);
+            return Some(model.clone());
+        }
+
+        self.misses.set(self.misses.get() + 1);
+        let model = self.inner.get_model(constraints);
+        if let Some(ref model) = model {
+            self.models.borrow_mut().insert(key, model.clone());
+        }
+        model
+    }
+}
+
+impl<B: SolverBackend> SolverBackend for Rc<B> {
+    fn check_sat(&self, constraints: &ConstraintSet) -> SatResult {
+        (**self).check_sat(constraints)
+    }
+
+    fn get_model(&self, constraints: &ConstraintSet) -> Option<Model> {
+        (**self).get_model(constraints)
+    }
+}
+
+/// Decides each query with a fresh Z3 solver.
+#[derive(Clone, Copy, Debug)]
+pub struct Z3Backend;
+
+impl SolverBackend for Z3Backend {
+    fn check_sat(&self, constraints: &ConstraintSet) -> SatResult {
+        if uses_floats(constraints) {
+            return smtlib_query(constraints, false).0;
+        }
+
+        let cfg = self.config(constraints);
+        let ctx = z3::Context::new(&cfg);
+        let solver = z3::Solver::new(&ctx);
+
+        for c in constraints.iter() {
+            solver.assert(&self.constraint_to_ast(&ctx, *c));
+        }
+
+        let start = Instant::now();
+        if solver.check() {
+            SatResult::Sat
+        } else if timed_out(start, constraints.timeout_ms) {
+            // z3 reports an unknown result the same way as an unsatisfiable one.
+            SatResult::Unknown
+        } else {
+            SatResult::Unsat
+        }
+    }
+
+    fn get_model(&self, constraints: &ConstraintSet) -> Option<Model> {
+        if uses_floats(constraints) {
+            return smtlib_query(constraints, true).1;
+        }
+
+        let cfg = self.config(constraints);
+        let ctx = z3::Context::new(&cfg);
+        let solver = z3::Solver::new(&ctx);
+
+        for c in constraints.iter() {
+            solver.assert(&self.constraint_to_ast(&ctx, *c));
+        }
+
+        if !solver.check() {
+            return None;
+        }
+        let model = solver.get_model();
+
+        let mut values = Vec::with_capacity(constraints.variables.len());
+        for (idx, &(var_type, _)) in constraints.variables.iter().enumerate() {
+            let value = match var_type {
+                VarType::Bool => {
+                    model.eval(&ctx.numbered_bool_const(idx as u32))
+                        .and_then(|v| v.as_bool())
+                        .map(|b| b as u8)
+                }
+                VarType::BitVec8 => {
+                    model.eval(&ctx.numbered_bitvector_const(idx as u32, 8))
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as u8)
+                }
+                VarType::Array => None,
+            };
+            values.push(value);
+        }
+
+        Some(Model { values })
+    }
+}
+
+/// Decides queries with one Z3 solver that is kept from query to query. Consecutive
+/// queries mostly come from a path and the paths forked from it, so they share a prefix
+/// of their constraints. Each constraint is asserted in a scope of its own, and only the
+/// constraints after the prefix shared with the previous query are popped and asserted
+/// anew. The constraints are handed to Z3 in their SMT-LIB rendering, so floats are
+/// supported too.
+#[derive(Debug, Default)]
+pub struct IncrementalBackend {
+    solver: RefCell<Option<IncrementalSolver>>,
+    reused: Cell<usize>,
+    asserted: Cell<usize>,
+}
+
+#[derive(Debug)]
+struct IncrementalSolver {
+    ctx: z3_sys::Z3_context,
+    solver: z3_sys::Z3_solver,
+    /// The asserted constraints. The one at index `n` is in scope `n + 1`.
+    asserted: Vec<Constraint>,
+}
+
+impl IncrementalBackend {
+    pub fn new() -> Self {
+        IncrementalBackend::default()
+    }
+
+    /// Number of constraints that queries found already asserted by an earlier query.
+    pub fn reused_constraints(&self) -> usize {
+        self.reused.get()
+    }
+
+    /// Number of constraints that had to be asserted.
+    pub fn asserted_constraints(&self) -> usize {
+        self.asserted.get()
+    }
+
+    fn query(&self, constraints: &ConstraintSet, want_model: bool) -> (SatResult, Option<Model>) {
+        use z3_sys::*;
+
+        let mut solver = self.solver.borrow_mut();
+        if solver.is_none() {
+            *solver = Some(IncrementalSolver::new());
+        }
+        let solver = solver.as_mut().unwrap();
+
+        let query: Vec<Constraint> = constraints.iter().cloned().collect();
+        let shared = solver.asserted.iter().zip(&query).take_while(|&(a, b)| a == b).count();
+        self.reused.set(self.reused.get() + shared);
+        self.asserted.set(self.asserted.get() + query.len() - shared);
+        unsafe {
+            solver.pop(shared);
+            for &c in &query[shared..] {
+                solver.push(c, constraints.variables);
+            }
+
+            let params = Z3_mk_params(solver.ctx);
+            Z3_params_inc_ref(solver.ctx, params);
+            let key = ::std::ffi::CString::new("timeout").unwrap();
+            // The default timeout of z3 is the largest one.
+            let timeout_ms = constraints.timeout_ms.map_or(u32::max_value() as u64, |t| t);
+            Z3_params_set_uint(solver.ctx, params, Z3_mk_string_symbol(solver.ctx, key.as_ptr()),
+                               ::std::cmp::min(timeout_ms, u32::max_value() as u64) as u32);
+            Z3_solver_set_params(solver.ctx, solver.solver, params);
+            Z3_params_dec_ref(solver.ctx, params);
+
+            let result = match Z3_solver_check(solver.ctx, solver.solver) {
+                Z3_L_TRUE => SatResult::Sat,
+                Z3_L_FALSE => SatResult::Unsat,
+                _ => SatResult::Unknown,
+            };
+            let model = if want_model && result == SatResult::Sat {
+                Some(smtlib_model(solver.ctx, solver.solver, constraints.variables))
+            } else {
+                None
+            };
+            (result, model)
+        }
+    }
+}
+
+impl IncrementalSolver {
+    fn new() -> Self {
+        use z3_sys::*;
+        unsafe {
+            let cfg = Z3_mk_config();
+            let ctx = Z3_mk_context(cfg);
+            Z3_del_config(cfg);
+            // The default handler exits the process.
+            Z3_set_error_handler(ctx, ignore_z3_error);
+            let solver = Z3_mk_solver(ctx);
+            Z3_solver_inc_ref(ctx, solver);
+            IncrementalSolver { ctx, solver, asserted: Vec::new() }
+        }
+    }
+
+    /// Pops the scopes of all but the first `keep` constraints.
+    unsafe fn pop(&mut self, keep: usize) {
+        let scopes = self.asserted.len() - keep;
+        if scopes > 0 {
+            z3_sys::Z3_solver_pop(self.ctx, self.solver, scopes as u32);
+            self.asserted.truncate(keep);
+        }
+    }
+
+    /// Asserts `constraint` in a new scope.
+    unsafe fn push(&mut self, constraint: Constraint, variables: &[(VarType, VarOrigin)]) {
+        use std::ffi::CString;
+        use std::ptr;
+        use z3_sys::*;
+
+        // Constants with the same name and sort are the same in every script, so each
+        // script only declares the variables of its constraint.
+        let mut vars = Vec::new();
+        constraint_vars(constraint, &mut vars);
+        vars.sort();
+        vars.dedup();
+        let mut script = String::new();
+        for idx in vars {
+            script.push_str(&smtlib_declaration(idx, variables[idx].0));
+        }
+        script.push_str(&format!("(assert {})\n", smtlib_constraint(constraint)));
+
+        let script = CString::new(script).expect("SMT-LIB contains no NUL bytes");
+        let formula = Z3_parse_smtlib2_string(
+            self.ctx, script.as_ptr(), 0, ptr::null(), ptr::null(), 0, ptr::null(), ptr::null());
+        if Z3_get_error_code(self.ctx) != Z3_OK {
+            bug!("z3 rejected the SMT-LIB rendering of a constraint:\n{}", script.to_string_lossy());
+        }
+        Z3_solver_push(self.ctx, self.solver);
+        Z3_solver_assert(self.ctx, self.solver, formula);
+        self.asserted.push(constraint);
+    }
+}
+
+impl Drop for IncrementalSolver {
+    fn drop(&mut self) {
+        unsafe {
+            z3_sys::Z3_solver_dec_ref(self.ctx, self.solver);
+            z3_sys::Z3_del_context(self.ctx);
+        }
+    }
+}
+
+impl SolverBackend for IncrementalBackend {
+    fn check_sat(&self, constraints: &ConstraintSet) -> SatResult {
+        self.query(constraints, false).0
+    }
+
+    fn get_model(&self, constraints: &ConstraintSet) -> Option<Model> {
+        self.query(constraints, true).1
+    }
+}
+
+/// Returns true if some constraint does floating-point arithmetic or comparisons.
+fn uses_floats(constraints: &ConstraintSet) -> bool {
+    constraints.iter().any(|c| match *c {
+        Constraint::Binop { kind, .. } |
+        Constraint::Unop { kind, .. } |
+        Constraint::Compare { kind, .. } |
+        Constraint::IfThenElse { kind, .. } => kind.is_float(),
+        _ => false,
+    })
+}
+
+extern "C" fn ignore_z3_error(_ctx: z3_sys::Z3_context, _error: z3_sys::Z3_error_code) {}
+
+/// Decides `constraints` by handing their SMT-LIB rendering to Z3. The z3 bindings do
+/// not cover the floating-point theory, so this is how `Z3Backend` decides queries that
+/// involve floats. A model is only extracted if `want_model` is set.
+fn smtlib_query(constraints: &ConstraintSet, want_model: bool) -> (SatResult, Option<Model>) {
+    use std::ffi::CString;
+    use std::ptr;
+    use z3_sys::*;
+
+    let script = CString::new(constraints.smtlib_assertions()).expect("SMT-LIB contains no NUL bytes");
+    unsafe {
+        let cfg = Z3_mk_config();
+        if let Some(timeout_ms) = constraints.timeout_ms {
+            let key = CString::new("timeout").unwrap();
+            let value = CString::new(timeout_ms.to_string()).unwrap();
+            Z3_set_param_value(cfg, key.as_ptr(), value.as_ptr());
+        }
+        let ctx = Z3_mk_context(cfg);
+        Z3_del_config(cfg);
+        // The default handler exits the process.
+        Z3_set_error_handler(ctx, ignore_z3_error);
+
+        let formula = Z3_parse_smtlib2_string(
+            ctx, script.as_ptr(), 0, ptr::null(), ptr::null(), 0, ptr::null(), ptr::null());
+        if Z3_get_error_code(ctx) != Z3_OK {
+            Z3_del_context(ctx);
+            bug!("z3 rejected the SMT-LIB rendering of a query:\n{}", script.to_string_lossy());
+        }
+
+        let solver = Z3_mk_solver(ctx);
+        Z3_solver_inc_ref(ctx, solver);
+        Z3_solver_assert(ctx, solver, formula);
+        let result = match Z3_solver_check(ctx, solver) {
+            Z3_L_TRUE => SatResult::Sat,
+            Z3_L_FALSE => SatResult::Unsat,
+            _ => SatResult::Unknown,
+        };
+
+        let model = if want_model && result == SatResult::Sat {
+            Some(smtlib_model(ctx, solver, constraints.variables))
+        } else {
+            None
+        };
+
+        Z3_solver_dec_ref(ctx, solver);
+        Z3_del_context(ctx);
+        (result, model)
+    }
+}
+
+/// Reads the values of `variables` from the model of `solver`, whose last check
+/// was satisfiable. The variables are looked up by their SMT-LIB names.
+unsafe fn smtlib_model(
+    ctx: z3_sys::Z3_context,
+    solver: z3_sys::Z3_solver,
+    variables: &[(VarType, VarOrigin)],
+) -> Model {
+    use std::ffi::CString;
+    use std::ptr;
+    use z3_sys::*;
+
+    let model = Z3_solver_get_model(ctx, solver);
+    Z3_model_inc_ref(ctx, model);
+    let mut values = Vec::with_capacity(variables.len());
+    for (idx, &(var_type, _)) in variables.iter().enumerate() {
+        let sort = match var_type {
+            VarType::Bool => Z3_mk_bool_sort(ctx),
+            VarType::BitVec8 => Z3_mk_bv_sort(ctx, 8),
+            VarType::Array => {
+                values.push(None);
+                continue;
+            }
+        };
+        let name = CString::new(format!("v{}", idx)).unwrap();
+        let var = Z3_mk_const(ctx, Z3_mk_string_symbol(ctx, name.as_ptr()), sort);
+        let mut value = ptr::null_mut();
+        if Z3_model_eval(ctx, model, var, 1, &mut value) == 0 {
+            values.push(None);
+            continue;
+        }
+        values.push(match var_type {
+            VarType::Bool => match Z3_get_bool_value(ctx, value) {
+                Z3_L_TRUE => Some(1),
+                Z3_L_FALSE => Some(0),
+                _ => None,
+            },
+            _ => {
+                let mut n = 0;
+                if Z3_get_numeral_uint64(ctx, value, &mut n) != 0 {
+                    Some(n as u8)
+                } else {
+                    None
+                }
+            }
+        });
+    }
+    Z3_model_dec_ref(ctx, model);
+    Model { values }
+}
+
+fn timed_out(start: Instant, timeout_ms: Option<u64>) -> bool {
+    let elapsed = start.elapsed();
+    let elapsed_ms = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1_000_000) as u64;
+    timeout_ms.map_or(false, |timeout_ms| elapsed_ms >= timeout_ms)
+}
+
+impl Z3Backend {
+    fn config(&self, constraints: &ConstraintSet) -> z3::Config {
+        let mut cfg = z3::Config::new();
+        if let Some(timeout_ms) = constraints.timeout_ms {
+            cfg.set_timeout_msec(timeout_ms);
+        }
+        cfg
+    }
+
+    fn sbyte_to_ast<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        sbyte: SByte)
+        -> z3::Ast<'a>
+    {
+        match sbyte {
+            SByte::Abstract(b) => {
+                ctx.numbered_bitvector_const(b.0, 8)
+            }
+            SByte::Concrete(b) => {
+                z3::Ast::bv_from_u64(&ctx, b as u64, 8)
+            }
+        }
+    }
+
+    fn sbyte_slice_to_ast<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        sbytes: &[SByte])
+        -> z3::Ast<'a>
+    {
+        if sbytes.is_empty() {
+            panic!("expected non-empty sbyte slice");
+        } else if sbytes.len() == 1 {
+            self.sbyte_to_ast(ctx, sbytes[0])
+        } else {
+            let mut result = self.sbyte_to_ast(ctx, sbytes[0]);
+            for sbyte in &sbytes[1..] {
+                result = self.sbyte_to_ast(ctx, *sbyte).concat(&result);
+            }
+
+            result
+        }
+    }
+
+    fn constraint_to_ast<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        constraint: Constraint)
+        -> z3::Ast<'a>
+    {
+        match constraint {
+            Constraint::Binop { operator, kind, lhs, rhs_operand1,
+                                rhs_operand2, lhs_kind } => {
+                self.primval_to_ast(&ctx, lhs, lhs_kind)._eq(
+                    &self.mir_binop_to_ast(
+                        &ctx,
+                        operator,
+                        self.primval_to_ast(&ctx, rhs_operand1, kind),
+                        self.primval_to_ast(&ctx, rhs_operand2, kind),
+                        kind))
+            }
+            Constraint::Unop { operator, kind, lhs, operand, .. } => {
+                self.primval_to_ast(&ctx, lhs, kind)._eq(
+                    &self.mir_unop_to_ast(
+                        &ctx,
+                        operator,
+                        self.primval_to_ast(&ctx, operand, kind)))
+            }
+
+            Constraint::NumericIntrinsic { operator, kind, lhs, operand, .. } => {
+                self.primval_to_ast(&ctx, lhs, kind)._eq(
+                    &self.mir_intrinsic_to_ast(
+                        &ctx,
+                        operator,
+                        self.primval_to_ast(&ctx, operand, kind),
+                        kind))
+            }
+
+            Constraint::Compare { op, lhs, rhs, kind, .. } => {
+                self.mir_binop_to_ast(
+                    &ctx,
+                    op,
+                    self.primval_to_ast(&ctx, lhs, kind),
+                    self.primval_to_ast(&ctx, rhs, kind),
+                    kind)
+            }
+
+            Constraint::IfThenElse { discriminant, kind, then_branch, else_branch, lhs } => {
+                self.primval_to_ast(&ctx, lhs, kind)._eq(
+                    &self.primval_to_ast(&ctx, discriminant, PrimValKind::Bool).ite(
+                        &self.primval_to_ast(&ctx, then_branch, kind),
+                        &self.primval_to_ast(&ctx, else_branch, kind)))
+
+            }
+
+            Constraint::ArrayElement { array, index, value, } => {
+                let c = ::z3::Ast::new_const(
+                    &::z3::Symbol::from_int(ctx, array.0),
+                    &ctx.array_sort(
+                        &ctx.bitvector_sort(64),
+                        &ctx.bitvector_sort(8)));
+
+                c.select(&self.primval_to_ast(ctx, index, PrimValKind::U64))._eq(
+                    &self.sbyte_to_ast(ctx, value))
+            }
+
+            Constraint::ArrayStore { array, index, value, lhs } => {
+                let c0 = ::z3::Ast::new_const(
+                    &::z3::Symbol::from_int(ctx, array.0),
+                    &ctx.array_sort(
+                        &ctx.bitvector_sort(64),
+                        &ctx.bitvector_sort(8)));
+
+                let c1 = ::z3::Ast::new_const(
+                    &::z3::Symbol::from_int(ctx, lhs.0),
+                    &ctx.array_sort(
+                        &ctx.bitvector_sort(64),
+                        &ctx.bitvector_sort(8)));
+
+                c1._eq(
+                    &c0.store(
+                        &self.primval_to_ast(ctx, index, PrimValKind::U64),
+                        &self.sbyte_to_ast(ctx, value)))
+            }
+
+        }
+    }
+
+    fn primval_to_ast<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        primval: PrimVal,
+        kind: PrimValKind)
+        -> z3::Ast<'a>
+    {
+        match primval {
+            PrimVal::Undef => {
+                unimplemented!()
+            }
+            PrimVal::Ptr(_) => {
+                unimplemented!()
+            }
+            PrimVal::Abstract(sbytes) => {
+                if let PrimValKind::Bool = kind {
+                    match sbytes[0] {
+                        SByte::Abstract(b) => {
+                            ctx.numbered_bool_const(b.0)
+                        }
+                        SByte::Concrete(_b) => {
+                            unimplemented!()
+                        }
+                    }
+                } else {
+                    let num_bytes = kind.num_bytes();
+                    self.sbyte_slice_to_ast(ctx, &sbytes[..num_bytes])
+                }
+            }
+            PrimVal::Bytes(v) => {
+                match kind {
+                    PrimValKind::Bool => z3::Ast::from_bool(&ctx, v != 0),
+                    PrimValKind::U8 | PrimValKind::I8 => z3::Ast::bv_from_u64(&ctx, v as u64, 8),
+                    PrimValKind::U16 | PrimValKind::I16 => z3::Ast::bv_from_u64(&ctx, v as u64, 16),
+                    PrimValKind::U32 | PrimValKind::I32 => z3::Ast::bv_from_u64(&ctx, v as u64, 32),
+                    PrimValKind::U64 | PrimValKind::I64 => z3::Ast::bv_from_u64(&ctx, v as u64, 64),
+
+                    PrimValKind::Char => z3::Ast::bv_from_u64(&ctx, v as u64, 32),
+
+                    _ => {
+                        unimplemented!()
+                    }
+                }
+            }
+        }
+    }
+
+    fn mir_binop_to_ast<'a>(
+        &self,
+        _ctx: &'a z3::Context,
+        operator: mir::BinOp,
+        left: z3::Ast<'a>,
+        right: z3::Ast<'a>,
+        kind: PrimValKind)
+        -> z3::Ast<'a>
+    {
+        match (operator, kind) {
+            (mir::BinOp::Eq, _) => left._eq(&right),
+            (mir::BinOp::Ne, _) => left._eq(&right).not(),
+
+            (mir::BinOp::Lt, kind) if kind.is_signed_int() => left.bvslt(&right),
+            (mir::BinOp::Lt, _) => left.bvult(&right),
+
+            (mir::BinOp::Le, kind) if kind.is_signed_int() => left.bvsle(&right),
+            (mir::BinOp::Le, _) => left.bvule(&right),
+
+            (mir::BinOp::Gt, kind) if kind.is_signed_int() => left.bvsgt(&right),
+            (mir::BinOp::Gt, _) => left.bvugt(&right),
+
+            (mir::BinOp::Ge, kind) if kind.is_signed_int() => left.bvsge(&right),
+            (mir::BinOp::Ge, _) => left.bvuge(&right),
+
+            (mir::BinOp::Add, _) => left.bvadd(&right),
+            (mir::BinOp::Sub, _) => left.bvsub(&right),
+
+            (mir::BinOp::BitXor, PrimValKind::Bool) => left.xor(&right),
+            (mir::BinOp::BitXor, _) => left.bvxor(&right),
+
+            (mir::BinOp::BitAnd, PrimValKind::Bool) => left.and(&[&right]),
+            (mir::BinOp::BitAnd, _) => left.bvand(&right),
+
+            (mir::BinOp::BitOr, PrimValKind::Bool) => left.or(&[&right]),
+            (mir::BinOp::BitOr, _) => left.bvor(&right),
+
+            (mir::BinOp::Mul, _) => left.bvmul(&right),
+            (mir::BinOp::Shl, _) => left.bvshl(&right),
+
+            (mir::BinOp::Shr, kind) if kind.is_signed_int() => left.bvashr(&right),
+            (mir::BinOp::Shr, _) => left.bvlshr(&right),
+
+            (mir::BinOp::Div, kind) if kind.is_signed_int() => left.bvsdiv(&right),
+            (mir::BinOp::Div, _) => left.bvudiv(&right),
+
+            (mir::BinOp::Rem, kind) if kind.is_signed_int() => left.bvsrem(&right),
+            (mir::BinOp::Rem, _) => left.bvurem(&right),
+
+            _ => unimplemented!("{:?}", operator),
+        }
+    }
+
+    fn mir_unop_to_ast<'a>(
+        &self,
+        _ctx: &'a z3::Context,
+        operator: mir::UnOp,
+        val: z3::Ast<'a>,)
+        -> z3::Ast<'a>
+    {
+        match operator {
+            mir::UnOp::Not => val.not(),
+            mir::UnOp::Neg => val.bvneg(),
+        }
+    }
+
+    fn mir_intrinsic_to_ast<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        operator: NumericIntrinsic,
+        val: z3::Ast<'a>,
+        kind: PrimValKind)
+        -> z3::Ast<'a>
+    {
+        let num_bits = kind.num_bytes() as u32 * 8;
+        match operator {
+            NumericIntrinsic::Bitreverse => {
+                let one = z3::Ast::bv_from_u64(&ctx, 1, num_bits);
+                let mut r = z3::Ast::bv_from_u64(&ctx, 0, num_bits);
+                for idx in 0..num_bits {
+                    let from = z3::Ast::bv_from_u64(&ctx, idx as u64, num_bits);
+                    let to = z3::Ast::bv_from_u64(&ctx, (num_bits - 1 - idx) as u64, num_bits);
+                    r = r.bvor(&val.bvlshr(&from).bvand(&one).bvshl(&to));
+                }
+                r
+            }
+            NumericIntrinsic::Ctlz => {
+                // The leading zeros are the trailing zeros of the reversed bits.
+                let reversed = self.mir_intrinsic_to_ast(ctx, NumericIntrinsic::Bitreverse, val, kind);
+                self.mir_intrinsic_to_ast(ctx, NumericIntrinsic::Cttz, reversed, kind)
+            }
+            NumericIntrinsic::Ctpop => {
+                let one = z3::Ast::bv_from_u64(&ctx, 1, num_bits);
+                let mut r = z3::Ast::bv_from_u64(&ctx, 0, num_bits);
+                for idx in 0..num_bits {
+                    let shift = z3::Ast::bv_from_u64(&ctx, idx as u64, num_bits);
+                    r = r.bvadd(&val.bvlshr(&shift).bvand(&one));
+                }
+                r
+            }
+            NumericIntrinsic::Cttz => {
+                let mut bits = kind.num_bytes() * 8;
+                if bits > 128 {
+                    unimplemented!();
+                }
+                let num_bits = bits as u32;
+                let zero = z3::Ast::bv_from_u64(&ctx, 0, num_bits);
+                let mut r = z3::Ast::bv_from_u64(&ctx, 0, num_bits);
+                let mut x = val;
+                while bits > 1 {
+                    bits /= 2;
+                    let mask = (num::Wrapping(1u64) << bits) - num::Wrapping(1u64);
+                    let z3mask = z3::Ast::bv_from_u64(&ctx, mask.0, num_bits);
+                    let z3bits = z3::Ast::bv_from_u64(&ctx, bits as u64, num_bits);
+                    let ends_with_zeros = x.bvand(&z3mask)._eq(&zero);
+                    r = ends_with_zeros.ite(&r.bvadd(&z3bits), &r);
+                    x = ends_with_zeros.ite(&x.bvlshr(&z3bits), &x);
+                }
+                let one = z3::Ast::bv_from_u64(&ctx, 1, num_bits);
+                r.bvadd(&one.bvsub(&x.bvand(&one)))
+            },
+        }
+    }
+}
+
+fn smtlib_sbyte(sbyte: SByte) -> String {
+    match sbyte {
+        SByte::Abstract(b) => format!("v{}", b.0),
+        SByte::Concrete(b) => format!("(_ bv{} 8)", b),
+    }
+}
+
+fn smtlib_primval(primval: PrimVal, kind: PrimValKind) -> String {
+    match primval {
+        PrimVal::Undef => {
+            unimplemented!()
+        }
+        PrimVal::Ptr(_) => {
+            unimplemented!()
+        }
+        PrimVal::Abstract(sbytes) => {
+            if let PrimValKind::Bool = kind {
+                match sbytes[0] {
+                    SByte::Abstract(b) => format!("v{}", b.0),
+                    SByte::Concrete(b) => if b != 0 { "true".to_string() } else { "false".to_string() },
+                }
+            } else {
+                // The first byte is the least significant, as in `Z3Backend`.
+                let num_bytes = kind.num_bytes();
+                let mut result = smtlib_sbyte(sbytes[0]);
+                for sbyte in &sbytes[1..num_bytes] {
+                    result = format!("(concat {} {})", smtlib_sbyte(*sbyte), result);
+                }
+                result
+            }
+        }
+        PrimVal::Bytes(v) => {
+            match kind {
+                PrimValKind::Bool => if v != 0 { "true".to_string() } else { "false".to_string() },
+                // Floats are kept as their bits, see `smtlib_to_fp()`.
+                _ => format!("(_ bv{} {})", v, kind.num_bytes() * 8),
+            }
+        }
+    }
+}
+
+/// Reinterprets the bits of a float of `kind` as a term of the matching
+/// floating-point sort.
+fn smtlib_to_fp(bits: String, kind: PrimValKind) -> String {
+    match kind {
+        PrimValKind::F32 => format!("((_ to_fp 8 24) {})", bits),
+        PrimValKind::F64 => format!("((_ to_fp 11 53) {})", bits),
+        _ => bug!("expected a float kind, got {:?}", kind),
+    }
+}
+
+/// Like `smtlib_binop()` for floats, whose comparisons follow IEEE 754, so that
+/// NaN compares unequal to everything. Arithmetic rounds to nearest, ties to even.
+fn smtlib_float_binop(operator: mir::BinOp, left: String, right: String, kind: PrimValKind) -> String {
+    let left = smtlib_to_fp(left, kind);
+    let right = smtlib_to_fp(right, kind);
+    let op = match operator {
+        mir::BinOp::Eq => "fp.eq",
+        mir::BinOp::Ne => return format!("(not (fp.eq {} {}))", left, right),
+        mir::BinOp::Lt => "fp.lt",
+        mir::BinOp::Le => "fp.leq",
+        mir::BinOp::Gt => "fp.gt",
+        mir::BinOp::Ge => "fp.geq",
+
+        mir::BinOp::Add => "fp.add RNE",
+        mir::BinOp::Sub => "fp.sub RNE",
+        mir::BinOp::Mul => "fp.mul RNE",
+        mir::BinOp::Div => "fp.div RNE",
+
+        _ => unimplemented!("floating-point {:?} in SMT-LIB", operator),
+    };
+    format!("({} {} {})", op, left, right)
+}
+
+fn smtlib_binop(operator: mir::BinOp, left: String, right: String, kind: PrimValKind) -> String {
+    if kind.is_float() {
+        return smtlib_float_binop(operator, left, right, kind);
+    }
+    let signed = kind.is_signed_int();
+    let op = match (operator, kind) {
+        (mir::BinOp::Eq, _) => "=",
+        (mir::BinOp::Ne, _) => return format!("(not (= {} {}))", left, right),
+
+        (mir::BinOp::Lt, _) => if signed { "bvslt" } else { "bvult" },
+        (mir::BinOp::Le, _) => if signed { "bvsle" } else { "bvule" },
+        (mir::BinOp::Gt, _) => if signed { "bvsgt" } else { "bvugt" },
+        (mir::BinOp::Ge, _) => if signed { "bvsge" } else { "bvuge" },
+
+        (mir::BinOp::Add, _) => "bvadd",
+        (mir::BinOp::Sub, _) => "bvsub",
+        (mir::BinOp::Mul, _) => "bvmul",
+
+        (mir::BinOp::BitXor, PrimValKind::Bool) => "xor",
+        (mir::BinOp::BitXor, _) => "bvxor",
+        (mir::BinOp::BitAnd, PrimValKind::Bool) => "and",
+        (mir::BinOp::BitAnd, _) => "bvand",
+        (mir::BinOp::BitOr, PrimValKind::Bool) => "or",
+        (mir::BinOp::BitOr, _) => "bvor",
+
+        (mir::BinOp::Shl, _) => "bvshl",
+        (mir::BinOp::Shr, _) => if signed { "bvashr" } else { "bvlshr" },
+        (mir::BinOp::Div, _) => if signed { "bvsdiv" } else { "bvudiv" },
+        (mir::BinOp::Rem, _) => if signed { "bvsrem" } else { "bvurem" },
+
+        _ => unimplemented!("{:?} in SMT-LIB", operator),
+    };
+    format!("({} {} {})", op, left, right)
+}
+
+/// Counts trailing zeros by binary search, like `Z3Backend::mir_intrinsic_to_ast`,
+/// naming the intermediate values with `let` so that the term stays small.
+fn smtlib_cttz(val: String, kind: PrimValKind) -> String {
+    let num_bits = kind.num_bytes() * 8;
+    let bv = |n: u64| format!("(_ bv{} {})", n, num_bits);
+    let mut result = format!("(let ((cttz_r0 {}) (cttz_x0 {})) ", bv(0), val);
+    let mut depth = 1;
+    let mut bits = num_bits;
+    let mut step = 0;
+    while bits > 1 {
+        bits /= 2;
+        let mask = (num::Wrapping(1u64) << bits) - num::Wrapping(1u64);
+        result.push_str(&format!(
+            "(let ((cttz_z{n} (= (bvand cttz_x{n} {mask}) {zero}))) \
+             (let ((cttz_r{m} (ite cttz_z{n} (bvadd cttz_r{n} {bits}) cttz_r{n})) \
+                   (cttz_x{m} (ite cttz_z{n} (bvlshr cttz_x{n} {bits}) cttz_x{n}))) ",
+            n = step, m = step + 1, mask = bv(mask.0), zero = bv(0), bits = bv(bits as u64)));
+        depth += 2;
+        step += 1;
+    }
+    result.push_str(&format!(
+        "(bvadd cttz_r{n} (bvsub {one} (bvand cttz_x{n} {one})))",
+        n = step, one = bv(1)));
+    for _ in 0..depth {
+        result.push(')');
+    }
+    result
+}
+
+fn smtlib_ctpop(val: String, kind: PrimValKind) -> String {
+    let num_bits = kind.num_bytes() * 8;
+    let bv = |n: usize| format!("(_ bv{} {})", n, num_bits);
+    let mut result = bv(0);
+    for idx in 0..num_bits {
+        result = format!("(bvadd {} (bvand (bvlshr {} {}) {}))", result, val, bv(idx), bv(1));
+    }
+    result
+}
+
+fn smtlib_bitreverse(val: String, kind: PrimValKind) -> String {
+    let num_bits = kind.num_bytes() * 8;
+    let bv = |n: usize| format!("(_ bv{} {})", n, num_bits);
+    let mut result = bv(0);
+    for idx in 0..num_bits {
+        result = format!("(bvor {} (bvshl (bvand (bvlshr {} {}) {}) {}))",
+                         result, val, bv(idx), bv(1), bv(num_bits - 1 - idx));
+    }
+    result
+}
+
+fn smtlib_declaration(idx: usize, var_type: VarType) -> String {
+    let sort = match var_type {
+        VarType::Bool => "Bool",
+        VarType::BitVec8 => "(_ BitVec 8)",
+        VarType::Array => "(Array (_ BitVec 64) (_ BitVec 8))",
+    };
+    format!("(declare-const v{} {})\n", idx, sort)
+}
+
+/// Pushes the ids of the variables that `constraint` mentions onto `vars`.
+fn constraint_vars(constraint: Constraint, vars: &mut Vec<usize>) {
+    fn sbyte_vars(sbyte: SByte, vars: &mut Vec<usize>) {
+        if let SByte::Abstract(var) = sbyte {
+            vars.push(var.0 as usize);
+        }
+    }
+    fn primval_vars(primval: PrimVal, vars: &mut Vec<usize>) {
+        if let PrimVal::Abstract(sbytes) = primval {
+            for &sbyte in sbytes.iter() {
+                sbyte_vars(sbyte, vars);
+            }
+        }
+    }
+
+    match constraint {
+        Constraint::Binop { rhs_operand1, rhs_operand2, lhs, .. } => {
+            primval_vars(rhs_operand1, vars);
+            primval_vars(rhs_operand2, vars);
+            primval_vars(lhs, vars);
+        }
+        Constraint::Unop { operand, lhs, .. } |
+        Constraint::NumericIntrinsic { operand, lhs, .. } => {
+            primval_vars(operand, vars);
+            primval_vars(lhs, vars);
+        }
+        Constraint::Compare { lhs, rhs, .. } => {
+            primval_vars(lhs, vars);
+            primval_vars(rhs, vars);
+        }
+        Constraint::IfThenElse { discriminant, then_branch, else_branch, lhs, .. } => {
+            primval_vars(discriminant, vars);
+            primval_vars(then_branch, vars);
+            primval_vars(else_branch, vars);
+            primval_vars(lhs, vars);
+        }
+        Constraint::ArrayElement { array, index, value } => {
+            vars.push(array.0 as usize);
+            primval_vars(index, vars);
+            sbyte_vars(value, vars);
+        }
+        Constraint::ArrayStore { array, index, value, lhs } => {
+            vars.push(array.0 as usize);
+            vars.push(lhs.0 as usize);
+            primval_vars(index, vars);
+            sbyte_vars(value, vars);
+        }
+    }
+}
+
+fn smtlib_constraint(constraint: Constraint) -> String {
+    match constraint {
+        Constraint::Binop { operator, kind, lhs, rhs_operand1, rhs_operand2, lhs_kind } => {
+            let lhs = if lhs_kind.is_float() {
+                smtlib_to_fp(smtlib_primval(lhs, lhs_kind), lhs_kind)
+            } else {
+                smtlib_primval(lhs, lhs_kind)
+            };
+            format!("(= {} {})",
+                    lhs,
+                    smtlib_binop(operator,
+                                 smtlib_primval(rhs_operand1, kind),
+                                 smtlib_primval(rhs_operand2, kind),
+                                 kind))
+        }
+
+        Constraint::Unop { operator: mir::UnOp::Neg, kind, lhs, operand } if kind.is_float() => {
+            format!("(= {} (fp.neg {}))",
+                    smtlib_to_fp(smtlib_primval(lhs, kind), kind),
+                    smtlib_to_fp(smtlib_primval(operand, kind), kind))
+        }
+
+        Constraint::Unop { operator, kind, lhs, operand } => {
+            let op = match (operator, kind) {
+                (mir::UnOp::Not, PrimValKind::Bool) => "not",
+                (mir::UnOp::Not, _) => "bvnot",
+                (mir::UnOp::Neg, _) => "bvneg",
+            };
+            format!("(= {} ({} {}))", smtlib_primval(lhs, kind), op, smtlib_primval(operand, kind))
+        }
+
+        Constraint::NumericIntrinsic { operator, kind, lhs, operand } => {
+            let value = match operator {
+                NumericIntrinsic::Bitreverse => smtlib_bitreverse(smtlib_primval(operand, kind), kind),
+                NumericIntrinsic::Ctlz =>
+                    smtlib_cttz(smtlib_bitreverse(smtlib_primval(operand, kind), kind), kind),
+                NumericIntrinsic::Ctpop => smtlib_ctpop(smtlib_primval(operand, kind), kind),
+                NumericIntrinsic::Cttz => smtlib_cttz(smtlib_primval(operand, kind), kind),
+            };
+            format!("(= {} {})", smtlib_primval(lhs, kind), value)
+        }
+
+        Constraint::Compare { op, kind, lhs, rhs } => {
+            smtlib_binop(op, smtlib_primval(lhs, kind), smtlib_primval(rhs, kind), kind)
+        }
+
+        Constraint::IfThenElse { discriminant, kind, then_branch, else_branch, lhs } => {
+            format!("(= {} (ite {} {} {}))",
+                    smtlib_primval(lhs, kind),
+                    smtlib_primval(discriminant, PrimValKind::Bool),
+                    smtlib_primval(then_branch, kind),
+                    smtlib_primval(else_branch, kind))
+        }
+
+        Constraint::ArrayElement { array, index, value } => {
+            format!("(= (select v{} {}) {})",
+                    array.0, smtlib_primval(index, PrimValKind::U64), smtlib_sbyte(value))
+        }
+
+        Constraint::ArrayStore { array, index, value, lhs } => {
+            format!("(= v{} (store v{} {} {}))",
+                    lhs.0, array.0, smtlib_primval(index, PrimValKind::U64), smtlib_sbyte(value))
+        }
+    }
+}
@@ -8,23 +8,93 @@ use rustc::mir::visit::{Visitor, PlaceContext};
 use rustc::mir;
 use rustc::ty::{subst, self};
 use rustc::middle::const_val::ConstVal;
+use rustc_data_structures::indexed_vec::Idx;
 
 use error::{EvalResult, EvalError};
-use eval_context::{EvalContext, StackPopCleanup};
-use executor::FinishStep;
+use eval_context::{AsmOutput, BranchEvent, BranchKind, EvalContext, StackPopCleanup, ValTy};
+use executor::{FinishStep, FinishStepVariant};
 use lvalue::{Global, GlobalId, Lvalue};
+use value::{PrimVal, Value};
 use syntax::codemap::Span;
 
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub fn inc_step_counter_and_check_limit(&mut self, n: u64) -> EvalResult<'tcx> {
         self.steps_remaining = self.steps_remaining.saturating_sub(n);
-        if self.steps_remaining > 0 {
-            Ok(())
-        } else {
+        if self.steps_remaining == 0 {
+            return Err(EvalError::ExecutionTimeLimitReached);
+        }
+
+        // Reading the clock on every step would be too slow.
+        if self.steps_until_time_check > n {
+            self.steps_until_time_check -= n;
+            return Ok(());
+        }
+        self.steps_until_time_check = self.time_check_interval;
+        if self.time_limit_exceeded() {
             Err(EvalError::ExecutionTimeLimitReached)
+        } else {
+            Ok(())
         }
     }
 
+    fn inc_frame_step_counter_and_check_limit(&mut self) -> EvalResult<'tcx> {
+        let limit = self.max_steps_per_frame;
+        let frame = self.frame_mut();
+        frame.steps += 1;
+        match limit {
+            Some(limit) if frame.steps > limit => Err(EvalError::FrameStepLimitReached),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the current block is a loop header that was revisited more often than allowed.
+    fn check_loop_bound(&self) -> EvalResult<'tcx> {
+        let frame = self.frame();
+        match self.loop_bound {
+            Some(bound) if frame.loop_visits.get(&frame.block).map_or(false, |&n| n > bound) =>
+                Err(EvalError::LoopBoundReached),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the arguments of the frame that is about to take its first step, and fails if an
+    /// active call of the same function was entered with the very same arguments, and the path
+    /// has neither read input nor forked since. Such a call can only end by hitting the stack
+    /// limit, unless it makes progress through memory that it is not passed.
+    fn check_recursion_progress(&mut self) -> EvalResult<'tcx> {
+        let args = {
+            let frame = self.frame();
+            let args = &frame.locals[..frame.mir.arg_count];
+            let refers_to_memory = args.iter().any(|arg| match *arg {
+                Value::ByRef(_) => true,
+                Value::ByVal(val) => val.is_ptr(),
+                Value::ByValPair(a, b) => a.is_ptr() || b.is_ptr(),
+            });
+            if refers_to_memory {
+                return Ok(());
+            }
+            args.to_vec()
+        };
+        let instance = self.frame().instance;
+        let path = (self.memory.constraints.num_variables(), self.memory.constraints.constraints().len());
+        let repeated = self.stack[..self.stack.len() - 1].iter().any(|caller| {
+            caller.instance == instance && caller.entry_args.as_ref() == Some(&args) &&
+                caller.entry_path == path
+        });
+        if repeated {
+            return Err(EvalError::NonProgressingRecursion(instance.def_id()));
+        }
+        let frame = self.frame_mut();
+        frame.entry_args = Some(args);
+        frame.entry_path = path;
+        Ok(())
+    }
+
+    /// Returns true if the wall-clock budget of the exploration is used up.
+    pub(crate) fn time_limit_exceeded(&self) -> bool {
+        self.time_limit.map_or(false, |limit| self.start_time.elapsed() >= limit)
+    }
+
     /// Returns true as long as there are more things to do.
     pub fn step(&mut self)
                 -> EvalResult<'tcx, (bool, Option<Vec<FinishStep<'tcx>>>)>
@@ -35,13 +105,25 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         if self.stack.is_empty() {
             return Ok((false, None));
         }
+        self.inc_frame_step_counter_and_check_limit()?;
+        if self.detect_nonprogressing_recursion && self.frame().steps == 1 {
+            self.check_recursion_progress()?;
+        }
 
         let block = self.frame().block;
         let stmt_id = self.frame().stmt;
+        if stmt_id == 0 {
+            self.check_loop_bound()?;
+        }
         let mir = self.mir();
         let basic_block = &mir.basic_blocks()[block];
 
         if let Some(stmt) = basic_block.statements.get(stmt_id) {
+            if self.concrete_fast_path && self.concrete_statement(stmt)? {
+                self.executed_steps += 1;
+                self.frame_mut().stmt += 1;
+                return Ok((true, None));
+            }
             let mut new = Ok(0);
             ConstantExtractor {
                 span: stmt.source_info.span,
@@ -51,7 +133,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 new_constants: &mut new,
             }.visit_statement(block, stmt, mir::Location { block, statement_index: stmt_id });
             if new? == 0 {
-                self.statement(stmt)?;
+                self.executed_steps += 1;
+                let branches = self.statement(stmt)?;
+                let kind = match stmt.kind {
+                    mir::StatementKind::Assign(_, mir::Rvalue::Use(_)) => BranchKind::SymbolicPointer,
+                    _ => BranchKind::Other,
+                };
+                self.trace_branches(kind, block, stmt_id, &branches);
+                return Ok((true, branches));
             }
             // if ConstantExtractor added new frames, we don't execute anything here
             // but await the next call to step
@@ -68,7 +157,17 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             new_constants: &mut new,
         }.visit_terminator(block, terminator, mir::Location { block, statement_index: stmt_id });
         if new? == 0 {
-            Ok((true, self.terminator(terminator)?))
+            let kind = match terminator.kind {
+                mir::TerminatorKind::SwitchInt { switch_ty, .. } if switch_ty.is_bool() =>
+                    BranchKind::Conditional,
+                mir::TerminatorKind::SwitchInt { .. } => BranchKind::SwitchInt,
+                mir::TerminatorKind::Assert { .. } => BranchKind::Conditional,
+                _ => BranchKind::Other,
+            };
+            self.executed_steps += 1;
+            let branches = self.terminator(terminator)?;
+            self.trace_branches(kind, block, stmt_id, &branches);
+            Ok((true, branches))
         } else {
             // if ConstantExtractor added new frames, we don't execute anything here
             // but await the next call to step
@@ -76,11 +175,46 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         }
     }
 
-    fn statement(&mut self, stmt: &mir::Statement<'tcx>) -> EvalResult<'tcx> {
+    /// Passes the branches of a fork at `block` and `statement` to the branch trace.
+    fn trace_branches(
+        &self,
+        kind: BranchKind,
+        block: mir::BasicBlock,
+        statement: usize,
+        branches: &Option<Vec<FinishStep<'tcx>>>,
+    ) {
+        let (trace, branches) = match (&self.branch_trace, branches) {
+            (&Some(ref trace), &Some(ref branches)) if branches.len() > 1 => (trace, branches),
+            _ => return,
+        };
+        let function = self.frame().instance.to_string();
+        for (idx, branch) in branches.iter().enumerate() {
+            let target = match branch.variant {
+                FinishStepVariant::Continue { goto_block, .. } => Some(goto_block.index()),
+                _ => None,
+            };
+            let event = BranchEvent {
+                function: function.clone(),
+                block: block.index(),
+                statement,
+                kind,
+                branch: idx,
+                target,
+                constraints: branch.constraints.clone(),
+            };
+            (&mut *trace.borrow_mut())(&event);
+        }
+    }
+
+    /// Executes a statement. If the statement reads a symbolic value that it
+    /// needs to branch on, returns the possible successors.
+    fn statement(&mut self, stmt: &mir::Statement<'tcx>)
+                 -> EvalResult<'tcx, Option<Vec<FinishStep<'tcx>>>>
+    {
         trace!("{:?}", stmt);
 
         use rustc::mir::StatementKind::*;
-        match stmt.kind {
+        let branches = match stmt.kind {
             Assign(ref place, ref rvalue) => self.eval_rvalue_into_lvalue(rvalue, place)?,
 
             SetDiscriminant {
@@ -90,28 +224,127 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let dest = self.eval_lvalue(place)?;
                 let dest_ty = self.lvalue_ty(place);
                 self.write_discriminant_value(dest_ty, dest, variant_index)?;
+                None
             }
 
             // Miri can safely ignore these. Only translation needs it.
             StorageLive(_) |
-            StorageDead(_) => {}
+            StorageDead(_) => None,
 
             // Validity checks.
             Validate(_op, ref _lvalues) => {
                 // TODO
+                None
             }
 
-            UserAssertTy(..) => {}
-            EndRegion(..) => {}
+            UserAssertTy(..) => None,
+            EndRegion(..) => None,
 
             // Defined to do nothing. These are added by optimization passes, to avoid changing the
             // size of MIR constantly.
-            Nop => {}
+            Nop => None,
 
-            InlineAsm { .. } => return Err(EvalError::InlineAsm),
-        }
+            InlineAsm { ref asm, ref outputs, ref inputs } => {
+                self.inline_asm(&asm.asm.as_str(), outputs, inputs)?;
+                None
+            }
+        };
 
         self.frame_mut().stmt += 1;
+        Ok(branches)
+    }
+
+    /// Evaluates `stmt` directly on the locals of the current frame if it assigns a copy of a
+    /// local, or a binary operation on locals, to a local, all holding concrete integers.
+    /// Returns `false` without doing anything as soon as anything else is involved, e.g. an
+    /// `Abstract` value, a pointer, memory, or a constant, so that the statement goes through
+    /// the general evaluation instead.
+    fn concrete_statement(&mut self, stmt: &mir::Statement<'tcx>) -> EvalResult<'tcx, bool> {
+        let (dest, rvalue) = match stmt.kind {
+            mir::StatementKind::Assign(mir::Place::Local(dest), ref rvalue) if dest != mir::RETURN_PLACE =>
+                (dest, rvalue),
+            _ => return Ok(false),
+        };
+        // A local that lives in memory may be observed through pointers.
+        if let Value::ByRef(_) = self.frame().get_local(dest)? {
+            return Ok(false);
+        }
+        let value = match *rvalue {
+            mir::Rvalue::Use(ref operand) => match self.concrete_operand(operand)? {
+                Some(val) => Value::ByVal(val),
+                None => return Ok(false),
+            },
+            mir::Rvalue::BinaryOp(op, ref left, ref right) |
+            mir::Rvalue::CheckedBinaryOp(op, ref left, ref right) => {
+                let (left_val, right_val) = match (self.concrete_operand(left)?, self.concrete_operand(right)?) {
+                    (Some(left_val), Some(right_val)) => (left_val, right_val),
+                    _ => return Ok(false),
+                };
+                let left_ty = self.operand_ty(left);
+                let right_ty = self.operand_ty(right);
+                let (val, overflowed) = self.binary_op(op, left_val, left_ty, right_val, right_ty)?;
+                match *rvalue {
+                    mir::Rvalue::CheckedBinaryOp(..) => Value::ByValPair(val, overflowed),
+                    _ => Value::ByVal(val),
+                }
+            }
+            _ => return Ok(false),
+        };
+        self.frame_mut().set_local(dest, value);
+        Ok(true)
+    }
+
+    /// The value of `operand` if it is a local of the current frame holding a concrete integer.
+    fn concrete_operand(&self, operand: &mir::Operand<'tcx>) -> EvalResult<'tcx, Option<PrimVal>> {
+        match *operand {
+            mir::Operand::Copy(mir::Place::Local(local)) |
+            mir::Operand::Move(mir::Place::Local(local)) if local != mir::RETURN_PLACE => {
+                match self.frame().get_local(local)? {
+                    Value::ByVal(val @ PrimVal::Bytes(_)) => Ok(Some(val)),
+                    _ => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Evaluates an `asm!` block with its summary, or fails if it has none.
+    fn inline_asm(
+        &mut self,
+        template: &str,
+        outputs: &[mir::Place<'tcx>],
+        inputs: &[mir::Operand<'tcx>],
+    ) -> EvalResult<'tcx> {
+        let summary = match self.asm_summaries.get(template) {
+            Some(summary) => summary.clone(),
+            None => return Err(EvalError::InlineAsm),
+        };
+        let mut input_vals = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            input_vals.push(self.eval_operand_to_primval(input)?);
+        }
+        let results = (&mut *summary.borrow_mut())(&input_vals);
+        if results.len() != outputs.len() {
+            return Err(EvalError::Unimplemented(format!(
+                "the summary of `{}` returned {} values for {} outputs",
+                template, results.len(), outputs.len())));
+        }
+
+        for (output, result) in outputs.iter().zip(results) {
+            let dest = self.eval_lvalue(output)?;
+            let ty = self.lvalue_ty(output);
+            match result {
+                AsmOutput::Value(val) => self.write_primval(dest, val, ty)?,
+                AsmOutput::Symbolic => {
+                    let value = self.mk_symbolic(ty)?;
+                    self.write_value(ValTy { value, ty }, dest)?;
+                    if let Value::ByRef(ptr) = value {
+                        // `write_value` copied the allocation into the destination.
+                        self.memory.deallocate(ptr)?;
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
@@ -0,0 +1,79 @@
+//! Strategies for choosing which of the queued paths the executor steps next.
+
+use std::collections::VecDeque;
+
+use eval_context::EvalContext;
+
+/// The paths that wait to be explored. Index 0 is the most recently queued path, the last
+/// index the one that has been waiting the longest.
+pub struct Frontier<'f, 'a: 'f, 'tcx: 'a> {
+    pub(crate) queue: &'f VecDeque<EvalContext<'a, 'tcx>>,
+}
+
+impl<'f, 'a, 'tcx> Frontier<'f, 'a, 'tcx> {
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// The number of forks that the path at `idx` has gone through.
+    pub fn branch_depth(&self, idx: usize) -> usize {
+        self.queue[idx].branch_depth
+    }
+}
+
+/// Chooses the path to explore next, e.g. to find shallow bugs quickly or to get deep
+/// into the program. Set with `ExecutionConfig::path_strategy`.
+pub trait PathStrategy {
+    /// Returns the index of the path in `frontier` to step next. Only called with
+    /// nonempty frontiers.
+    fn pick(&mut self, frontier: &Frontier) -> usize;
+}
+
+/// Steps the most recently queued path, so that a path is followed to its end before
+/// its siblings are explored. This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DepthFirst;
+
+impl PathStrategy for DepthFirst {
+    fn pick(&mut self, _frontier: &Frontier) -> usize {
+        0
+    }
+}
+
+/// Steps the path that has waited the longest, so that paths complete in the order of
+/// the number of forks they take.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BreadthFirst;
+
+impl PathStrategy for BreadthFirst {
+    fn pick(&mut self, frontier: &Frontier) -> usize {
+        frontier.len() - 1
+    }
+}
+
+/// Steps a pseudo-randomly chosen path. The choices only depend on the seed, so runs
+/// with the same seed explore the paths in the same order.
+#[derive(Clone, Debug)]
+pub struct RandomPath {
+    state: u64,
+}
+
+impl RandomPath {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves the all-zero state.
+        RandomPath { state: seed | 1 }
+    }
+}
+
+impl PathStrategy for RandomPath {
+    fn pick(&mut self, frontier: &Frontier) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % frontier.len() as u64) as usize
+    }
+}
@@ -3,10 +3,12 @@ use rustc::ty::layout::{Size, Align};
 use rustc::ty::subst::Substs;
 use rustc::ty::{self, Ty};
 
+use constraints::Constraint;
 use error::{EvalError, EvalResult};
 use eval_context::{EvalContext, ValTy};
+use executor::{FinishStep, FinishStepVariant};
 use lvalue::{Lvalue, LvalueExtra};
-use memory::{MemoryPointer};
+use memory::{MemoryPointer, PointerOffset, SByte};
 use value::{PrimVal, PrimValKind, Value};
 
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
@@ -18,7 +20,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         dest_ty: Ty<'tcx>,
         dest_layout: ty::layout::TyLayout<'tcx>,
         target: mir::BasicBlock,
-    ) -> EvalResult<'tcx> {
+    ) -> EvalResult<'tcx, Option<Vec<FinishStep<'tcx>>>> {
         let arg_vals: EvalResult<Vec<Value>> = args.iter()
             .map(|arg| self.eval_operand(arg))
             .collect();
@@ -46,6 +48,12 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             "mul_with_overflow" =>
                 self.intrinsic_with_overflow(mir::BinOp::Mul, &args[0], &args[1], dest, dest_ty)?,
 
+            "saturating_add" =>
+                self.intrinsic_saturating(mir::BinOp::Add, &args[0], &args[1], dest, dest_ty)?,
+
+            "saturating_sub" =>
+                self.intrinsic_saturating(mir::BinOp::Sub, &args[0], &args[1], dest, dest_ty)?,
+
 
             "arith_offset" => {
                 let offset = self.value_to_primval(arg_vals[1], isize)?.to_i128()? as i64;
@@ -56,8 +64,28 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
             "assume" => {
                 let bool = self.tcx.types.bool;
-                let cond = self.value_to_primval(arg_vals[0], bool)?.to_bool()?;
-                if !cond { return Err(EvalError::AssumptionNotHeld); }
+                let cond = self.value_to_primval(arg_vals[0], bool)?;
+                if cond.is_concrete() {
+                    if !cond.to_bool()? {
+                        return self.reached_undefined_behavior(EvalError::AssumptionNotHeld);
+                    }
+                } else {
+                    // Continue with the assumption as a path constraint. If it cannot
+                    // hold, there are no branches and the path is dropped.
+                    let holds = Constraint::new_compare(
+                        mir::BinOp::Eq, PrimValKind::Bool, cond, PrimVal::from_bool(true));
+                    let mut branches = Vec::new();
+                    if self.memory.constraints.is_feasible_with(&[holds]) {
+                        branches.push(FinishStep {
+                            constraints: vec![holds],
+                            variant: FinishStepVariant::Continue {
+                                goto_block: target,
+                                set_lvalue: None,
+                            },
+                        });
+                    }
+                    return Ok(Some(branches));
+                }
             }
 
             "atomic_load" |
@@ -151,10 +179,20 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let elem_align = self.type_align(elem_ty)?;
                 let src = arg_vals[0].read_ptr(&self.memory)?;
                 let dest = arg_vals[1].read_ptr(&self.memory)?;
-                let count = self.value_to_primval(arg_vals[2], usize)?.to_u64()?;
-                self.memory.copy(src, dest, count * elem_size, elem_align)?;
+                let count = self.value_to_primval(arg_vals[2], usize)?;
+                if elem_size == 0 {
+                    // nothing to copy
+                } else if !count.is_concrete() {
+                    let nonoverlapping = intrinsic_name == "copy_nonoverlapping";
+                    return self.abstract_count_copy(
+                        src.to_ptr()?, dest.to_ptr()?, count, elem_size, elem_align, nonoverlapping, target,
+                    ).map(Some);
+                } else {
+                    self.memory.copy(src, dest, count.to_u64()? * elem_size, elem_align)?;
+                }
             }
 
+            "bitreverse" |
             "ctpop" |
             "cttz" |
             "cttz_nonzero" |
@@ -175,10 +213,41 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 self.write_primval(dest, num, ty)?
             }
 
+            "rotate_left" | "rotate_right" => {
+                let ty = instance.substs.type_at(0);
+                let kind = self.ty_to_primval_kind(ty)?;
+                let val = self.value_to_primval(arg_vals[0], ty)?;
+                let amount = self.value_to_primval(arg_vals[1], ty)?;
+                let result = self.rotate(intrinsic_name == "rotate_left", val, amount, kind)?;
+                self.write_primval(dest, result, ty)?;
+            }
+
             "discriminant_value" => {
                 let ty = instance.substs.type_at(0);
                 let adt_ptr = arg_vals[0].read_ptr(&self.memory)?.to_ptr()?;
-                let discr_val = self.read_discriminant_value(Lvalue::from_ptr(adt_ptr), ty)?;
+                let adt_lvalue = Lvalue::from_ptr(adt_ptr);
+                if let Some((discr_val, valid, invalid)) =
+                    self.read_abstract_discriminant_primval(adt_lvalue, ty)?
+                {
+                    let mut branches = Vec::new();
+                    if let Some(invalid) = invalid {
+                        branches.push(FinishStep {
+                            constraints: invalid,
+                            variant: FinishStepVariant::Error(EvalError::InvalidDiscriminant),
+                        });
+                    }
+                    if self.memory.constraints.is_feasible_with(&valid) {
+                        branches.push(FinishStep {
+                            constraints: valid,
+                            variant: FinishStepVariant::Continue {
+                                goto_block: target,
+                                set_lvalue: Some((dest, discr_val, dest_ty)),
+                            },
+                        });
+                    }
+                    return Ok(Some(branches));
+                }
+                let discr_val = self.read_discriminant_value(adt_lvalue, ty)?;
                 self.write_primval(dest, PrimVal::Bytes(discr_val), dest_ty)?;
             }
 
@@ -244,17 +313,34 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 self.write_primval(dest, result.0, dest_ty)?;
             }
 
+            "minnumf32" | "maxnumf32" | "minnumf64" | "maxnumf64" => {
+                let ty = if intrinsic_name.ends_with("f32") { f32 } else { f64 };
+                let a = self.value_to_primval(arg_vals[0], ty)?;
+                let b = self.value_to_primval(arg_vals[1], ty)?;
+                let result = self.min_max(intrinsic_name.starts_with("max"), a, b, ty)?;
+                self.write_primval(dest, result, dest_ty)?;
+            }
+
             "exact_div" => {
                 // added in https://github.com/rust-lang/rust/pull/49297
-
-                // TODO report undefined behavior in cases where
-                // `a % b != 0` or `b == 0` or `a = ty::min_value() && b == 1`
-
                 let ty = instance.substs.type_at(0);
                 let a = self.value_to_primval(arg_vals[0], ty)?;
                 let b = self.value_to_primval(arg_vals[1], ty)?;
-                let result = self.binary_op(mir::BinOp::Div, a, ty, b, ty)?;
-                self.write_primval(dest, result.0, dest_ty)?;
+                if !a.is_concrete() || !b.is_concrete() {
+                    return self.abstract_exact_div(a, b, ty, dest, dest_ty, target).map(Some);
+                }
+                let (rem, _) = self.binary_op(mir::BinOp::Rem, a, ty, b, ty)?;
+                if rem.to_u128()? != 0 {
+                    return Err(EvalError::InexactDivision {
+                        dividend: a.to_u128()?,
+                        divisor: b.to_u128()?,
+                    });
+                }
+                let (result, overflowed) = self.binary_op(mir::BinOp::Div, a, ty, b, ty)?;
+                if overflowed {
+                    return Err(EvalError::OverflowingMath);
+                }
+                self.write_primval(dest, result, dest_ty)?;
             }
 
             "likely" |
@@ -338,13 +424,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         PrimVal::Bytes(size as u128),
                         offset_primval,
                         PrimValKind::U64);
-                    let new_offset = self.memory.constraints.add_binop_constraint(
-                        mir::BinOp::Add,
-                        ptr.to_ptr()?.offset.as_primval(),
-                        byte_offset,
-                        PrimValKind::U64);
-                    let new_ptr = MemoryPointer::with_primval_offset(ptr.to_ptr()?.alloc_id, new_offset);
-                    self.write_primval(dest, PrimVal::Ptr(new_ptr), dest_ty)?;
+                    let new_ptr = self.memory.abstract_offset(ptr.to_ptr()?, byte_offset);
+                    return self.abstract_pointer_offset(new_ptr, dest, dest_ty, target).map(Some);
                 }
             }
 
@@ -408,7 +489,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             "size_of_val" => {
                 let ty = instance.substs.type_at(0);
                 let (size, _) = self.size_and_align_of_dst(ty, arg_vals[0])?;
-                self.write_primval(dest, PrimVal::from_u128(size.bytes() as u128), dest_ty)?;
+                self.write_primval(dest, size, dest_ty)?;
             }
 
             "min_align_of_val" |
@@ -434,9 +515,26 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let src_ty = substs.type_at(0);
                 let dest_ty = substs.type_at(1);
                 let size = self.type_size(dest_ty)?.expect("transmute() type must be sized");
+                let src_size = self.type_size(src_ty)?.expect("transmute() type must be sized");
+                if src_size != size {
+                    return Err(EvalError::Intrinsic(format!(
+                        "transmute from {} ({} bytes) to {} ({} bytes) changes the size",
+                        src_ty, src_size, dest_ty, size)));
+                }
+                // The value is written with the source type and read back with the
+                // destination type, so symbolic bytes are carried over unchanged.
                 let ptr = self.force_allocation(dest)?.to_ptr()?;
                 self.memory.mark_packed(ptr, size);
                 self.write_value_to_ptr(arg_vals[0], PrimVal::Ptr(ptr), src_ty)?;
+
+                // The source may be an aggregate such as `[u8; 4]`, so the char is read
+                // back from the bytes just written.
+                if let ty::TyChar = dest_ty.sty {
+                    if !self.memory.points_to_concrete(ptr, 4)? {
+                        let c = self.memory.read_abstract(PrimVal::Ptr(ptr), 4)?;
+                        return Ok(Some(self.abstract_char_validity(c, target)));
+                    }
+                }
             }
 
             "uninit" => {
@@ -447,7 +545,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             this.memory.mark_definedness(PrimVal::Ptr(ptr), size, false)?;
                             Ok(Value::ByRef(ptr))
                         },
-                        _ => Ok(Value::ByVal(PrimVal::Undef)),
+                        // Keep the shape of the value, so that each half can be defined on its own.
+                        Value::ByValPair(..) => Ok(Value::ByValPair(PrimVal::Undef, PrimVal::Undef)),
+                        Value::ByVal(_) => Ok(Value::ByVal(PrimVal::Undef)),
                     }
                 };
                 match dest {
@@ -463,14 +563,24 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let u8 = self.tcx.types.u8;
                 let ty = instance.substs.type_at(0);
                 let ty_align = self.type_align(ty)?;
-                let val_byte = self.value_to_primval(arg_vals[1], u8)?.to_u128()? as u8;
+                let val_byte = match self.value_to_primval(arg_vals[1], u8)? {
+                    PrimVal::Abstract(sbytes) => sbytes[0],
+                    val => SByte::Concrete(val.to_u128()? as u8),
+                };
                 let size = self.type_size(ty)?.expect("write_bytes() type must be sized");
                 let ptr = arg_vals[0].read_ptr(&self.memory)?;
-                let count = self.value_to_primval(arg_vals[2], usize)?.to_u64()?;
-                if count > 0 {
+                let count = self.value_to_primval(arg_vals[2], usize)?;
+                if size == 0 {
+                    // nothing to write
+                } else if !count.is_concrete() {
+                    return self.abstract_count_write_bytes(
+                        ptr.to_ptr()?, val_byte, count, size, ty_align, target,
+                    ).map(Some);
+                } else if count.to_u64()? > 0 {
+                    let count = count.to_u64()?;
                     let ptr = ptr.to_ptr()?;
                     self.memory.check_align(ptr, ty_align, size * count)?;
-                    self.memory.write_repeat(ptr, val_byte, size * count)?;
+                    self.memory.write_repeat_sbyte(ptr, val_byte, size * count)?;
                 }
             }
 
@@ -482,20 +592,408 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         // Since we pushed no stack frame, the main loop will act
         // as if the call just completed and it's returning to the
         // current frame.
-        Ok(())
+        Ok(None)
+    }
+
+    /// Writes `ptr`, the result of a symbolic `offset`, to `dest`. Forks into the paths where
+    /// `ptr` stays within its allocation, or at most one byte past its end, and where it does not.
+    fn abstract_pointer_offset(
+        &mut self,
+        ptr: MemoryPointer,
+        dest: Lvalue<'tcx>,
+        dest_ty: Ty<'tcx>,
+        target: mir::BasicBlock,
+    ) -> EvalResult<'tcx, Vec<FinishStep<'tcx>>> {
+        let (allocation_size, size) = {
+            let alloc = self.memory.get(ptr.alloc_id)?;
+            let allocation_size = alloc.bytes.len() as u64;
+            let size = match alloc.abstract_size {
+                Some(sbytes) => PrimVal::Abstract(sbytes),
+                None => PrimVal::from_u128(allocation_size as u128),
+            };
+            (allocation_size, size)
+        };
+        let offset = ptr.offset.as_primval();
+
+        let mut branches = Vec::new();
+
+        // Negative offsets wrap around, so they are out of bounds as well.
+        let out_of_bounds = vec![Constraint::new_compare(
+            mir::BinOp::Gt, PrimValKind::U64, offset, size)];
+        if self.memory.constraints.is_feasible_with(&out_of_bounds) {
+            branches.push(FinishStep {
+                constraints: out_of_bounds,
+                variant: FinishStepVariant::Error(
+                    EvalError::PointerOutOfBounds { ptr, access: false, allocation_size }),
+            });
+        }
+
+        let in_bounds = vec![Constraint::new_compare(
+            mir::BinOp::Le, PrimValKind::U64, offset, size)];
+        if self.memory.constraints.is_feasible_with(&in_bounds) {
+            branches.push(FinishStep {
+                constraints: in_bounds,
+                variant: FinishStepVariant::Continue {
+                    goto_block: target,
+                    set_lvalue: Some((dest, PrimVal::Ptr(ptr), dest_ty)),
+                },
+            });
+        }
+
+        Ok(branches)
+    }
+
+    /// Rotates `val` left or right by `amount` modulo its width. Symbolic operands are
+    /// rotated with a pair of shifts, which the solver handles for any amount.
+    fn rotate(&mut self, left: bool, val: PrimVal, amount: PrimVal, kind: PrimValKind) -> EvalResult<'tcx, PrimVal> {
+        use value::PrimValKind::*;
+        let bits = kind.num_bytes() as u128 * 8;
+        let mask = if bits == 128 { !0 } else { (1 << bits) - 1 };
+
+        if val.is_concrete() && amount.is_concrete() {
+            let x = val.to_u128()? & mask;
+            let n = amount.to_u128()? % bits;
+            let mut rotated = if n == 0 {
+                x
+            } else if left {
+                ((x << n) | (x >> (bits - n))) & mask
+            } else {
+                ((x >> n) | (x << (bits - n))) & mask
+            };
+            // Signed values are kept sign-extended, as `integer_intrinsic` does.
+            if kind.is_signed_int() {
+                let shift = 128 - bits;
+                rotated = (((rotated << shift) as i128) >> shift) as u128;
+            }
+            return Ok(PrimVal::Bytes(rotated));
+        }
+
+        // Shift as unsigned, so that the bits shifted in are zero.
+        let kind = match kind {
+            I8 => U8,
+            I16 => U16,
+            I32 => U32,
+            I64 => U64,
+            I128 => U128,
+            kind => kind,
+        };
+        let (n, rest) = if amount.is_concrete() {
+            let n = amount.to_u128()? % bits;
+            (PrimVal::Bytes(n), PrimVal::Bytes((bits - n) % bits))
+        } else {
+            let n = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Rem, amount, PrimVal::Bytes(bits), kind);
+            let rest = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Sub, PrimVal::Bytes(bits), n, kind);
+            let rest = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Rem, rest, PrimVal::Bytes(bits), kind);
+            (n, rest)
+        };
+        let (first, second) = if left {
+            (mir::BinOp::Shl, mir::BinOp::Shr)
+        } else {
+            (mir::BinOp::Shr, mir::BinOp::Shl)
+        };
+        let high = self.memory.constraints.add_binop_constraint(first, val, n, kind);
+        let low = self.memory.constraints.add_binop_constraint(second, val, rest, kind);
+        Ok(self.memory.constraints.add_binop_constraint(mir::BinOp::BitOr, high, low, kind))
+    }
+
+    /// Forks `exact_div(a, b)` on symbolic operands into the paths where `b` is zero, where
+    /// the division has a remainder, where it overflows, and where it is exact and the
+    /// quotient is written to `dest`.
+    fn abstract_exact_div(
+        &mut self,
+        a: PrimVal,
+        b: PrimVal,
+        ty: Ty<'tcx>,
+        dest: Lvalue<'tcx>,
+        dest_ty: Ty<'tcx>,
+        target: mir::BasicBlock,
+    ) -> EvalResult<'tcx, Vec<FinishStep<'tcx>>> {
+        let kind = self.ty_to_primval_kind(ty)?;
+        let mut branches = Vec::new();
+        let mut exact = Vec::new();
+
+        if b.is_concrete() {
+            if b.to_u128()? == 0 {
+                return Err(EvalError::DivisionByZero(None));
+            }
+        } else {
+            let zero = vec![Constraint::new_compare(mir::BinOp::Eq, kind, b, PrimVal::Bytes(0))];
+            if self.memory.constraints.is_feasible_with(&zero) {
+                branches.push(FinishStep {
+                    constraints: zero,
+                    variant: FinishStepVariant::Error(EvalError::DivisionByZero(None)),
+                });
+            }
+            exact.push(Constraint::new_compare(mir::BinOp::Ne, kind, b, PrimVal::Bytes(0)));
+        }
+
+        if kind.is_signed_int() {
+            // `MIN / -1` does not fit in the type.
+            let bits = self.type_size(ty)?.expect("integers are sized") * 8;
+            let min = PrimVal::from_i128(i128::min_value() >> (128 - bits));
+            let a_is_min = self.memory.constraints.add_binop_constraint(mir::BinOp::Eq, a, min, kind);
+            let b_is_neg_one = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Eq, b, PrimVal::from_i128(-1), kind);
+            let overflows = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::BitAnd, a_is_min, b_is_neg_one, PrimValKind::Bool);
+            let mut overflow = exact.clone();
+            overflow.push(Constraint::new_compare(
+                mir::BinOp::Eq, PrimValKind::Bool, overflows, PrimVal::from_bool(true)));
+            if self.memory.constraints.is_feasible_with(&overflow) {
+                branches.push(FinishStep {
+                    constraints: overflow,
+                    variant: FinishStepVariant::Error(EvalError::OverflowingMath),
+                });
+            }
+            exact.push(Constraint::new_compare(
+                mir::BinOp::Eq, PrimValKind::Bool, overflows, PrimVal::from_bool(false)));
+        }
+
+        let rem = self.memory.constraints.add_binop_constraint(mir::BinOp::Rem, a, b, kind);
+        let mut inexact = exact.clone();
+        inexact.push(Constraint::new_compare(mir::BinOp::Ne, kind, rem, PrimVal::Bytes(0)));
+        let dividend = self.memory.constraints.get_witness_with(a, kind, &inexact);
+        let divisor = self.memory.constraints.get_witness_with(b, kind, &inexact);
+        if let (Some(dividend), Some(divisor)) = (dividend, divisor) {
+            branches.push(FinishStep {
+                constraints: inexact,
+                variant: FinishStepVariant::Error(EvalError::InexactDivision { dividend, divisor }),
+            });
+        }
+
+        exact.push(Constraint::new_compare(mir::BinOp::Eq, kind, rem, PrimVal::Bytes(0)));
+        if self.memory.constraints.is_feasible_with(&exact) {
+            let quotient = self.memory.constraints.add_binop_constraint(mir::BinOp::Div, a, b, kind);
+            branches.push(FinishStep {
+                constraints: exact,
+                variant: FinishStepVariant::Continue {
+                    goto_block: target,
+                    set_lvalue: Some((dest, quotient, dest_ty)),
+                },
+            });
+        }
+
+        Ok(branches)
+    }
+
+    /// Forks on whether the symbolic `c` is a valid char, i.e. at most `0x10FFFF` and not a
+    /// surrogate. The invalid path fails with a witness of the invalid value.
+    fn abstract_char_validity(&mut self, c: PrimVal, target: mir::BasicBlock) -> Vec<FinishStep<'tcx>> {
+        let below_surrogates = self.memory.constraints.add_binop_constraint(
+            mir::BinOp::Le, c, PrimVal::Bytes(0xD7FF), PrimValKind::U32);
+        let above_surrogates = self.memory.constraints.add_binop_constraint(
+            mir::BinOp::Ge, c, PrimVal::Bytes(0xE000), PrimValKind::U32);
+        let in_range = self.memory.constraints.add_binop_constraint(
+            mir::BinOp::Le, c, PrimVal::Bytes(0x10FFFF), PrimValKind::U32);
+        let upper = self.memory.constraints.add_binop_constraint(
+            mir::BinOp::BitAnd, above_surrogates, in_range, PrimValKind::Bool);
+        let valid = self.memory.constraints.add_binop_constraint(
+            mir::BinOp::BitOr, below_surrogates, upper, PrimValKind::Bool);
+
+        let mut branches = Vec::new();
+
+        let invalid = vec![Constraint::new_compare(
+            mir::BinOp::Eq, PrimValKind::Bool, valid, PrimVal::from_bool(false))];
+        if let Some(witness) = self.memory.constraints.get_witness_with(c, PrimValKind::U32, &invalid) {
+            branches.push(FinishStep {
+                constraints: invalid,
+                variant: FinishStepVariant::Error(EvalError::InvalidChar(witness)),
+            });
+        }
+
+        let valid = vec![Constraint::new_compare(
+            mir::BinOp::Eq, PrimValKind::Bool, valid, PrimVal::from_bool(true))];
+        if self.memory.constraints.is_feasible_with(&valid) {
+            branches.push(FinishStep {
+                constraints: valid,
+                variant: FinishStepVariant::Continue {
+                    goto_block: target,
+                    set_lvalue: None,
+                },
+            });
+        }
+
+        branches
+    }
+
+    /// Copies `count` elements of `elem_size` bytes from `src` to `dest`, where `count` is
+    /// symbolic. Forks into the paths where the copy is in bounds and where it is not and,
+    /// if `nonoverlapping`, into the path where the two ranges overlap.
+    fn abstract_count_copy(
+        &mut self,
+        src: MemoryPointer,
+        dest: MemoryPointer,
+        count: PrimVal,
+        elem_size: u64,
+        elem_align: u64,
+        nonoverlapping: bool,
+        target: mir::BasicBlock,
+    ) -> EvalResult<'tcx, Vec<FinishStep<'tcx>>> {
+        let (src_offset, dest_offset) = match (src.offset, dest.offset) {
+            (PointerOffset::Concrete(src_offset), PointerOffset::Concrete(dest_offset)) =>
+                (src_offset, dest_offset),
+            _ => return Err(EvalError::UnsupportedSymbolicOperation {
+                op: "copy with a symbolic count".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
+        };
+        let src_size = self.memory.get(src.alloc_id)?.bytes.len() as u64;
+        let dest_size = self.memory.get(dest.alloc_id)?.bytes.len() as u64;
+        let (limiting_ptr, limiting_offset, allocation_size) =
+            if src_size.saturating_sub(src_offset) <= dest_size.saturating_sub(dest_offset) {
+                (src, src_offset, src_size)
+            } else {
+                (dest, dest_offset, dest_size)
+            };
+        let max_count = allocation_size.saturating_sub(limiting_offset) / elem_size;
+
+        let mut branches = Vec::new();
+
+        let out_of_bounds = vec![Constraint::new_compare(
+            mir::BinOp::Gt, PrimValKind::U64, count, PrimVal::from_u128(max_count as u128))];
+        if self.memory.constraints.is_feasible_with(&out_of_bounds) {
+            let size = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Mul, count, PrimVal::from_u128(elem_size as u128), PrimValKind::U64);
+            let end = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Add, PrimVal::from_u128(limiting_offset as u128), size, PrimValKind::U64);
+            let ptr = match end {
+                PrimVal::Abstract(sbytes) =>
+                    MemoryPointer { alloc_id: limiting_ptr.alloc_id, offset: PointerOffset::Abstract(sbytes) },
+                _ => bug!("symbolic copy end is concrete"),
+            };
+            branches.push(FinishStep {
+                constraints: out_of_bounds,
+                variant: FinishStepVariant::Error(
+                    EvalError::PointerOutOfBounds { ptr, access: true, allocation_size }),
+            });
+        }
+
+        let mut in_bounds_count = max_count;
+        if nonoverlapping && src.alloc_id == dest.alloc_id {
+            let gap = if src_offset < dest_offset { dest_offset - src_offset } else { src_offset - dest_offset };
+            let overlap_count = gap / elem_size;
+            if overlap_count < max_count {
+                let overlapping = vec![
+                    Constraint::new_compare(
+                        mir::BinOp::Gt, PrimValKind::U64, count, PrimVal::from_u128(overlap_count as u128)),
+                    Constraint::new_compare(
+                        mir::BinOp::Lt, PrimValKind::U64, count, PrimVal::from_u128(max_count as u128 + 1)),
+                ];
+                if self.memory.constraints.is_feasible_with(&overlapping) {
+                    branches.push(FinishStep {
+                        constraints: overlapping,
+                        variant: FinishStepVariant::Error(EvalError::Intrinsic(
+                            "copy_nonoverlapping called on overlapping ranges".to_owned())),
+                    });
+                }
+                in_bounds_count = overlap_count;
+            }
+        }
+
+        let in_bounds = vec![Constraint::new_compare(
+            mir::BinOp::Lt, PrimValKind::U64, count, PrimVal::from_u128(in_bounds_count as u128 + 1))];
+        if self.memory.constraints.is_feasible_with(&in_bounds) {
+            let min_count = self.memory.constraints.min_value_with(
+                count, PrimValKind::U64, &in_bounds, in_bounds_count);
+            let max_count = self.memory.constraints.max_value_with(
+                count, PrimValKind::U64, &in_bounds, in_bounds_count);
+            let size = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Mul, count, PrimVal::from_u128(elem_size as u128), PrimValKind::U64);
+            self.memory.copy_abstract_size(
+                src, dest, size, min_count * elem_size, max_count * elem_size, elem_align)?;
+            branches.push(FinishStep {
+                constraints: in_bounds,
+                variant: FinishStepVariant::Continue {
+                    goto_block: target,
+                    set_lvalue: None,
+                },
+            });
+        }
+
+        Ok(branches)
+    }
+
+    /// Fills `count` elements of `elem_size` bytes at `ptr` with `val`, where `count` is
+    /// symbolic. Forks into the paths where the filled range is in bounds and where it is not.
+    fn abstract_count_write_bytes(
+        &mut self,
+        ptr: MemoryPointer,
+        val: SByte,
+        count: PrimVal,
+        elem_size: u64,
+        elem_align: u64,
+        target: mir::BasicBlock,
+    ) -> EvalResult<'tcx, Vec<FinishStep<'tcx>>> {
+        let offset = match ptr.offset {
+            PointerOffset::Concrete(offset) => offset,
+            PointerOffset::Abstract(_) => return Err(EvalError::UnsupportedSymbolicOperation {
+                op: "write_bytes with a symbolic count".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
+        };
+        let allocation_size = self.memory.get(ptr.alloc_id)?.bytes.len() as u64;
+        let max_count = allocation_size.saturating_sub(offset) / elem_size;
+
+        let mut branches = Vec::new();
+
+        let out_of_bounds = vec![Constraint::new_compare(
+            mir::BinOp::Gt, PrimValKind::U64, count, PrimVal::from_u128(max_count as u128))];
+        if self.memory.constraints.is_feasible_with(&out_of_bounds) {
+            let size = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Mul, count, PrimVal::from_u128(elem_size as u128), PrimValKind::U64);
+            let end = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Add, PrimVal::from_u128(offset as u128), size, PrimValKind::U64);
+            let end_ptr = match end {
+                PrimVal::Abstract(sbytes) =>
+                    MemoryPointer { alloc_id: ptr.alloc_id, offset: PointerOffset::Abstract(sbytes) },
+                _ => bug!("symbolic write_bytes end is concrete"),
+            };
+            branches.push(FinishStep {
+                constraints: out_of_bounds,
+                variant: FinishStepVariant::Error(
+                    EvalError::PointerOutOfBounds { ptr: end_ptr, access: true, allocation_size }),
+            });
+        }
+
+        let in_bounds = vec![Constraint::new_compare(
+            mir::BinOp::Le, PrimValKind::U64, count, PrimVal::from_u128(max_count as u128))];
+        if self.memory.constraints.is_feasible_with(&in_bounds) {
+            let min_count = self.memory.constraints.min_value_with(
+                count, PrimValKind::U64, &in_bounds, max_count);
+            let max_count = self.memory.constraints.max_value_with(
+                count, PrimValKind::U64, &in_bounds, max_count);
+            let size = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Mul, count, PrimVal::from_u128(elem_size as u128), PrimValKind::U64);
+            self.memory.check_align(ptr, elem_align, max_count * elem_size)?;
+            self.memory.write_repeat_abstract_count(
+                ptr, val, size, min_count * elem_size, max_count * elem_size)?;
+            branches.push(FinishStep {
+                constraints: in_bounds,
+                variant: FinishStepVariant::Continue {
+                    goto_block: target,
+                    set_lvalue: None,
+                },
+            });
+        }
+
+        Ok(branches)
     }
 
     /// Return the size and aligment of the value at the given type.
     /// Note that the value does not matter if the type is sized. For unsized types,
     /// the value has to be a fat pointer, and we only care about the "extra" data in it.
+    /// The size is symbolic if the value is a slice with a symbolic length.
     pub fn size_and_align_of_dst(
         &mut self,
         ty: ty::Ty<'tcx>,
         value: Value,
-    ) -> EvalResult<'tcx, (Size, Align)> {
+    ) -> EvalResult<'tcx, (PrimVal, Align)> {
         let layout = self.type_layout(ty)?;
         if !layout.is_unsized() {
-            Ok(layout.size_and_align())
+            Ok((PrimVal::from_u128(layout.size.bytes() as u128), layout.align))
         } else {
             match ty.sty {
                 ty::TyAdt(..) | ty::TyTuple(..) => {
@@ -539,7 +1037,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     // here. But this is where the add would go.)
 
                     // Return the sum of sizes and max of aligns.
-                    let size = sized_size + unsized_size;
+                    let usize = self.tcx.types.usize;
+                    let sized_size = PrimVal::from_u128(sized_size.bytes() as u128);
+                    let (size, _) = self.binary_op(mir::BinOp::Add, sized_size, usize, unsized_size, usize)?;
 
                     // Choose max of two known alignments (combined value must
                     // be aligned according to more restrictive of the two).
@@ -556,18 +1056,26 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     //
                     //   `(size + (align-1)) & -align`
 
-                    Ok((size.abi_align(align), align))
+                    Ok((self.abi_align_size(size, align)?, align))
                 }
                 ty::TyDynamic(..) => {
                     let (_, vtable) = value.into_ptr_vtable_pair(&mut self.memory)?;
                     // the second entry in the vtable is the dynamic size of the object.
-                    self.read_size_and_align_from_vtable(vtable)
+                    let (size, align) = self.read_size_and_align_from_vtable(vtable)?;
+                    Ok((PrimVal::from_u128(size.bytes() as u128), align))
                 }
 
                 ty::TySlice(_) | ty::TyStr => {
                     let (elem_size, align) = layout.field(&self, 0)?.size_and_align();
                     let (_, len) = value.into_slice(&mut self.memory)?;
-                    Ok((elem_size * len.to_u64()?, align))
+                    if len.is_concrete() {
+                        let size = elem_size * len.to_u64()?;
+                        return Ok((PrimVal::from_u128(size.bytes() as u128), align));
+                    }
+                    let usize = self.tcx.types.usize;
+                    let elem_size = PrimVal::from_u128(elem_size.bytes() as u128);
+                    let (size, _) = self.binary_op(mir::BinOp::Mul, len, usize, elem_size, usize)?;
+                    Ok((size, align))
                 }
 
                 _ => bug!("size_of_val::<{:?}>", ty),
@@ -575,6 +1083,22 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         }
     }
 
+    /// Rounds `size` up to a multiple of `align`, as `Size::abi_align` does for concrete sizes.
+    fn abi_align_size(&mut self, size: PrimVal, align: Align) -> EvalResult<'tcx, PrimVal> {
+        if size.is_concrete() {
+            let size = Size::from_bytes(size.to_u64()?).abi_align(align);
+            return Ok(PrimVal::from_u128(size.bytes() as u128));
+        }
+        // `(size + (align - 1)) & !(align - 1)`
+        let usize = self.tcx.types.usize;
+        let mask = align.abi() as u128 - 1;
+        let bits = self.memory.pointer_size() * 8;
+        let (size, _) = self.binary_op(mir::BinOp::Add, size, usize, PrimVal::from_u128(mask), usize)?;
+        let inverted = !mask & (u128::max_value() >> (128 - bits));
+        let (size, _) = self.binary_op(mir::BinOp::BitAnd, size, usize, PrimVal::from_u128(inverted), usize)?;
+        Ok(size)
+    }
+
     /// Returns the normalized type of a struct field
     fn field_ty(
         &self,
@@ -614,6 +1138,19 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 }
 
                 let result_val = match name {
+                    "bitreverse" => {
+                        let num_bits = kind.num_bytes() as u32 * 8;
+                        let mut reversed = 0u128;
+                        for idx in 0..num_bits {
+                            reversed |= ((bytes >> idx) & 1) << (num_bits - 1 - idx);
+                        }
+                        // Signed values are kept sign-extended, as `integer_intrinsic` does.
+                        if kind.is_signed_int() {
+                            let shift = 128 - num_bits;
+                            reversed = (((reversed << shift) as i128) >> shift) as u128;
+                        }
+                        PrimVal::Bytes(reversed)
+                    }
                     "bswap" => integer_intrinsic!(swap_bytes),
                     "ctlz" => integer_intrinsic!(leading_zeros),
                     "ctpop" => integer_intrinsic!(count_ones),
@@ -634,13 +1171,16 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         }
                         Ok(PrimVal::Abstract(sbytes))
                     }
-                    "cttz" => {
-                        Ok(self.memory.constraints.add_intrinsic_constraint(
-                            ::constraints::NumericIntrinsic::Cttz,
-                            val,
-                            kind))
+                    "bitreverse" | "ctlz" | "ctpop" | "cttz" => {
+                        let op = match name {
+                            "bitreverse" => ::constraints::NumericIntrinsic::Bitreverse,
+                            "ctlz" => ::constraints::NumericIntrinsic::Ctlz,
+                            "ctpop" => ::constraints::NumericIntrinsic::Ctpop,
+                            _ => ::constraints::NumericIntrinsic::Cttz,
+                        };
+                        Ok(self.memory.constraints.add_intrinsic_constraint(op, val, kind))
                     }
-                    _ => unimplemented!(),
+                    _ => bug!("not a numeric intrinsic: {}", name),
                 }
             }
             _ => unimplemented!(),
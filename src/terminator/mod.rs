@@ -8,19 +8,63 @@ use syntax::abi::Abi;
 
 use constraints::Constraint;
 use error::{EvalError, EvalResult};
-use eval_context::{EvalContext, StackPopCleanup, ValTy, is_inhabited};
+use eval_context::{EvalContext, PanicInfo, StackPopCleanup, ValTy, is_inhabited};
 use executor::{FinishStep, FinishStepVariant};
 use lvalue::Lvalue;
-use memory::{SByte};
+use memory::{AllocKind, MemoryPointer, SByte};
 use value::{PrimVal, PrimValKind};
 use value::Value;
 use rustc_data_structures::indexed_vec::Idx;
+use rustc_const_math::ConstMathErr;
 
 mod drop;
 mod intrinsic;
 
+/// Whether `ty` is an integer that the solver supports, i.e. not a 128-bit one.
+fn is_solver_int(ty: Ty) -> bool {
+    use syntax::ast::{IntTy, UintTy};
+    match ty.sty {
+        TypeVariants::TyInt(IntTy::I128) | TypeVariants::TyUint(UintTy::U128) => false,
+        TypeVariants::TyInt(_) | TypeVariants::TyUint(_) => true,
+        _ => false,
+    }
+}
+
+/// Converts a failed MIR math assertion into an `EvalError`.
+fn math_error<'tcx>(span: Span, err: &ConstMathErr) -> EvalError<'tcx> {
+    match *err {
+        ConstMathErr::DivisionByZero |
+        ConstMathErr::RemainderByZero => EvalError::DivisionByZero(Some(span)),
+        _ => EvalError::Math(span, err.clone()),
+    }
+}
+
+/// Whether `msg` belongs to an overflow check that rustc only inserts with debug
+/// assertions. Division overflow is checked in release builds too.
+fn is_overflow_check(msg: &mir::AssertMessage) -> bool {
+    use rustc_const_math::Op;
+    match *msg {
+        mir::AssertMessage::Math(ConstMathErr::Overflow(Op::Div)) |
+        mir::AssertMessage::Math(ConstMathErr::Overflow(Op::Rem)) => false,
+        mir::AssertMessage::Math(ConstMathErr::Overflow(_)) => true,
+        _ => false,
+    }
+}
+
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub(super) fn goto_block(&mut self, target: mir::BasicBlock) {
+        if let Some(ref coverage) = self.coverage {
+            let frame = self.frame();
+            coverage.borrow_mut().edge(&frame.instance.to_string(), frame.block, target);
+        }
+        if self.loop_bound.is_some() {
+            let frame = self.frame_mut();
+            let back_edge = frame.dominators.as_ref()
+                .map_or(false, |dominators| dominators.is_dominated_by(frame.block, target));
+            if back_edge {
+                *frame.loop_visits.entry(target).or_insert(0) += 1;
+            }
+        }
         self.frame_mut().block = target;
         self.frame_mut().stmt = 0;
     }
@@ -50,6 +94,21 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let discr_prim = self.value_to_primval(discr_val, discr_ty)?;
                 let discr_kind = self.ty_to_primval_kind(discr_ty)?;
 
+                // Don't fork if the constraints already pin down the discriminant. Otherwise the
+                // target of the value that was found to be feasible needs no query of its own,
+                // so this costs at most one query more than forking right away.
+                let mut witness = None;
+                let discr_prim = match self.memory.constraints.witness_and_uniqueness(discr_prim, discr_kind) {
+                    Some((n, true)) if !discr_prim.is_concrete() => PrimVal::Bytes(n),
+                    Some((n, false)) => {
+                        witness = Some(n);
+                        discr_prim
+                    }
+                    _ => discr_prim,
+                };
+                // The witness has only the bytes of the discriminant, unlike signed values.
+                let truncate = |n: u128| n & ((1u128 << (8 * discr_kind.num_bytes())) - 1);
+
                 if discr_prim.is_concrete() {
 
                     // Branch to the `otherwise` case by default, if no match is found.
@@ -74,7 +133,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             mir::BinOp::Eq, discr_kind, discr_prim, prim);
                         otherwise_constraints.push(
                             Constraint::new_compare(mir::BinOp::Ne, discr_kind, discr_prim, prim));
-                        if self.memory.constraints.is_feasible_with(&[eq_constraint]) {
+                        if witness.map_or(false, |n| truncate(*const_int) == n) ||
+                            self.memory.constraints.is_feasible_with(&[eq_constraint])
+                        {
                             feasible_blocks_with_constraints.push(
                                 FinishStep {
                                     constraints: vec![eq_constraint],
@@ -86,7 +147,10 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         }
                     }
 
-                    if self.memory.constraints.is_feasible_with(&otherwise_constraints) {
+                    let witness_is_otherwise = witness.map_or(false, |n| {
+                        values.iter().all(|&const_int| truncate(const_int) != n)
+                    });
+                    if witness_is_otherwise || self.memory.constraints.is_feasible_with(&otherwise_constraints) {
                         feasible_blocks_with_constraints.push(
                             FinishStep {
                                 constraints: otherwise_constraints,
@@ -155,7 +219,15 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 Ok(None)
             }
 
+            Assert { ref msg, target, .. } if !self.debug_assertions && is_overflow_check(msg) => {
+                // The overflowing operation has already produced the wrapped value.
+                self.goto_block(target);
+                Ok(None)
+            }
+
             Assert { ref cond, expected, ref msg, target, .. } => {
+                // A symbolic condition that the constraints pin down is found out by the two
+                // feasibility checks below, which then leave a single branch.
                 let cond_val = self.eval_operand_to_primval(cond)?;
                 if cond_val.is_concrete() {
                     let cond_val = cond_val.to_bool()?;
@@ -163,22 +235,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         self.goto_block(target);
                         Ok(None)
                     } else {
-                        match *msg {
-                            mir::AssertMessage::BoundsCheck { ref len, ref index } => {
-                                let span = terminator.source_info.span;
-                                let len = self.eval_operand_to_primval(len)
-                                    .expect("can't eval len")
-                                    .to_u64()?;
-                                let index = self.eval_operand_to_primval(index)
-                                    .expect("can't eval index")
-                                    .to_u64()?;
-                                Err(EvalError::ArrayIndexOutOfBounds(span, len, index))
-                            },
-                            mir::AssertMessage::Math(ref err) =>
-                                Err(EvalError::Math(terminator.source_info.span, err.clone())),
-                            mir::AssertMessage::GeneratorResumedAfterReturn => unimplemented!(),
-                            mir::AssertMessage::GeneratorResumedAfterPanic => unimplemented!(),
-                        }
+                        Err(self.assert_error(msg, terminator.source_info.span, &[])?)
                     }
                 } else {
                     let expected_val = PrimVal::from_bool(expected);
@@ -204,22 +261,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     }
 
                     if self.memory.constraints.is_feasible_with(&fail_constraints[..]) {
-                        let e = match *msg {
-                            mir::AssertMessage::BoundsCheck { ref len, ref index } => {
-                                let span = terminator.source_info.span;
-                                let len = self.eval_operand_to_primval(len)
-                                    .expect("can't eval len")
-                                    .to_u64()?;
-                                let index = self.eval_operand_to_primval(index)
-                                    .expect("can't eval index")
-                                    .to_u64()?;
-                                EvalError::ArrayIndexOutOfBounds(span, len, index)
-                            },
-                            mir::AssertMessage::Math(ref err) =>
-                                EvalError::Math(terminator.source_info.span, err.clone()),
-                            mir::AssertMessage::GeneratorResumedAfterReturn => unimplemented!(),
-                            mir::AssertMessage::GeneratorResumedAfterPanic => unimplemented!(),
-                        };
+                        let e = self.assert_error(msg, terminator.source_info.span, &fail_constraints)?;
 
                         finish_steps.push(
                             FinishStep {
@@ -235,7 +277,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             DropAndReplace { .. } => unimplemented!(),
             Resume => unimplemented!(),
             Abort => unimplemented!(),
-            Unreachable => Err(EvalError::Unreachable),
+            Unreachable => self.reached_undefined_behavior(EvalError::Unreachable),
             Yield { .. } => unimplemented!(),
             GeneratorDrop => unimplemented!(),
             FalseEdges { .. } => unimplemented!(),
@@ -243,6 +285,47 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         }
     }
 
+    /// Reports `err` if the path can be taken by some input, which the consumer receives
+    /// as the witness. Otherwise the path is dropped, since the program never gets here.
+    pub(super) fn reached_undefined_behavior(
+        &self,
+        err: EvalError<'tcx>,
+    ) -> EvalResult<'tcx, Option<Vec<FinishStep<'tcx>>>> {
+        if self.memory.constraints.is_feasible_with(&[]) {
+            Err(err)
+        } else {
+            Ok(Some(Vec::new()))
+        }
+    }
+
+    /// Builds the error for a failed `Assert`. Symbolic operands of the message are
+    /// replaced by values that satisfy `constraints` on top of the path constraints.
+    fn assert_error(
+        &mut self,
+        msg: &mir::AssertMessage<'tcx>,
+        span: Span,
+        constraints: &[Constraint],
+    ) -> EvalResult<'tcx, EvalError<'tcx>> {
+        match *msg {
+            mir::AssertMessage::BoundsCheck { ref len, ref index } => {
+                let len = self.eval_operand_to_primval(len).expect("can't eval len");
+                let index = self.eval_operand_to_primval(index).expect("can't eval index");
+                let witness = |val: PrimVal| -> EvalResult<'tcx, u64> {
+                    match self.memory.constraints.get_witness_with(val, PrimValKind::U64, constraints) {
+                        Some(n) => Ok(n as u64),
+                        None => val.to_u64(),
+                    }
+                };
+                Ok(EvalError::ArrayIndexOutOfBounds(span, witness(len)?, witness(index)?))
+            },
+            mir::AssertMessage::Math(ref err) => Ok(math_error(span, err)),
+            // Resuming a finished generator panics in compiled code too. Unlike the panics that
+            // go through `panic`, the handler is not called as this may only be one side of a fork.
+            mir::AssertMessage::GeneratorResumedAfterReturn |
+            mir::AssertMessage::GeneratorResumedAfterPanic => Ok(EvalError::Panic),
+        }
+    }
+
     /// Decides whether it is okay to call the method with signature `real_sig` using signature `sig`.
     /// FIXME: This should take into account the platform-dependent ABI description.
     fn check_sig_compat(
@@ -319,16 +402,16 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             ty::InstanceDef::Intrinsic(..) => {
                 let (ret, target) = match destination {
                     Some(dest) => dest,
-                    _ => return Err(EvalError::Unreachable),
+                    _ => return self.reached_undefined_behavior(EvalError::Unreachable),
                 };
                 let ty = sig.output();
                 if !is_inhabited(self.tcx, ty) {
-                    return Err(EvalError::Unreachable);
+                    return self.reached_undefined_behavior(EvalError::Unreachable);
                 }
                 let layout = self.type_layout(ty)?;
-                self.call_intrinsic(instance, arg_operands, ret, ty, layout, target)?;
+                let branches = self.call_intrinsic(instance, arg_operands, ret, ty, layout, target)?;
                 self.dump_local(ret);
-                Ok(None)
+                Ok(branches)
             },
             ty::InstanceDef::ClosureOnceShim{..} => {
                 let mut args = Vec::new();
@@ -337,14 +420,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     let arg_ty = self.operand_ty(arg);
                     args.push(ValTy { value: arg_val, ty: arg_ty});
                 }
-                if self.eval_fn_call_inner(
+                if let Some(branches) = self.eval_fn_call_inner(
                     instance,
                     destination,
                     arg_operands,
                     span,
                     sig,
                 )? {
-                    return Ok(None);
+                    return Ok(branches);
                 }
                 let mut arg_locals = self.frame().mir.args_iter();
                 match sig.abi {
@@ -392,14 +475,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     args.push(ValTy { value: arg_val, ty: arg_ty });
                 }
 
-                if self.eval_fn_call_inner(
+                if let Some(branches) = self.eval_fn_call_inner(
                     instance,
                     destination,
                     arg_operands,
                     span,
                     sig,
                 )? {
-                    return Ok(None);
+                    return Ok(branches);
                 }
 
                 let mut arg_locals = self.frame().mir.args_iter();
@@ -508,6 +591,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     }
 
     /// Returns Ok(true) when the function was handled completely due to mir not being available
+    /// Returns `Some` if the call was handled without pushing a stack frame, along
+    /// with the branches the call forked into, if any.
     fn eval_fn_call_inner(
         &mut self,
         instance: ty::Instance<'tcx>,
@@ -515,7 +600,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         arg_operands: &[mir::Operand<'tcx>],
         span: Span,
         sig: ty::FnSig<'tcx>,
-    ) -> EvalResult<'tcx, bool> {
+    ) -> EvalResult<'tcx, Option<Option<Vec<FinishStep<'tcx>>>>> {
         trace!("eval_fn_call_inner: {:#?}, {:#?}", instance, destination);
 
         // Try to intercept some calls, regardless of whether MIR exists for them or not.
@@ -523,10 +608,87 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         match instance.def {
             ty::InstanceDef::Item(def_id) => {
                 match self.tcx.item_path_str(def_id).as_str() {
+                    path if self.opaque_fns.iter().any(|f| f == path) => {
+                        let (lval, block) = match destination {
+                            Some(destination) => destination,
+                            None => return Err(EvalError::Unimplemented(
+                                format!("skipping the diverging function {}", path))),
+                        };
+                        let ty = sig.output();
+                        let value = self.mk_symbolic(ty)?;
+                        self.write_value(ValTy { value, ty }, lval)?;
+                        if let Value::ByRef(ptr) = value {
+                            self.memory.deallocate(ptr)?;
+                        }
+                        self.goto_block(block);
+                        return Ok(Some(None));
+                    }
+                    "std::rt::begin_panic" |
+                    "std::panicking::begin_panic" if self.panic_handler.is_some() => {
+                        let message_ty = self.operand_ty(&arg_operands[0]);
+                        let is_str = message_ty.builtin_deref(true).map_or(false, |tam| match tam.ty.sty {
+                            TypeVariants::TyStr => true,
+                            _ => false,
+                        });
+                        let message = if is_str {
+                            let message = self.eval_operand(&arg_operands[0])?;
+                            self.read_concrete_str(message)?
+                        } else {
+                            None
+                        };
+                        let location = self.eval_operand(&arg_operands[1])?;
+                        let location = self.read_panic_location(location, self.operand_ty(&arg_operands[1]))?;
+                        return Err(self.panic(PanicInfo { message, location, span }));
+                    }
+                    "std::rt::begin_panic_fmt" |
+                    "std::panicking::begin_panic_fmt" if self.panic_handler.is_some() => {
+                        let location = self.eval_operand(&arg_operands[1])?;
+                        let location = self.read_panic_location(location, self.operand_ty(&arg_operands[1]))?;
+                        return Err(self.panic(PanicInfo { message: None, location, span }));
+                    }
+                    "seer::mk_symbolic" => {
+                        let (lval, block) = destination.expect("mk_symbolic() does not diverge");
+                        let ty = sig.output();
+                        let value = self.mk_symbolic(ty)?;
+                        self.write_value(ValTy { value, ty }, lval)?;
+                        if let Value::ByRef(ptr) = value {
+                            // `write_value` copied the allocation into the destination.
+                            self.memory.deallocate(ptr)?;
+                        }
+                        self.goto_block(block);
+                        return Ok(Some(None));
+                    }
+                    "std::cmp::Ord::min" |
+                    "std::cmp::Ord::max" if is_solver_int(self.operand_ty(&arg_operands[0])) => {
+                        // Selecting the result keeps symbolic operands from forking the path
+                        // on the comparison in the default implementation.
+                        let (lval, block) = destination.expect("Ord::min() and Ord::max() do not diverge");
+                        let ty = self.operand_ty(&arg_operands[0]);
+                        let a = self.eval_operand_to_primval(&arg_operands[0])?;
+                        let b = self.eval_operand_to_primval(&arg_operands[1])?;
+                        let max = &self.tcx.item_name(def_id)[..] == "max";
+                        let result = self.min_max(max, a, b, ty)?;
+                        self.write_primval(lval, result, ty)?;
+                        self.goto_block(block);
+                        return Ok(Some(None));
+                    }
+                    "std::ptr::swap" |
+                    "std::mem::swap" => {
+                        // Swapping through a single temporary keeps the bytes as they are and
+                        // skips the block-wise loop of the library implementation.
+                        let (_lval, block) = destination.expect("swap() does not diverge");
+                        let ty = instance.substs.type_at(0);
+                        let (size, align) = self.type_layout(ty)?.size_and_align();
+                        let a = self.eval_operand_to_primval(&arg_operands[0])?;
+                        let b = self.eval_operand_to_primval(&arg_operands[1])?;
+                        self.memory.swap(a, b, size.bytes(), align.abi())?;
+                        self.goto_block(block);
+                        return Ok(Some(None));
+                    }
                     "std::io::stdin" => {
                         let (_lval, block) = destination.expect("std::io::stdin() does not diverge");
                         self.goto_block(block);
-                        return Ok(true);
+                        return Ok(Some(None));
                     }
                     "<std::io::Stdin as std::io::Read>::read" => {
                         let (lval, block) = destination.expect("Stdin::read() does not diverge");
@@ -538,6 +700,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         let num_bytes = match args[1] {
                             Value::ByValPair(PrimVal::Ptr(ptr), PrimVal::Bytes(len)) => {
                                 self.memory.write_fresh_abstract_bytes(ptr, len as u64)?;
+                                if !self.assume_stdin_constraints(ptr, len as u64)? {
+                                    return Ok(Some(Some(Vec::new())));
+                                }
                                 len
                             }
                             _ => {
@@ -559,7 +724,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             num_bytes, usize_bytes)?; // payload
 
                         self.goto_block(block);
-                        return Ok(true);
+                        return Ok(Some(None));
                     }
                     "<std::io::Stdin as std::io::Read>::read_exact" => {
                         let (lval, block) = destination.expect("Stdin::read() does not diverge");
@@ -571,6 +736,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         match args[1] {
                             Value::ByValPair(PrimVal::Ptr(ptr), PrimVal::Bytes(len)) => {
                                 self.memory.write_fresh_abstract_bytes(ptr, len as u64)?;
+                                if !self.assume_stdin_constraints(ptr, len as u64)? {
+                                    return Ok(Some(Some(Vec::new())));
+                                }
                             }
                             _ => {
                                 unimplemented!()
@@ -586,7 +754,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             0)?;
 
                         self.goto_block(block);
-                        return Ok(true);
+                        return Ok(Some(None));
                     }
 
                     "std::io::Stdin::lock" => {
@@ -594,6 +762,20 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             EvalError::Unimplemented(
                                 "no abstract implementation for stdin.lock()".into()));
                     }
+                    _ if self.is_derived_partial_eq(def_id) => {
+                        let (lval, block) = destination.expect("PartialEq::eq() does not diverge");
+                        let ty = self.operand_ty(&arg_operands[0]);
+                        let ty = ty.builtin_deref(true).expect("PartialEq takes references").ty;
+                        let a = self.eval_operand_to_primval(&arg_operands[0])?.to_ptr()?;
+                        let b = self.eval_operand_to_primval(&arg_operands[1])?.to_ptr()?;
+                        let ne = &self.tcx.item_name(def_id)[..] == "ne";
+                        if let Some(result) = self.derived_struct_eq(ne, ty, a, b)? {
+                            let bool = self.tcx.types.bool;
+                            self.write_primval(lval, result, bool)?;
+                            self.goto_block(block);
+                            return Ok(Some(None));
+                        }
+                    }
                     _ => (),
                 }
             }
@@ -605,8 +787,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let mir = match self.load_mir(instance.def) {
             Ok(mir) => mir,
             Err(EvalError::NoMirFor(path)) => {
-                self.call_missing_fn(instance, destination, arg_operands, sig, path)?;
-                return Ok(true);
+                let branches = self.call_missing_fn(instance, destination, arg_operands, sig, path)?;
+                return Ok(Some(branches));
             }
             Err(other) => return Err(other),
         };
@@ -624,7 +806,66 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             return_to_block,
         )?;
 
-        Ok(false)
+        Ok(None)
+    }
+
+    /// Returns true if `def_id` is a method of a `#[derive(PartialEq)]` impl.
+    fn is_derived_partial_eq(&self, def_id: DefId) -> bool {
+        let impl_id = match self.tcx.impl_of_method(def_id) {
+            Some(impl_id) => impl_id,
+            None => return false,
+        };
+        let trait_id = self.tcx.impl_trait_ref(impl_id).map(|trait_ref| trait_ref.def_id);
+        let is_eq = self.tcx.lang_items().eq_trait().map_or(false, |eq| trait_id == Some(eq));
+        is_eq && self.tcx.has_attr(impl_id, "automatically_derived")
+    }
+
+    /// Compares the structs at `a` and `b` like the derived `eq` (or `ne`) would, if all the
+    /// fields of `ty` are integers, bools or chars. The field comparisons are combined into a
+    /// single boolean, so that symbolic fields do not fork the path once per field.
+    fn derived_struct_eq(
+        &mut self,
+        ne: bool,
+        ty: Ty<'tcx>,
+        a: MemoryPointer,
+        b: MemoryPointer,
+    ) -> EvalResult<'tcx, Option<PrimVal>> {
+        match ty.sty {
+            TypeVariants::TyAdt(adt_def, _) if adt_def.is_struct() => {}
+            _ => return Ok(None),
+        }
+        let layout = self.type_layout(ty)?;
+        let mut fields = Vec::with_capacity(layout.fields.count());
+        for i in 0..layout.fields.count() {
+            let field_ty = layout.field(&self, i)?.ty;
+            let scalar = is_solver_int(field_ty) || match field_ty.sty {
+                TypeVariants::TyBool | TypeVariants::TyChar => true,
+                _ => false,
+            };
+            if !scalar {
+                return Ok(None);
+            }
+            fields.push((layout.fields.offset(i).bytes(), field_ty));
+        }
+
+        let (compare, combine) = if ne {
+            (mir::BinOp::Ne, mir::BinOp::BitOr)
+        } else {
+            (mir::BinOp::Eq, mir::BinOp::BitAnd)
+        };
+        let bool = self.tcx.types.bool;
+        let mut result = PrimVal::from_bool(!ne);
+        for (offset, field_ty) in fields {
+            let a_field = self.offset_lvalue_ptr(a, offset)?;
+            let b_field = self.offset_lvalue_ptr(b, offset)?;
+            let a_val = self.read_value(a_field, field_ty)?;
+            let a_val = self.value_to_primval(a_val, field_ty)?;
+            let b_val = self.read_value(b_field, field_ty)?;
+            let b_val = self.value_to_primval(b_val, field_ty)?;
+            let (field_result, _) = self.binary_op(compare, a_val, field_ty, b_val, field_ty)?;
+            result = self.binary_op(combine, result, bool, field_result, bool)?.0;
+        }
+        Ok(Some(result))
     }
 
     /// Returns Ok() when the function was handled, fail otherwise
@@ -635,13 +876,12 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         arg_operands: &[mir::Operand<'tcx>],
         sig: ty::FnSig<'tcx>,
         path: String,
-    ) -> EvalResult<'tcx> {
+    ) -> EvalResult<'tcx, Option<Vec<FinishStep<'tcx>>>> {
         if sig.abi == Abi::C {
             // An external C function
             let ty = sig.output();
             let (ret, target) = destination.unwrap();
-            self.call_c_abi(instance.def_id(), arg_operands, ret, ty, target)?;
-            return Ok(());
+            return self.call_c_abi(instance.def_id(), arg_operands, ret, ty, target);
         }
 
         // A Rust function is missing, which means we are running with MIR missing for libstd (or other dependencies).
@@ -656,12 +896,15 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             "std::io::_print" => {
                 trace!("Ignoring output.  To run programs that print, make sure you have a libstd with full MIR.");
                 self.goto_block(destination.unwrap().1);
-                Ok(())
+                Ok(None)
             },
             "std::thread::Builder::new" => Err(EvalError::Unimplemented("miri does not support threading".to_owned())),
             "std::env::args" => Err(EvalError::Unimplemented("miri does not support program arguments".to_owned())),
             "std::panicking::rust_panic_with_hook" |
-            "std::rt::begin_panic_fmt" => Err(EvalError::Panic),
+            "std::rt::begin_panic_fmt" => {
+                let span = self.frame().span;
+                Err(self.panic(PanicInfo { message: None, location: None, span }))
+            }
             "std::panicking::panicking" |
             "std::rt::panicking" => {
                 let (lval, block) = destination.expect("std::rt::panicking does not diverge");
@@ -669,21 +912,23 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let bool = self.tcx.types.bool;
                 self.write_primval(lval, PrimVal::from_bool(false), bool)?;
                 self.goto_block(block);
-                Ok(())
+                Ok(None)
             }
             "alloc::allocator::Layout::from_size_align" => {
                 let (lval, block) = destination.expect("from_size_align() does not diverge");
                 let dest_ptr = self.force_allocation(lval)?.to_ptr()?;
 
                 let usize = self.tcx.types.usize;
-                let size = self.value_to_primval(args[0], usize)?.to_u128()?;
+                let size = self.value_to_primval(args[0], usize)?;
                 let align = self.value_to_primval(args[1], usize)?.to_u128()?;
 
                 if !align.is_power_of_two() {
                     unimplemented!();
                 }
 
-                if size as usize > ::std::usize::MAX - (align as usize - 1) {
+                // TODO: fork on the overflow check for symbolic sizes
+                if size.is_concrete() &&
+                    size.to_u128()? as usize > ::std::usize::MAX - (align as usize - 1) {
                     unimplemented!();
                 }
 
@@ -693,43 +938,54 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 self.memory.write_uint(dest_ptr, 1, usize_bytes)?; // discriminant = Some
 
                 // payload
-                self.memory.write_uint(dest_ptr.offset(usize_bytes, self.memory.layout)?,
-                                       size, usize_bytes)?;
+                self.memory.write_primval(PrimVal::Ptr(dest_ptr.offset(usize_bytes, self.memory.layout)?),
+                                          size, usize_bytes)?;
                 self.memory.write_uint(dest_ptr.offset(usize_bytes * 2, self.memory.layout)?,
                                        align, usize_bytes)?;
 
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             "alloc::alloc::::__rust_alloc" => {
                 let usize = self.tcx.types.usize;
-                let size = self.value_to_primval(args[0], usize)?.to_u64()?;
+                let size = self.value_to_primval(args[0], usize)?;
                 let align = self.value_to_primval(args[1], usize)?.to_u64()?;
-                let ptr = self.memory.allocate(size, align)?;
 
                 let (lval, block) = destination.expect("alloc() does not diverge");
+                if let PrimVal::Abstract(sbytes) = size {
+                    return self.allocate_abstract(sbytes, align, false, None, lval, sig.output(), block)
+                        .map(Some);
+                }
+
+                let ptr = self.memory.allocate_with_kind(size.to_u64()?, align, AllocKind::Heap)?;
                 let dest_ptr = self.force_allocation(lval)?.to_ptr()?;
 
                 self.memory.write_ptr(dest_ptr, ptr)?;
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             "alloc::alloc::::__rust_alloc_zeroed" => {
                 let usize = self.tcx.types.usize;
-                let size = self.value_to_primval(args[0], usize)?.to_u64()?;
+                let size = self.value_to_primval(args[0], usize)?;
                 let align = self.value_to_primval(args[1], usize)?.to_u64()?;
-                let ptr = self.memory.allocate(size, align)?;
-                self.memory.write_repeat(ptr, 0, size)?;
-
 
                 let (lval, block) = destination.expect("alloc() does not diverge");
+                if let PrimVal::Abstract(sbytes) = size {
+                    return self.allocate_abstract(sbytes, align, true, None, lval, sig.output(), block)
+                        .map(Some);
+                }
+
+                let size = size.to_u64()?;
+                let ptr = self.memory.allocate_with_kind(size, align, AllocKind::Heap)?;
+                self.memory.write_repeat(ptr, 0, size)?;
+
                 let dest_ptr = self.force_allocation(lval)?.to_ptr()?;
 
                 self.memory.write_ptr(dest_ptr, ptr)?;
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             "alloc::allocator::Layout::size" => {
@@ -738,14 +994,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let usize_bytes = self.memory.pointer_size();
                 let self_size = match args[0] {
                     Value::ByVal(PrimVal::Ptr(ptr)) => {
-                        self.memory.read_uint(ptr, usize_bytes)?
+                        self.memory.read_primval(ptr, usize_bytes, false)?
                     }
                     _ => unreachable!(),
                 };
 
-                self.write_primval(lval, PrimVal::Bytes(self_size), sig.output())?;
+                self.write_primval(lval, self_size, sig.output())?;
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             "alloc::allocator::Layout::repeat" => {
@@ -795,12 +1051,11 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     padded_size as u128, usize_bytes)?;
 
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             "alloc::alloc::::__rust_realloc" => {
                 let (lval, block) = destination.expect("realloc() does not diverge");
-                let dest_ptr = self.force_allocation(lval)?.to_ptr()?;
 
                 let ptr = match args[0] {
                     Value::ByVal(PrimVal::Ptr(p)) => p,
@@ -808,14 +1063,19 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 };
 
                 let usize = self.tcx.types.usize;
-                let _old_size = self.value_to_primval(args[1], usize)?.to_u64()?;
                 let align = self.value_to_primval(args[2], usize)?.to_u64()?;
-                let new_size = self.value_to_primval(args[3], usize)?.to_u64()?;
+                let new_size = self.value_to_primval(args[3], usize)?;
+                if let PrimVal::Abstract(sbytes) = new_size {
+                    return self.allocate_abstract(sbytes, align, false, Some(ptr), lval, sig.output(), block)
+                        .map(Some);
+                }
+                let new_size = new_size.to_u64()?;
 
+                let dest_ptr = self.force_allocation(lval)?.to_ptr()?;
                 let new_ptr = self.memory.reallocate(ptr, new_size, align)?;
                 self.memory.write_ptr(dest_ptr, new_ptr)?;
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             "alloc::alloc::::__rust_dealloc" => {
@@ -826,15 +1086,145 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     _ => unimplemented!(),
                 };
 
-                self.memory.deallocate(ptr)?;
+                let span = self.local_span();
+                self.memory.deallocate_at(ptr, span)?;
                 self.goto_block(block);
-                return Ok(());
+                return Ok(None);
             }
 
             _ => Err(EvalError::NoMirFor(path)),
         }
     }
 
+    /// Passes `info` to the panic handler, if there is one.
+    fn panic(&mut self, info: PanicInfo) -> EvalError<'tcx> {
+        if let Some(ref handler) = self.panic_handler {
+            (&mut *handler.borrow_mut())(&info);
+        }
+        self.panic_info = Some(info);
+        EvalError::Panic
+    }
+
+    /// Assumes the constraints that the `stdin_constraints` callback returns for the `len`
+    /// bytes at `ptr`, which were just read from stdin. Returns false if they cannot hold.
+    fn assume_stdin_constraints(&mut self, ptr: MemoryPointer, len: u64) -> EvalResult<'tcx, bool> {
+        let callback = match self.stdin_constraints {
+            Some(ref callback) => callback.clone(),
+            None => return Ok(true),
+        };
+        let bytes: Vec<PrimVal> = self.memory.read_bytes(PrimVal::Ptr(ptr), len)?.iter().map(|&sbyte| {
+            let mut sbytes = [SByte::Concrete(0); 8];
+            sbytes[0] = sbyte;
+            PrimVal::Abstract(sbytes)
+        }).collect();
+        let constraints = (&mut *callback.borrow_mut())(&bytes);
+        for constraint in constraints {
+            if !self.assume_constraint(constraint) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads the contents of a `&str`, if they are concrete.
+    fn read_concrete_str(&self, value: Value) -> EvalResult<'tcx, Option<String>> {
+        let (ptr, len) = value.into_slice(&self.memory)?;
+        let bytes = self.memory.read_bytes(ptr, len.to_u64()?)?;
+        let mut result = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            match byte {
+                SByte::Concrete(b) => result.push(b),
+                SByte::Abstract(_) => return Ok(None),
+            }
+        }
+        Ok(Some(String::from_utf8_lossy(&result).into_owned()))
+    }
+
+    /// Reads the `&(file, line, column)` argument of the panic entry points.
+    fn read_panic_location(
+        &self,
+        location: Value,
+        location_ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, Option<(String, u32, u32)>> {
+        let tuple_ty = location_ty.builtin_deref(true).expect("panic location is not a reference").ty;
+        let layout = self.type_layout(tuple_ty)?;
+        let ptr = location.read_ptr(&self.memory)?.to_ptr()?;
+        let field = |i| ptr.offset(layout.fields.offset(i).bytes(), self.memory.layout);
+
+        let file = self.read_concrete_str(Value::ByRef(field(0)?))?;
+        let line = self.memory.read_primval(field(1)?, 4, false)?.to_u128()? as u32;
+        let column = self.memory.read_primval(field(2)?, 4, false)?.to_u128()? as u32;
+        Ok(file.map(|file| (file, line, column)))
+    }
+
+    /// Forks an allocation of the symbolic `size` into the paths where it is zero,
+    /// too large, or successfully allocated and written to `dest`. The allocated bytes
+    /// are zero if `zeroed` is set, and undefined otherwise. If `realloc` is set, that
+    /// allocation is resized instead, keeping its bytes.
+    fn allocate_abstract(
+        &mut self,
+        size: [SByte; 8],
+        align: u64,
+        zeroed: bool,
+        realloc: Option<MemoryPointer>,
+        dest: Lvalue<'tcx>,
+        dest_ty: Ty<'tcx>,
+        target: mir::BasicBlock,
+    ) -> EvalResult<'tcx, Vec<FinishStep<'tcx>>> {
+        let size_val = PrimVal::Abstract(size);
+        let mut free = self.memory.free_memory();
+        if let Some(ptr) = realloc {
+            free += self.memory.get(ptr.alloc_id)?.usage;
+        }
+        let fits = PrimVal::from_u128(free as u128 + 1);
+        let free = PrimVal::from_u128(free as u128);
+        let mut branches = Vec::new();
+
+        let zero_constraints = vec![
+            Constraint::new_compare(mir::BinOp::Eq, PrimValKind::U64, size_val, PrimVal::Bytes(0))];
+        if self.memory.constraints.is_feasible_with(&zero_constraints) {
+            branches.push(FinishStep {
+                constraints: zero_constraints,
+                variant: FinishStepVariant::Error(EvalError::HeapAllocZeroBytes),
+            });
+        }
+
+        let oom_constraints = vec![
+            Constraint::new_compare(mir::BinOp::Gt, PrimValKind::U64, size_val, free)];
+        if let Some(allocation_size) =
+            self.memory.constraints.get_witness_with(size_val, PrimValKind::U64, &oom_constraints)
+        {
+            branches.push(FinishStep {
+                constraints: oom_constraints,
+                variant: FinishStepVariant::Error(EvalError::OutOfMemory {
+                    allocation_size: allocation_size as u64,
+                    memory_size: self.memory.memory_size(),
+                    memory_usage: self.memory.memory_size() - self.memory.free_memory(),
+                }),
+            });
+        }
+
+        let success_constraints = vec![
+            Constraint::new_compare(mir::BinOp::Ne, PrimValKind::U64, size_val, PrimVal::Bytes(0)),
+            Constraint::new_compare(mir::BinOp::Lt, PrimValKind::U64, size_val, fits),
+        ];
+        if self.memory.constraints.is_feasible_with(&success_constraints) {
+            let ptr = match realloc {
+                Some(ptr) => self.memory.reallocate_abstract(ptr, size, align, &success_constraints)?,
+                None => self.memory.allocate_abstract(size, align, &success_constraints, zeroed)?,
+            };
+            branches.push(FinishStep {
+                constraints: success_constraints,
+                variant: FinishStepVariant::Continue {
+                    goto_block: target,
+                    set_lvalue: Some((dest, PrimVal::Ptr(ptr), dest_ty)),
+                },
+            });
+        }
+
+        Ok(branches)
+    }
+
     fn call_c_abi(
         &mut self,
         def_id: DefId,
@@ -1004,11 +1394,33 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 }*/
             }
 
+            "strlen" => {
+                let ptr = args[0].read_ptr(&self.memory)?.to_ptr()?;
+                let mut lengths = self.memory.c_str_lengths(ptr, self.max_c_str_len)?;
+                if lengths.len() == 1 && lengths[0].1.is_empty() {
+                    let len = lengths.pop().unwrap().0?;
+                    self.write_primval(dest, PrimVal::from_u128(len as u128), dest_ty)?;
+                    self.goto_block(target);
+                } else {
+                    let branches = lengths.into_iter().map(|(len, constraints)| {
+                        let variant = match len {
+                            Ok(len) => FinishStepVariant::Continue {
+                                goto_block: target,
+                                set_lvalue: Some((dest, PrimVal::from_u128(len as u128), dest_ty)),
+                            },
+                            Err(e) => FinishStepVariant::Error(e),
+                        };
+                        FinishStep { constraints, variant }
+                    }).collect();
+                    return Ok(Some(branches));
+                }
+            }
+
             "getenv" => {
                 let result = {
                     let name_ptr = args[0].read_ptr(&self.memory)?.to_ptr()?;
                     let name = self.memory.read_c_str(name_ptr)?;
-                    match self.env_vars.get(name) {
+                    match self.env_vars.get(&name) {
                         Some(&var) => PrimVal::Ptr(var),
                         None => PrimVal::Bytes(0),
                     }
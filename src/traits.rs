@@ -82,6 +82,10 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub fn get_vtable(&mut self, ty: Ty<'tcx>, trait_ref: ty::PolyTraitRef<'tcx>) -> EvalResult<'tcx, MemoryPointer> {
         debug!("get_vtable(trait_ref={:?})", trait_ref);
 
+        if let Some(&vtable) = self.vtables.get(&(ty, trait_ref)) {
+            return Ok(vtable);
+        }
+
         let size = self.type_size(trait_ref.self_ty())?.expect("can't create a vtable for an unsized type");
         let align = self.type_align(trait_ref.self_ty())?;
 
@@ -105,6 +109,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         }
 
         self.memory.mark_static_initalized(vtable.alloc_id, false)?;
+        self.vtables.insert((ty, trait_ref), vtable);
 
         Ok(vtable)
     }
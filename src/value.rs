@@ -1,6 +1,7 @@
 use std::mem::transmute;
 use rustc::ty::layout::TargetDataLayout;
 
+use constraints::ConstraintContext;
 use error::{EvalError, EvalResult};
 use memory::{Memory, MemoryPointer, SByte};
 
@@ -34,7 +35,7 @@ pub(super) fn bytes_to_bool(n: u128) -> bool {
 /// For optimization of a few very common cases, there is also a representation for a pair of
 /// primitive values (`ByValPair`). It allows Miri to avoid making allocations for checked binary
 /// operations and fat pointers. This idea was taken from rustc's trans.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Value {
     ByRef(MemoryPointer),
     ByVal(PrimVal),
@@ -45,7 +46,7 @@ pub enum Value {
 /// `memory::Allocation`. It is in many ways like a small chunk of a `Allocation`, up to 8 bytes in
 /// size. Like a range of bytes in an `Allocation`, a `PrimVal` can either represent the raw bytes
 /// of a simple value, a pointer into another `Allocation`, or be undefined.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum PrimVal {
     /// The raw bytes of a simple value.
     Bytes(u128),
@@ -63,7 +64,7 @@ pub enum PrimVal {
     Undef,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum PrimValKind {
     I8, I16, I32, I64, I128,
     U8, U16, U32, U64, U128,
@@ -106,8 +107,13 @@ impl<'a, 'tcx: 'a> Value {
         match *self {
             ByRef(ref_ptr) => {
                 let ptr = mem.read_ptr(ref_ptr)?;
-                let len = mem.read_usize(ref_ptr.offset(mem.pointer_size(), mem.layout)?)?;
-                Ok((ptr, PrimVal::Bytes(len as u128)))
+                let len_ptr = ref_ptr.offset(mem.pointer_size(), mem.layout)?;
+                let len = if mem.points_to_concrete(len_ptr, mem.pointer_size())? {
+                    PrimVal::Bytes(mem.read_usize(len_ptr)? as u128)
+                } else {
+                    mem.read_abstract(PrimVal::Ptr(len_ptr), mem.pointer_size())?
+                };
+                Ok((ptr, len))
             },
             ByValPair(ptr, val) => {
                 Ok((ptr, val))
@@ -115,6 +121,24 @@ impl<'a, 'tcx: 'a> Value {
             _ => unimplemented!(),
         }
     }
+
+    /// Joins `self` and `other`, the values of the same local on two merged paths, into a
+    /// value that is `self` where `guard` holds and `other` otherwise. `kind` is the kind
+    /// of the local, if it is a primitive.
+    pub(super) fn merge(
+        self,
+        other: Value,
+        kind: Option<PrimValKind>,
+        guard: PrimVal,
+        constraints: &mut ConstraintContext,
+    ) -> Option<Value> {
+        use self::Value::*;
+        match (self, other, kind) {
+            _ if self == other => Some(self),
+            (ByVal(a), ByVal(b), Some(kind)) => a.merge(b, kind, guard, constraints).map(ByVal),
+            _ => None,
+        }
+    }
 }
 
 impl<'tcx> PrimVal {
@@ -125,6 +149,35 @@ impl<'tcx> PrimVal {
         }
     }
 
+    /// Returns the value of `self` if `constraints` allow it to take only one.
+    pub fn try_concretize(&self, kind: PrimValKind, constraints: &ConstraintContext) -> Option<u128> {
+        constraints.try_concretize(*self, kind)
+    }
+
+    /// Returns a value that is `self` where `guard` holds and `other` otherwise, or `None`
+    /// if the solver cannot choose between them.
+    pub(super) fn merge(
+        self,
+        other: PrimVal,
+        kind: PrimValKind,
+        guard: PrimVal,
+        constraints: &mut ConstraintContext,
+    ) -> Option<PrimVal> {
+        use self::PrimValKind::*;
+        if self == other {
+            return Some(self);
+        }
+        match (self, other, kind) {
+            (PrimVal::Ptr(_), _, _) | (_, PrimVal::Ptr(_), _) |
+            (PrimVal::Undef, _, _) | (_, PrimVal::Undef, _) => None,
+            (_, _, Bool) |
+            (_, _, I8) | (_, _, I16) | (_, _, I32) | (_, _, I64) |
+            (_, _, U8) | (_, _, U16) | (_, _, U32) | (_, _, U64) |
+            (_, _, F32) | (_, _, F64) => Some(constraints.add_if_then_else(guard, kind, self, other)),
+            _ => None,
+        }
+    }
+
     pub fn from_u128(n: u128) -> Self {
         PrimVal::Bytes(n)
     }
@@ -152,7 +205,10 @@ impl<'tcx> PrimVal {
     pub fn to_bytes(self) -> EvalResult<'tcx, u128> {
         match self {
             PrimVal::Bytes(b) => Ok(b),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::UnsupportedSymbolicOperation {
+                op: "conversion to concrete bytes".to_owned(),
+                ty: "integer".to_owned(),
+            }),
             PrimVal::Ptr(_) => Err(EvalError::ReadBytesAsPointer),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
         }
@@ -161,7 +217,10 @@ impl<'tcx> PrimVal {
     pub fn to_ptr(self) -> EvalResult<'tcx, MemoryPointer> {
         match self {
             PrimVal::Bytes(_) => Err(EvalError::ReadBytesAsPointer),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::UnsupportedSymbolicOperation {
+                op: "conversion to a pointer".to_owned(),
+                ty: "integer".to_owned(),
+            }),
             PrimVal::Ptr(p) => Ok(p),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
         }
@@ -238,7 +297,10 @@ impl<'tcx> PrimVal {
             PrimVal::Bytes(b) => Ok(b == 0),
             PrimVal::Ptr(_) => Ok(false),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::UnsupportedSymbolicOperation {
+                op: "null check".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
         }
     }
 
@@ -250,7 +312,10 @@ impl<'tcx> PrimVal {
             },
             PrimVal::Ptr(ptr) => ptr.signed_offset(i, layout).map(PrimVal::Ptr),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::UnsupportedSymbolicOperation {
+                op: "signed offset".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
         }
     }
 
@@ -262,7 +327,10 @@ impl<'tcx> PrimVal {
             },
             PrimVal::Ptr(ptr) => ptr.offset(i, layout).map(PrimVal::Ptr),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::UnsupportedSymbolicOperation {
+                op: "offset".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
         }
     }
 
@@ -274,7 +342,10 @@ impl<'tcx> PrimVal {
             },
             PrimVal::Ptr(ptr) => Ok(PrimVal::Ptr(ptr.wrapping_signed_offset(i, layout))),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::UnsupportedSymbolicOperation {
+                op: "wrapping offset".to_owned(),
+                ty: "pointer".to_owned(),
+            }),
         }
     }
 }
@@ -0,0 +1,39 @@
+#![cfg(feature = "serialize")]
+#![feature(rustc_private)]
+
+extern crate rustc;
+extern crate rustc_const_math;
+extern crate seer;
+extern crate serde_json;
+extern crate syntax;
+
+use rustc::mir::BinOp;
+use rustc_const_math::{ConstMathErr, Op};
+use seer::{AllocId, MemoryPointer, StaticEvalError};
+use syntax::codemap::DUMMY_SP;
+
+fn round_trip(err: StaticEvalError) {
+    let json = serde_json::to_string(&err).unwrap();
+    let back: StaticEvalError = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", back), format!("{:?}", err));
+}
+
+#[test]
+fn static_eval_error_round_trip() {
+    round_trip(StaticEvalError::PointerOutOfBounds {
+        ptr: MemoryPointer::new(AllocId(3), 17),
+        access: true,
+        allocation_size: 16,
+        offset_witness: Some(17),
+    });
+    round_trip(StaticEvalError::ArrayIndexOutOfBounds(DUMMY_SP, 4, 9));
+    round_trip(StaticEvalError::Math(DUMMY_SP, ConstMathErr::Overflow(Op::Add)));
+    round_trip(StaticEvalError::DivisionByZero(None));
+    round_trip(StaticEvalError::InvalidBoolOp(BinOp::Shl));
+    round_trip(StaticEvalError::OutOfMemory {
+        allocation_size: 1024,
+        memory_size: 512,
+        memory_usage: 0,
+    });
+    round_trip(StaticEvalError::Panic);
+}
@@ -1,36 +1,82 @@
+#![feature(rustc_private)]
+
+extern crate rustc;
 extern crate seer;
+extern crate syntax;
+extern crate z3_sys;
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 fn expect_single_panic(filename: &str, expected_result: Vec<u8>) {
     expect_panics(filename, vec![expected_result]);
 }
 
-fn expect_panics(filename: &str, mut expected_results: Vec<Vec<u8>>) {
+fn expect_panics(filename: &str, expected_results: Vec<Vec<u8>>) {
+    expect_errors(filename, |e| match *e { ::seer::StaticEvalError::Panic => true, _ => false },
+                  expected_results);
+}
+
+fn expect_errors<P>(filename: &str, is_expected: P, mut expected_results: Vec<Vec<u8>>)
+    where P: Fn(&::seer::StaticEvalError) -> bool
+{
+    let mut found: Vec<_> = collect_paths(filename, |_| {}).into_iter()
+        .filter(|complete| match complete.result {
+            Err(ref e) => is_expected(e),
+            Ok(()) => false,
+        })
+        .map(|complete| complete.input)
+        .collect();
+    found.sort();
+    expected_results.sort();
+
+    assert_eq!(found, expected_results);
+}
+
+/// Runs the program in `filename` with the settings that `configure` adds, and returns
+/// every path that completes, in the order that they do.
+fn collect_paths<F>(filename: &str, configure: F) -> Vec<::seer::ExecutionComplete>
+    where F: FnOnce(&mut ::seer::ExecutionConfig)
+{
+    collect_paths_with_args(vec![filename.to_string()], configure)
+}
+
+/// Like `collect_paths`, for the compiler arguments `args`, e.g. a file and some flags.
+fn collect_paths_with_args<F>(args: Vec<String>, configure: F) -> Vec<::seer::ExecutionComplete>
+    where F: FnOnce(&mut ::seer::ExecutionConfig)
+{
     let found = Rc::new(RefCell::new(Vec::new()));
     let found1 = found.clone();
-    let consumer = move |complete| {
-        match complete {
-            ::seer::ExecutionComplete { result: Err(::seer::StaticEvalError::Panic),
-                                        input } => {
-                found1.borrow_mut().push(input);
-                true
-            }
-            _ => true,
-        }
+    let consumer = move |complete: ::seer::ExecutionComplete| {
+        found1.borrow_mut().push(complete);
+        true
     };
 
-    let args = vec!["seer".to_string(), filename.to_string()];
-    ::seer::ExecutionConfig::new()
-        .consumer(consumer)
-        .run(args);
+    let mut config = ::seer::ExecutionConfig::new();
+    configure(&mut config);
+    let mut full_args = vec!["seer".to_string()];
+    full_args.extend(args);
+    config.consumer(consumer).run(full_args);
 
-    let mut found = ::std::mem::replace(&mut *found.borrow_mut(), Vec::new());
-    found.sort();
-    expected_results.sort();
+    let found = ::std::mem::replace(&mut *found.borrow_mut(), Vec::new());
+    found
+}
 
-    assert_eq!(found, expected_results);
+/// The inputs of the paths in `paths` that panicked.
+fn panic_inputs(paths: &[::seer::ExecutionComplete]) -> Vec<Vec<u8>> {
+    paths.iter().filter(|complete| match complete.result {
+        Err(::seer::StaticEvalError::Panic) => true,
+        _ => false,
+    }).map(|complete| complete.input.clone()).collect()
+}
+
+/// Calls `f` with an empty memory for the default target, and the layout of that target.
+fn with_memory<F>(f: F)
+    where F: for<'a> FnOnce(&mut ::seer::Memory<'a, 'static>, &'a ::rustc::ty::layout::TargetDataLayout)
+{
+    let layout = ::rustc::ty::layout::TargetDataLayout::default();
+    let mut memory = ::seer::Memory::new(&layout, 1024);
+    f(&mut memory, &layout);
 }
 
 
@@ -104,6 +150,13 @@ fn symbolic_indexing() {
         vec![73, 158]);
 }
 
+#[test]
+fn symbolic_struct_array_index() {
+    expect_single_panic(
+        "tests/symbolic/struct_array_index.rs",
+        vec![2]);
+}
+
 #[test]
 fn symbolic_infeasible() {
     expect_panics("tests/symbolic/infeasible.rs", vec![]);
@@ -137,6 +190,324 @@ fn symbolic_div() {
         vec![57, 199]);
 }
 
+#[test]
+fn symbolic_enum_discriminant() {
+    expect_single_panic(
+        "tests/symbolic/enum_discriminant.rs",
+        vec![2]);
+}
+
+#[test]
+fn symbolic_niche_discriminant() {
+    expect_panics(
+        "tests/symbolic/niche_discriminant.rs",
+        vec![vec![7], vec![9]]);
+}
+
+#[test]
+fn symbolic_discriminant_value() {
+    expect_panics(
+        "tests/symbolic/discriminant_value.rs",
+        vec![vec![0, 7], vec![1, 42]]);
+}
+
+#[test]
+fn symbolic_is_null() {
+    expect_single_panic(
+        "tests/symbolic/is_null.rs",
+        vec![0]);
+}
+
+#[test]
+fn symbolic_checked_arith() {
+    expect_panics(
+        "tests/symbolic/checked_arith.rs",
+        vec![vec![255], vec![128], vec![43]]);
+}
+
+#[test]
+fn symbolic_overflowing_arith() {
+    expect_panics(
+        "tests/symbolic/overflowing_arith.rs",
+        vec![vec![255], vec![128]]);
+}
+
+#[test]
+fn symbolic_min_max() {
+    expect_single_panic("tests/symbolic/min_max.rs", vec![201]);
+}
+
+#[test]
+fn symbolic_copy_len() {
+    expect_single_panic(
+        "tests/symbolic/copy_symbolic_len.rs",
+        vec![4, 1, 2, 3, 4]);
+}
+
+#[test]
+fn copy_symbolic_size_undef_tail() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind, StaticEvalError};
+
+    with_memory(|memory, layout| {
+        let src = memory.allocate(4, 1).unwrap();
+        memory.write_fresh_abstract_bytes(src, 4).unwrap();
+        let dest = memory.allocate(4, 1).unwrap();
+
+        // Copy between 2 and 4 bytes into uninitialized memory.
+        let len_ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(len_ptr, 1).unwrap();
+        let len = memory.read_abstract(PrimVal::Ptr(len_ptr), 1).unwrap();
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Gt, PrimValKind::U8, len, PrimVal::Bytes(1)));
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Lt, PrimValKind::U8, len, PrimVal::Bytes(5)));
+        memory.copy_abstract_size(src, dest, len, 2, 4, 1).unwrap();
+
+        // Only the bytes that are always copied become defined.
+        assert!(memory.read_abstract(PrimVal::Ptr(dest), 2).is_ok());
+        match memory.read_abstract(PrimVal::Ptr(dest.offset(2, layout).unwrap()), 1) {
+            Err(err) => match StaticEvalError::from(err) {
+                StaticEvalError::ReadUndefBytes => {}
+                other => panic!("unexpected error: {:?}", other),
+            },
+            Ok(value) => panic!("read a byte that may not have been copied: {:?}", value),
+        }
+    });
+}
+
+#[test]
+fn symbolic_write_bytes() {
+    expect_single_panic(
+        "tests/symbolic/write_bytes_symbolic.rs",
+        vec![3, 7]);
+}
+
+#[test]
+fn write_symbolic_count_undef_tail() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind, SByte, StaticEvalError};
+
+    with_memory(|memory, layout| {
+        let buffer = memory.allocate(4, 1).unwrap();
+
+        // Write between 2 and 4 bytes into uninitialized memory.
+        let count_ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(count_ptr, 1).unwrap();
+        let count = memory.read_abstract(PrimVal::Ptr(count_ptr), 1).unwrap();
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Gt, PrimValKind::U8, count, PrimVal::Bytes(1)));
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Lt, PrimValKind::U8, count, PrimVal::Bytes(5)));
+        memory.write_repeat_abstract_count(buffer, SByte::Concrete(7), count, 2, 4).unwrap();
+
+        // Only the bytes that are always written become defined.
+        assert_eq!(memory.read_bytes(PrimVal::Ptr(buffer), 2).unwrap(), &[SByte::Concrete(7); 2][..]);
+        match memory.read_abstract(PrimVal::Ptr(buffer.offset(2, layout).unwrap()), 1) {
+            Err(err) => match StaticEvalError::from(err) {
+                StaticEvalError::ReadUndefBytes => {}
+                other => panic!("unexpected error: {:?}", other),
+            },
+            Ok(value) => panic!("read a byte that may not have been written: {:?}", value),
+        }
+    });
+}
+
+#[test]
+fn symbolic_div_by_zero() {
+    expect_errors(
+        "tests/symbolic/div_by_zero.rs",
+        |e| match *e { ::seer::StaticEvalError::DivisionByZero(_) => true, _ => false },
+        vec![vec![0]]);
+}
+
+#[test]
+fn symbolic_null_deref() {
+    expect_errors(
+        "tests/symbolic/null_deref.rs",
+        |e| match *e { ::seer::StaticEvalError::InvalidNullPointerUsage => true, _ => false },
+        vec![vec![0]]);
+}
+
+#[test]
+fn symbolic_int_to_ptr_deref() {
+    expect_errors(
+        "tests/symbolic/int_to_ptr.rs",
+        |e| match *e { ::seer::StaticEvalError::InvalidNullPointerUsage => true, _ => false },
+        vec![vec![0]]);
+}
+
+fn run_detecting_recursion(filename: &str, stack_limit: usize) -> Vec<::seer::ExecutionComplete> {
+    collect_paths(filename, |config| {
+        config.detect_nonprogressing_recursion(true).stack_limit(stack_limit);
+    })
+}
+
+
+#[test]
+fn non_progressing_recursion() {
+    let paths = run_detecting_recursion("tests/symbolic/endless_recursion.rs", 100);
+    let recursions: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::NonProgressingRecursion(_)) => Some(complete.input.clone()),
+        _ => None,
+    }).collect();
+    assert_eq!(recursions, vec![vec![7]]);
+}
+
+
+#[test]
+fn recursion_reading_stdin_progresses() {
+    // Every call reads another byte, so none of them repeats its caller.
+    let paths = run_detecting_recursion("tests/symbolic/stdin_recursion.rs", 8);
+    let mut completed = 0;
+    for complete in &paths {
+        match complete.result {
+            Ok(()) => {
+                assert_eq!(complete.input.last(), Some(&0));
+                completed += 1;
+            }
+            Err(::seer::StaticEvalError::StackFrameLimitReached) => {}
+            Err(ref e) => panic!("unexpected error: {:?}", e),
+        }
+    }
+    assert!(completed >= 2);
+}
+
+
+#[test]
+fn div_by_zero_location() {
+    let paths = collect_paths("tests/symbolic/div_by_zero.rs", |_| {});
+    let locations: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::DivisionByZero(_)) => Some(complete.location().cloned()),
+        _ => None,
+    }).collect();
+    assert_eq!(locations.len(), 1);
+    let (ref file, line, col) = *locations[0].as_ref().unwrap();
+    assert!(file.ends_with("div_by_zero.rs"));
+    assert_eq!((line, col), (7, 13));
+}
+
+
+#[test]
+fn symbolic_exact_div() {
+    let paths = collect_paths("tests/symbolic/exact_div.rs", |_| {});
+
+    let mut inexact = 0;
+    for complete in &paths {
+        match complete.result {
+            Ok(()) => assert_eq!(complete.input[0] % 3, 0),
+            Err(::seer::StaticEvalError::Panic) => {}
+            Err(::seer::StaticEvalError::InexactDivision { dividend, divisor }) => {
+                assert_eq!(divisor, 3);
+                assert!(dividend % 3 != 0);
+                assert_eq!(complete.input[0] as u128, dividend);
+                inexact += 1;
+            }
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+    assert_eq!(inexact, 1);
+    assert_eq!(panic_inputs(&paths), vec![vec![15]]);
+}
+
+
+#[test]
+fn symbolic_concretized_switch() {
+    expect_single_panic(
+        "tests/symbolic/concretized_switch.rs",
+        vec![5]);
+}
+
+#[test]
+fn concretize_query_count() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind};
+
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(ptr, 1).unwrap();
+        let x = memory.read_abstract(PrimVal::Ptr(ptr), 1).unwrap();
+
+        // One query for a value and one for another, after which the answer is cached.
+        let queries = memory.constraints.solver_queries();
+        let (witness, unique) = memory.constraints.witness_and_uniqueness(x, PrimValKind::U8).unwrap();
+        assert!(!unique);
+        assert_eq!(memory.constraints.solver_queries(), queries + 2);
+        assert_eq!(memory.constraints.witness_and_uniqueness(x, PrimValKind::U8), Some((witness, false)));
+        assert_eq!(memory.constraints.try_concretize(x, PrimValKind::U8), None);
+        assert_eq!(memory.constraints.solver_queries(), queries + 2);
+
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Eq, PrimValKind::U8, x, PrimVal::Bytes(5)));
+        assert_eq!(memory.constraints.try_concretize(x, PrimValKind::U8), Some(5));
+        assert_eq!(memory.constraints.solver_queries(), queries + 4);
+        assert_eq!(memory.constraints.try_concretize(x, PrimValKind::U8), Some(5));
+        assert_eq!(memory.constraints.solver_queries(), queries + 4);
+    });
+}
+
+#[test]
+fn symbolic_alloc_size() {
+    expect_single_panic(
+        "tests/symbolic/alloc_symbolic_size.rs",
+        vec![7]);
+}
+
+#[test]
+fn symbolic_alloc_size_bounds() {
+    let paths = collect_paths("tests/symbolic/alloc_symbolic_bounds.rs", |_| {});
+
+    // Only the lengths that are too short for the write fail: those below 3 when the
+    // pointer is moved, and 3 itself when the byte at offset 3 is written. The path
+    // with the longer lengths goes on to the panic.
+    let mut out_of_bounds = Vec::new();
+    for complete in &paths {
+        if let Err(::seer::StaticEvalError::PointerOutOfBounds { .. }) = complete.result {
+            out_of_bounds.push(complete.input[0]);
+        }
+    }
+    out_of_bounds.sort();
+    assert_eq!(out_of_bounds.len(), 2);
+    assert!(out_of_bounds[0] >= 1 && out_of_bounds[0] < 3);
+    assert_eq!(out_of_bounds[1], 3);
+    assert_eq!(panic_inputs(&paths), vec![vec![200]]);
+}
+
+
+#[test]
+fn alloc_zeroed_reads_zero() {
+    let paths = collect_paths("tests/symbolic/alloc_zeroed.rs", |_| {});
+    assert!(paths.len() >= 2);
+    for complete in &paths {
+        if let Err(ref e) = complete.result {
+            panic!("unexpected error: {:?}", e);
+        }
+    }
+}
+
+
+#[test]
+fn symbolic_vec_growth() {
+    let paths = collect_paths("tests/symbolic/vec_growth.rs", |_| {});
+    for complete in &paths {
+        match complete.result {
+            Ok(()) | Err(::seer::StaticEvalError::Panic) => {}
+            Err(ref e) => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    // The elements survive the growth from each of the three initial capacities.
+    let panics = panic_inputs(&paths);
+    let mut capacities: Vec<u8> = panics.iter().map(|input| input[0] % 3 + 1).collect();
+    capacities.sort();
+    capacities.dedup();
+    assert_eq!(capacities, vec![1, 2, 3]);
+    for input in panics.iter() {
+        assert_eq!((input[1], input[3]), (7, 42));
+    }
+}
+
+
 #[test]
 fn symbolic_ptr_offset() {
     expect_single_panic(
@@ -144,6 +515,42 @@ fn symbolic_ptr_offset() {
         vec![2, 4]);
 }
 
+#[test]
+fn symbolic_misaligned_read() {
+    let paths = collect_paths("tests/symbolic/misaligned_read.rs", |_| {});
+
+    let mut misaligned = 0;
+    for complete in &paths {
+        match complete.result {
+            Ok(()) | Err(::seer::StaticEvalError::Panic) => {}
+            Err(::seer::StaticEvalError::AlignmentCheckFailed { required, has }) => {
+                assert_eq!(required, 4);
+                assert!(has > 0 && has < 4, "unexpected misalignment {}", has);
+                assert_eq!(complete.input[0] as u64 % 4, has);
+                misaligned += 1;
+            }
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+    assert_eq!(misaligned, 1);
+    assert_eq!(panic_inputs(&paths), vec![vec![4]]);
+}
+
+
+#[test]
+fn symbolic_uninit_fields() {
+    expect_single_panic(
+        "tests/symbolic/uninit_fields.rs",
+        vec![1, 10]);
+}
+
+#[test]
+fn symbolic_uninit_pair() {
+    expect_single_panic(
+        "tests/symbolic/uninit_pair.rs",
+        vec![1, 10]);
+}
+
 #[test]
 fn symbolic_slice() {
     expect_single_panic(
@@ -174,3 +581,1637 @@ fn symbolic_trailing_zeros() {
         vec![128]);
 
 }
+
+#[test]
+fn symbolic_count_ones() {
+    let panics = panic_inputs(&collect_paths("tests/symbolic/count_ones.rs", |_| {}));
+    assert_eq!(panics.len(), 1);
+    let x = panics[0][0] as u16 | (panics[0][1] as u16) << 8;
+    assert!(x.is_power_of_two());
+}
+
+
+#[test]
+fn symbolic_rotate() {
+    expect_single_panic(
+        "tests/symbolic/rotate.rs",
+        vec![0x12]);
+}
+
+#[test]
+fn symbolic_index_out_of_bounds() {
+    let paths = collect_paths("tests/symbolic/index_out_of_bounds.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::ArrayIndexOutOfBounds(_, len, index)) =>
+            Some((len, index, complete.input[0] as u64)),
+        _ => None,
+    }).collect();
+    assert_eq!(found.len(), 1);
+    let (len, index, input) = found[0];
+    assert_eq!(len, 4);
+    assert!(index >= 4);
+    assert_eq!(index, input);
+    for input in panic_inputs(&paths) {
+        assert_eq!(input, vec![2]);
+    }
+}
+
+
+#[test]
+fn symbolic_overflow_assert() {
+    let paths = collect_paths("tests/symbolic/overflow_assert.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::Math(..)) => Some(complete.input[0]),
+        _ => None,
+    }).collect();
+    assert_eq!(found.len(), 1);
+    assert!(found[0] >= 56);
+    for input in panic_inputs(&paths) {
+        assert_eq!(input, vec![10]);
+    }
+}
+
+
+#[test]
+fn release_add_wraps() {
+    let paths = collect_paths("tests/symbolic/release_add.rs", |config| {
+        config.debug_assertions(false);
+    });
+    for complete in &paths {
+        match complete.result {
+            Ok(()) | Err(::seer::StaticEvalError::Panic) => {}
+            Err(ref e) => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    // 255 + 1 wraps to zero instead of failing the overflow check.
+    assert_eq!(panic_inputs(&paths), vec![vec![255]]);
+}
+
+
+#[test]
+fn concrete_fast_path_same_results() {
+    let run = |fast_path: bool| -> Vec<_> {
+        collect_paths("tests/symbolic/concrete_loop.rs", |config| {
+            config.concrete_fast_path(fast_path);
+        }).into_iter().map(|complete| {
+            let steps = complete.stats().steps;
+            (complete.result, complete.input, steps)
+        }).collect()
+    };
+
+    let fast = run(true);
+    assert_eq!(fast.len(), 2);
+    assert_eq!(format!("{:?}", fast), format!("{:?}", run(false)));
+}
+
+
+#[test]
+fn symbolic_signed_rem() {
+    let paths = collect_paths("tests/symbolic/signed_rem.rs", |_| {});
+    let overflows: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::Math(..)) => Some(complete.input[..2].to_vec()),
+        _ => None,
+    }).collect();
+    let panics: Vec<_> = panic_inputs(&paths).iter().map(|input| input[2..].to_vec()).collect();
+
+    assert_eq!(overflows, vec![vec![0x80, 0xff]]);
+    assert_eq!(panics, vec![vec![(-7i8) as u8, 3]]);
+}
+
+
+#[test]
+fn symbolic_shift_overflow() {
+    let paths = collect_paths("tests/symbolic/shift_overflow.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::Math(..)) => Some(complete.input[0]),
+        _ => None,
+    }).collect();
+    assert_eq!(found.len(), 1);
+    assert!(found[0] >= 32);
+    for input in panic_inputs(&paths) {
+        assert_eq!(input, vec![31]);
+    }
+}
+
+
+#[test]
+fn use_after_free_site() {
+    let paths = collect_paths("tests/symbolic/use_after_free.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::UseAfterFree { .. }) => Some(complete.freed_at().cloned()),
+        _ => None,
+    }).collect();
+
+    // The allocation is freed by `drop(b)`.
+    assert_eq!(found.len(), 1);
+    let (ref file, line, col) = *found[0].as_ref().unwrap();
+    assert!(file.ends_with("use_after_free.rs"));
+    assert_eq!((line, col), (4, 5));
+}
+
+
+#[test]
+fn symbolic_fn_ptr_table() {
+    expect_single_panic(
+        "tests/symbolic/fn_ptr_table.rs",
+        vec![2]);
+}
+
+#[test]
+fn fn_ptr_target_limit() {
+    let paths = collect_paths("tests/symbolic/fn_ptr_table.rs", |config| {
+        config.fn_ptr_target_limit(2);
+    });
+
+    assert!(paths.iter().any(|complete| match complete.result {
+        Err(::seer::StaticEvalError::TooManyFunctionPointerTargets { targets: 3, limit: 2 }) => true,
+        _ => false,
+    }));
+    assert!(panic_inputs(&paths).is_empty());
+}
+
+
+#[test]
+fn deterministic_alloc_ids() {
+    let run = || -> Vec<_> {
+        collect_paths("tests/symbolic/alloc_ids.rs", |_| {}).iter()
+            .map(|complete| format!("{:?}", complete))
+            .collect()
+    };
+
+    let first = run();
+    assert!(first.iter().any(|c| c.contains("PointerOutOfBounds")));
+    assert_eq!(first, run());
+}
+
+
+#[test]
+fn symbolic_transmute() {
+    expect_single_panic(
+        "tests/symbolic/transmute.rs",
+        vec![0x11, 0x22, 0x33, 0x44]);
+}
+
+#[test]
+fn symbolic_partially_defined() {
+    let paths = collect_paths("tests/symbolic/partially_defined.rs", |_| {});
+    let undef: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::ReadUndefBytes) => Some(complete.input.clone()),
+        _ => None,
+    }).collect();
+
+    assert_eq!(panic_inputs(&paths), vec![vec![1]]);
+    assert_eq!(undef.len(), 1);
+    assert!(undef[0][0] == 2 || undef[0][0] == 3);
+}
+
+
+#[test]
+fn vtable_cache() {
+    expect_single_panic(
+        "tests/symbolic/vtable_cache.rs",
+        vec![]);
+}
+
+#[test]
+fn symbolic_bitwise() {
+    expect_single_panic(
+        "tests/symbolic/bitwise.rs",
+        vec![0x17, 5, 3]);
+}
+
+#[test]
+fn symbolic_saturating() {
+    expect_single_panic(
+        "tests/symbolic/saturating.rs",
+        vec![255, 128]);
+}
+
+#[test]
+fn symbolic_ptr_compare() {
+    expect_single_panic(
+        "tests/symbolic/ptr_compare.rs",
+        vec![2, 3]);
+}
+
+#[test]
+fn typed_input_model() {
+    let paths = collect_paths("tests/symbolic/typed_input.rs", |config| {
+        config.entry_function("check");
+    });
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::Panic) => Some(complete.input_model().to_vec()),
+        _ => None,
+    }).collect();
+
+    let expected = ::seer::ConcreteValue::Tuple(vec![
+        ::seer::ConcreteValue::Uint(0xdeadbeef ^ 0x1234),
+        ::seer::ConcreteValue::Bool(true),
+    ]);
+    assert_eq!(expected.to_string(), "(3735923931, true)");
+    assert_eq!(found, vec![vec![("input".to_string(), expected)]]);
+}
+
+
+#[test]
+fn analyze_divide_by_zero() {
+    use seer::{ConcreteValue, ExecutionResult, StaticEvalError};
+
+    let args = vec!["seer".to_string(), "tests/symbolic/divide_by_zero.rs".to_string()];
+    let failures = ::seer::ExecutionConfig::new()
+        .entry_function("average")
+        .analyze(args);
+
+    assert_eq!(failures.len(), 1);
+    match failures[0] {
+        ExecutionResult::Errored { err: StaticEvalError::DivisionByZero(_), ref model } => {
+            assert_eq!(model.inputs[1], ("count".to_string(), ConcreteValue::Uint(0)));
+        }
+        ref other => panic!("unexpected failure: {:?}", other),
+    }
+}
+
+#[test]
+fn analyze_unsupported_input() {
+    use seer::{ExecutionResult, StaticEvalError};
+
+    // A `u128` argument cannot be symbolic, which is reported instead of explored.
+    let args = vec!["seer".to_string(), "tests/symbolic/unsupported_input.rs".to_string()];
+    let failures = ::seer::ExecutionConfig::new()
+        .entry_function("wide")
+        .analyze(args);
+
+    assert_eq!(failures.len(), 1);
+    match failures[0] {
+        ExecutionResult::Errored { err: StaticEvalError::Unimplemented(ref msg), .. } =>
+            assert!(msg.contains("u128")),
+        ref other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn opaque_fn_skipped() {
+    let paths = collect_paths("tests/symbolic/opaque_fn.rs", |config| {
+        config.opaque_fns(vec!["checksum".to_string()]);
+    });
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::Panic) => Some(complete.input_model().to_vec()),
+        _ => None,
+    }).collect();
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].len(), 1);
+    assert_eq!(found[0][0].1, ::seer::ConcreteValue::Uint(7));
+}
+
+
+#[test]
+fn input_model_rust_source() {
+    let model = ::seer::InputModel {
+        stdin: vec![1, 255],
+        inputs: vec![(
+            "input".to_string(),
+            ::seer::ConcreteValue::Tuple(vec![
+                ::seer::ConcreteValue::Uint(200),
+                ::seer::ConcreteValue::Bool(true),
+            ]),
+        ), (
+            "c".to_string(),
+            ::seer::ConcreteValue::Char('x'),
+        )],
+        types: vec!["(u8, bool)".to_string(), "char".to_string()],
+    };
+    assert_eq!(
+        model.to_rust_source(),
+        "let stdin: [u8; 2] = [1, 255];\nlet input: (u8, bool) = (200, true);\nlet c: char = 'x';\n");
+}
+
+#[test]
+fn mk_symbolic_bool() {
+    let paths = collect_paths("tests/symbolic/mk_symbolic.rs", |_| {});
+    let mut found: Vec<_> = paths.iter().map(|complete| {
+        assert!(complete.result.is_ok());
+        complete.input_model().to_vec()
+    }).collect();
+
+    found.sort_by_key(|model| model[0].1.to_string());
+    assert_eq!(found, vec![
+        vec![("symbolic0".to_string(), ::seer::ConcreteValue::Bool(false))],
+        vec![("symbolic0".to_string(), ::seer::ConcreteValue::Bool(true))],
+    ]);
+}
+
+
+#[test]
+fn execution_time_limit() {
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let found1 = found.clone();
+    let explorer = move |paths: &mut Iterator<Item = ::seer::ExecutionComplete>| {
+        // The exploration starts right before the explorer is called.
+        let start = ::std::time::Instant::now();
+        for complete in paths {
+            found1.borrow_mut().push((complete.result, start.elapsed()));
+        }
+    };
+
+    let args = vec!["seer".to_string(), "tests/symbolic/busy_loop.rs".to_string()];
+    ::seer::ExecutionConfig::new()
+        .time_limit_ms(200)
+        .time_check_interval(100)
+        .explore(args, explorer);
+
+    let found = found.borrow();
+    assert_eq!(found.len(), 1);
+    match found[0].0 {
+        Err(::seer::StaticEvalError::ExecutionTimeLimitReached) => {}
+        ref other => panic!("unexpected result {:?}", other),
+    }
+    assert!(found[0].1 < ::std::time::Duration::from_millis(2000), "took {:?}", found[0].1);
+}
+
+#[test]
+fn symbolic_return_struct() {
+    expect_single_panic("tests/symbolic/return_struct.rs", vec![42]);
+}
+
+#[test]
+fn symbolic_return_place_read() {
+    // Copy propagation turns `pair` in `make_pair` into the return place itself, so the
+    // callee writes `_0.first` and then reads it back to compute `_0.second`.
+    let args = vec!["tests/symbolic/return_struct.rs".to_string(), "-Zmir-opt-level=3".to_string()];
+    let paths = collect_paths_with_args(args, |_| {});
+
+    for complete in &paths {
+        match complete.result {
+            Ok(()) | Err(::seer::StaticEvalError::Panic) => {}
+            Err(ref e) => panic!("unexpected error: {:?}", e),
+        }
+    }
+    assert_eq!(panic_inputs(&paths), vec![vec![42]]);
+}
+
+
+#[test]
+fn symbolic_float() {
+    expect_single_panic("tests/symbolic/float.rs", vec![0, 0, 0, 0, 0, 0, 0, 0x40]);
+}
+
+#[test]
+fn frame_step_limit() {
+    let paths = collect_paths("tests/symbolic/long_frame.rs", |config| {
+        config.max_steps_per_frame(1000);
+    });
+
+    let limited = paths.iter().filter(|complete| match complete.result {
+        Err(::seer::StaticEvalError::FrameStepLimitReached) => true,
+        _ => false,
+    }).count();
+    let finished = paths.iter().filter(|complete| complete.result.is_ok()).count();
+    assert_eq!(limited, 1);
+    assert_eq!(finished, 1);
+}
+
+
+#[test]
+fn loop_bound_prunes() {
+    let paths = collect_paths("tests/symbolic/loop_bound.rs", |config| {
+        config.loop_bound(4);
+    });
+
+    // Inputs 0 to 4 leave the loop, everything above takes a fifth back edge.
+    let pruned = paths.iter().filter(|complete| match complete.result {
+        Err(::seer::StaticEvalError::LoopBoundReached) => true,
+        _ => false,
+    }).count();
+    let finished = paths.iter().filter(|complete| complete.result.is_ok()).count();
+    assert_eq!(pruned, 1);
+    assert_eq!(finished, 5);
+}
+
+
+#[test]
+fn build_applies_settings() {
+    let queries = Rc::new(Cell::new(0));
+    let mut config = ::seer::ExecutionConfig::new();
+    config.memory_size(1024 * 1024)
+        .time_limit_ms(60_000)
+        .stack_limit(20)
+        .loop_bound(7)
+        .solver_backend(CountingBackend { queries: queries.clone() });
+
+    let checked = Rc::new(Cell::new(false));
+    let checked1 = checked.clone();
+    let config1 = config.clone();
+    let args = vec!["seer".to_string(), "tests/symbolic/simple.rs".to_string()];
+    config.compile(args, move |tcx, def_id| {
+        let ecx = config1.build(tcx, def_id).unwrap();
+        assert_eq!(ecx.memory().memory_size(), 1024 * 1024);
+        assert_eq!(ecx.time_limit(), Some(::std::time::Duration::from_millis(60_000)));
+        assert_eq!(ecx.stack_limit(), 20);
+        assert_eq!(ecx.loop_bound(), Some(7));
+        assert_eq!(ecx.stack().len(), 1);
+
+        // The query goes to the configured backend.
+        let before = queries.get();
+        assert!(ecx.constraints().is_feasible_with(&[]));
+        assert_eq!(queries.get(), before + 1);
+        checked1.set(true);
+    });
+    assert!(checked.get());
+}
+
+fn path_order<S>(strategy: S) -> Vec<Vec<u8>>
+    where S: ::seer::PathStrategy + 'static
+{
+    let paths = collect_paths("tests/symbolic/path_order.rs", |config| {
+        config.path_strategy(strategy);
+    });
+    paths.into_iter().map(|complete| {
+        assert!(complete.result.is_ok());
+        complete.input
+    }).collect()
+}
+
+
+#[test]
+fn path_strategies() {
+    // Whether a path completes after two forks rather than three.
+    let shallow = |input: &Vec<u8>| input[1] == 1;
+
+    let bfs = path_order(::seer::BreadthFirst);
+    assert_eq!(bfs.len(), 6);
+    let first_deep = bfs.iter().position(|input| !shallow(input)).unwrap();
+    assert!(bfs[first_deep..].iter().all(|input| !shallow(input)));
+
+    // Depth-first finishes all paths on one side of the first fork before entering the
+    // other, so a shallow path of the second side completes after deep paths of the first.
+    let dfs = path_order(::seer::DepthFirst);
+    assert_eq!(dfs.len(), 6);
+    let first_deep = dfs.iter().position(|input| !shallow(input)).unwrap();
+    assert!(dfs[first_deep..].iter().any(|input| shallow(input)));
+
+    let random = path_order(::seer::RandomPath::new(17));
+    assert_eq!(random.len(), 6);
+}
+
+#[test]
+fn symbolic_ptr_walk() {
+    let paths = collect_paths("tests/symbolic/ptr_walk.rs", |_| {});
+    assert_eq!(panic_inputs(&paths), vec![vec![2]]);
+
+    // Moving the pointer more than one element past the end is reported with an
+    // offset that the solver found for it.
+    let out_of_bounds = paths.iter().any(|complete| match complete.result {
+        Err(::seer::StaticEvalError::PointerOutOfBounds {
+            access: false, allocation_size, offset_witness: Some(offset), ..
+        }) => offset > allocation_size,
+        _ => false,
+    });
+    assert!(out_of_bounds);
+}
+
+
+#[test]
+fn state_merging_diamond() {
+    let run = |merge: bool| collect_paths("tests/symbolic/merge_diamond.rs", |config| {
+        config.enable_state_merging(merge);
+    });
+
+    assert_eq!(run(false).len(), 4);
+
+    // Both if-else diamonds merge, so only the final branch forks.
+    let merged = run(true);
+    assert_eq!(merged.len(), 2);
+    let panics = panic_inputs(&merged);
+    assert_eq!(panics.len(), 1);
+    let input = &panics[0];
+    assert!(input[0] == 7 && input[1] > 100, "input {:?} does not panic", input);
+}
+
+
+#[test]
+fn stdin_constraints() {
+    use seer::{Constraint, PrimVal, PrimValKind};
+
+    let paths = collect_paths("tests/symbolic/stdin_range.rs", |config| {
+        config.stdin_constraints(|bytes| {
+            let mut constraints = Vec::new();
+            for &byte in bytes {
+                constraints.push(Constraint::new_compare(
+                    ::rustc::mir::BinOp::Ge, PrimValKind::U8, byte, PrimVal::Bytes(b'a' as u128)));
+                constraints.push(Constraint::new_compare(
+                    ::rustc::mir::BinOp::Le, PrimValKind::U8, byte, PrimVal::Bytes(b'z' as u128)));
+            }
+            constraints
+        });
+    });
+
+    assert!(!paths.is_empty());
+    for complete in &paths {
+        let input = &complete.input;
+        assert!(input.iter().all(|&b| b'a' <= b && b <= b'z'), "input {:?} out of range", input);
+    }
+    assert_eq!(panic_inputs(&paths), vec![vec![b'q']]);
+}
+
+
+#[test]
+fn symbolic_slice_len() {
+    let paths = collect_paths("tests/symbolic/slice_len.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::ArrayIndexOutOfBounds(_, len, index)) => Some((len, index)),
+        _ => None,
+    }).collect();
+    assert_eq!(found.len(), 1);
+    let (len, index) = found[0];
+    assert_eq!(len, 3);
+    assert!(index >= 3);
+    for input in panic_inputs(&paths) {
+        assert_eq!(input, vec![3, 2]);
+    }
+}
+
+
+#[test]
+fn symbolic_split_at() {
+    let panics = panic_inputs(&collect_paths("tests/symbolic/split_at.rs", |_| {}));
+
+    // Both parts have symbolic lengths that add up to the length of `s`, so the panic
+    // in `main` needs `s.len() == 5`. The other panics split past the end of `s`.
+    let (split, out_of_range): (Vec<_>, Vec<_>) = panics.iter().partition(|input| **input == vec![5, 2]);
+    assert_eq!(split.len(), 1);
+    assert!(!out_of_range.is_empty());
+    for input in out_of_range {
+        assert!(input[0] <= 8 && input[1] > input[0], "not out of range: {:?}", input);
+    }
+}
+
+
+#[test]
+fn symbolic_size_of_val() {
+    expect_single_panic("tests/symbolic/size_of_val.rs", vec![3]);
+}
+
+#[test]
+fn concrete_size_of() {
+    expect_single_panic(
+        "tests/symbolic/size_of_const.rs",
+        vec![3]);
+}
+
+#[test]
+fn symbolic_ptr_write_aggregate() {
+    expect_single_panic("tests/symbolic/ptr_write_aggregate.rs", vec![5, 7]);
+}
+
+#[test]
+fn symbolic_swap() {
+    expect_single_panic("tests/symbolic/swap.rs", vec![9, 5]);
+}
+
+#[test]
+fn symbolic_derived_eq() {
+    let paths = collect_paths("tests/symbolic/derived_eq.rs", |_| {});
+
+    // The comparison itself does not fork, so the only forks are on its result and on `p.x`.
+    assert_eq!(paths.len(), 3);
+    assert_eq!(panic_inputs(&paths), vec![vec![3, 7, 3]]);
+}
+
+
+#[test]
+fn unsupported_symbolic_float_op() {
+    let paths = collect_paths("tests/symbolic/float_rem.rs", |_| {});
+
+    assert_eq!(paths.len(), 1);
+    match paths[0].result {
+        Err(::seer::StaticEvalError::UnsupportedSymbolicOperation { ref op, ref ty }) => {
+            assert_eq!(op, "Rem");
+            assert_eq!(ty, "F64");
+        }
+        ref other => panic!("expected an unsupported symbolic operation, got {:?}", other),
+    }
+}
+
+
+#[test]
+fn concretize_on_unsupported() {
+    let paths = collect_paths("tests/symbolic/concretized_rem.rs", |config| {
+        config.concretize_on_unsupported(true);
+    });
+
+    assert_eq!(paths.len(), 1);
+    match paths[0].result {
+        Ok(()) => {}
+        ref other => panic!("expected the path to complete, got {:?}", other),
+    }
+    let concretizations = paths[0].concretizations();
+    assert_eq!(concretizations.len(), 1);
+    assert_eq!(concretizations[0].op, "Rem");
+    assert_eq!(concretizations[0].ty, "F64");
+
+    // The input is the bits of the value that `x` was pinned to.
+    let bits = paths[0].input.iter().rev().fold(0u128, |bits, &byte| (bits << 8) | byte as u128);
+    assert_eq!(bits, concretizations[0].value);
+}
+
+
+#[test]
+fn symbolic_invalid_char() {
+    let paths = collect_paths("tests/symbolic/char_from_u32.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::InvalidChar(c)) => Some(c),
+        _ => None,
+    }).collect();
+    assert_eq!(found.len(), 1);
+    assert!(found[0] >= 0xD800 && found[0] <= 0xDFFF);
+    for input in panic_inputs(&paths) {
+        assert_eq!(input, vec![0xff, 0xd7, 0, 0]);
+    }
+}
+
+
+#[test]
+fn symbolic_transmute_array_to_char() {
+    let paths = collect_paths("tests/symbolic/transmute_char.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::InvalidChar(c)) => Some(c),
+        _ => None,
+    }).collect();
+    assert_eq!(found.len(), 1);
+    assert!(found[0] >= 0xD800 && found[0] <= 0xDFFF);
+    for input in panic_inputs(&paths) {
+        assert_eq!(input, vec![0xff, 0xd7, 0, 0]);
+    }
+}
+
+
+#[test]
+fn static_error_fn_ptr_mismatch() {
+    let paths = collect_paths("tests/symbolic/fn_ptr_mismatch.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::FunctionPointerTyMismatch { ref expected, ref got, .. }) =>
+            Some((expected.clone(), got.clone())),
+        _ => None,
+    }).collect();
+
+    assert_eq!(found.last(), Some(&("fn()".to_string(), "fn(i32)".to_string())));
+}
+
+
+#[test]
+fn static_error_fn_ptr_abi_mismatch() {
+    let paths = collect_paths("tests/symbolic/fn_ptr_abi_mismatch.rs", |_| {});
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::FunctionPointerTyMismatch {
+            ref expected_abi, ref got_abi, expected_args, got_args, ..
+        }) => Some((expected_abi.clone(), got_abi.clone(), expected_args, got_args)),
+        _ => None,
+    }).collect();
+
+    assert_eq!(found.last(), Some(&("C".to_string(), "Rust".to_string(), 1, 1)));
+}
+
+
+#[test]
+fn fn_ptr_abi_mismatch_message() {
+    let message = Rc::new(RefCell::new(String::new()));
+    let message1 = message.clone();
+    let args = vec!["seer".to_string(), "tests/symbolic/fn_ptr_abi_mismatch.rs".to_string()];
+    ::seer::ExecutionConfig::new().compile(args, move |tcx, _| {
+        use rustc::hir::Unsafety;
+        use syntax::abi::Abi;
+        let sig = tcx.mk_fn_sig(
+            vec![tcx.types.u8].into_iter(), tcx.mk_nil(), false, Unsafety::Normal, Abi::C);
+        let got = tcx.mk_fn_sig(
+            vec![tcx.types.u8, tcx.types.u8].into_iter(), tcx.mk_nil(), false, Unsafety::Normal, Abi::Rust);
+        *message1.borrow_mut() = ::seer::EvalError::FunctionPointerTyMismatch(sig, got).to_string();
+    });
+
+    let message = message.borrow();
+    assert!(message.contains("(C ABI, 1 arguments)"), "{}", message);
+    assert!(message.contains("(Rust ABI, 2 arguments)"), "{}", message);
+}
+
+#[test]
+fn static_error_out_of_memory() {
+    let paths = collect_paths("tests/symbolic/memory_size.rs", |config| {
+        config.memory_size(512);
+    });
+    let found: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::OutOfMemory { allocation_size, memory_size, .. }) =>
+            Some((allocation_size, memory_size)),
+        _ => None,
+    }).collect();
+
+    assert_eq!(found.last(), Some(&(1024, 512)));
+}
+
+
+#[test]
+#[should_panic(expected = "memory size must be nonzero")]
+fn zero_memory_size() {
+    ::seer::ExecutionConfig::new().memory_size(0);
+}
+
+#[test]
+fn panic_handler_message() {
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let found1 = found.clone();
+    let handler = move |info: &::seer::PanicInfo| {
+        let line = info.location.as_ref().map(|&(_, line, _)| line);
+        found1.borrow_mut().push((info.message.clone(), line));
+    };
+
+    let args = vec!["seer".to_string(), "tests/symbolic/panic_message.rs".to_string()];
+    ::seer::ExecutionConfig::new()
+        .panic_handler(handler)
+        .run(args);
+
+    assert_eq!(*found.borrow(), vec![(Some("too big".to_string()), Some(9))]);
+}
+
+#[test]
+fn classified_outcomes() {
+    let paths = collect_paths("tests/symbolic/panic_message.rs", |config| {
+        config.panic_handler(|_: &::seer::PanicInfo| {});
+    });
+
+    let mut panicked = 0;
+    let mut completed = 0;
+    for complete in &paths {
+        match complete.outcome() {
+            ::seer::ExecutionResult::Panicked { ref info, ref model } => {
+                let info = info.as_ref().expect("panic info was not recorded");
+                assert_eq!(info.message, Some("too big".to_string()));
+                assert!(model.stdin[0] > 200);
+                panicked += 1;
+            }
+            ::seer::ExecutionResult::Completed(ref model) => {
+                assert!(model.stdin[0] <= 200);
+                completed += 1;
+            }
+            ref other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+    assert_eq!((panicked, completed), (1, 1));
+}
+
+
+#[test]
+fn branch_trace_events() {
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let found1 = found.clone();
+    let trace = move |event: &::seer::BranchEvent| {
+        if event.function == "main" {
+            found1.borrow_mut().push(event.clone());
+        }
+    };
+
+    let args = vec!["seer".to_string(), "tests/symbolic/branch_trace.rs".to_string()];
+    ::seer::ExecutionConfig::new()
+        .branch_trace(trace)
+        .run(args);
+
+    let found = found.borrow();
+    assert_eq!(found.len(), 2);
+    assert_eq!((found[0].block, found[0].statement), (found[1].block, found[1].statement));
+    assert_ne!(found[0].target, found[1].target);
+    let compare = |event: &::seer::BranchEvent| {
+        assert_eq!(event.kind, ::seer::BranchKind::Conditional);
+        assert_eq!(event.constraints.len(), 1);
+        match event.constraints[0] {
+            ::seer::Constraint::Compare { op, lhs, rhs, .. } => (op, lhs, rhs),
+            ref other => panic!("unexpected constraint {:?}", other),
+        }
+    };
+    let (op0, lhs0, rhs0) = compare(&found[0]);
+    let (op1, lhs1, rhs1) = compare(&found[1]);
+    assert_eq!((lhs0, rhs0), (lhs1, rhs1));
+    assert_eq!(op0, ::rustc::mir::BinOp::Eq);
+    assert_eq!(op1, ::rustc::mir::BinOp::Ne);
+}
+
+#[test]
+fn symbolic_switch_int() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let events1 = events.clone();
+    let trace = move |event: &::seer::BranchEvent| {
+        if event.function == "main" {
+            events1.borrow_mut().push(event.clone());
+        }
+    };
+    let paths = collect_paths("tests/symbolic/switch_int.rs", |config| {
+        config.branch_trace(trace);
+    });
+
+    assert_eq!(paths.len(), 3);
+    let mut panics: Vec<_> = paths.iter()
+        .filter(|complete| complete.result.is_err())
+        .map(|complete| complete.input.clone())
+        .collect();
+    panics.sort();
+    assert_eq!(panics, vec![vec![1], vec![2]]);
+    for complete in paths.iter().filter(|complete| complete.result.is_ok()) {
+        assert!(complete.input[0] != 1 && complete.input[0] != 2);
+    }
+
+    // One arm per value, and the `otherwise` arm excludes both of them.
+    let events = events.borrow();
+    assert_eq!(events.len(), 3);
+    let compared = |constraint: &::seer::Constraint| match *constraint {
+        ::seer::Constraint::Compare { op, rhs: ::seer::PrimVal::Bytes(n), .. } => (op, n),
+        ref other => panic!("unexpected constraint {:?}", other),
+    };
+    let constraints: Vec<Vec<_>> = events.iter().map(|event| {
+        assert_eq!(event.kind, ::seer::BranchKind::SwitchInt);
+        event.constraints.iter().map(&compared).collect()
+    }).collect();
+    use rustc::mir::BinOp;
+    assert_eq!(constraints, vec![
+        vec![(BinOp::Eq, 1)],
+        vec![(BinOp::Eq, 2)],
+        vec![(BinOp::Ne, 1), (BinOp::Ne, 2)],
+    ]);
+}
+
+#[test]
+fn discriminant_branch_kind() {
+    let kinds = Rc::new(RefCell::new(Vec::new()));
+    let kinds1 = kinds.clone();
+    let trace = move |event: &::seer::BranchEvent| {
+        if event.function == "main" {
+            kinds1.borrow_mut().push(event.kind);
+        }
+    };
+
+    let args = vec!["seer".to_string(), "tests/symbolic/enum_discriminant.rs".to_string()];
+    ::seer::ExecutionConfig::new()
+        .branch_trace(trace)
+        .consumer(|_| true)
+        .run(args);
+
+    // Reading the discriminant of the symbolic enum forks in a statement, but not
+    // through a symbolic pointer.
+    let kinds = kinds.borrow();
+    assert!(kinds.contains(&::seer::BranchKind::Other));
+    assert!(!kinds.contains(&::seer::BranchKind::SymbolicPointer));
+}
+
+#[derive(Debug)]
+struct CountingBackend {
+    queries: Rc<Cell<usize>>,
+}
+
+impl ::seer::SolverBackend for CountingBackend {
+    fn check_sat(&self, constraints: &::seer::ConstraintSet) -> ::seer::SatResult {
+        self.queries.set(self.queries.get() + 1);
+        ::seer::SolverBackend::check_sat(&::seer::Z3Backend, constraints)
+    }
+
+    fn get_model(&self, constraints: &::seer::ConstraintSet) -> Option<::seer::Model> {
+        self.queries.set(self.queries.get() + 1);
+        ::seer::SolverBackend::get_model(&::seer::Z3Backend, constraints)
+    }
+}
+
+#[test]
+fn custom_solver_backend() {
+    let queries = Rc::new(Cell::new(0));
+    let paths = collect_paths("tests/symbolic/simple.rs", |config| {
+        config.solver_backend(CountingBackend { queries: queries.clone() });
+    });
+
+    assert_eq!(panic_inputs(&paths), vec![vec![43]]);
+    assert!(queries.get() > 0);
+}
+
+
+#[test]
+fn caching_solver_backend() {
+    let backend = Rc::new(::seer::CachingBackend::new(::seer::Z3Backend));
+
+    // Answers from the cache must not change which inputs are found.
+    let paths = collect_paths("tests/symbolic/comparisons.rs", |config| {
+        config.solver_backend(backend.clone());
+    });
+
+    assert_eq!(panic_inputs(&paths), vec![vec![17, 18, 38, 37, 101]]);
+    assert!(backend.hits() > 0);
+}
+
+
+#[test]
+fn caching_backend_branch_heavy() {
+    let backend = Rc::new(::seer::CachingBackend::new(::seer::Z3Backend));
+
+    // Every byte forks, and the paths share their prefixes, so the cache answers a
+    // share of the queries.
+    let paths = collect_paths("tests/symbolic/branch_heavy.rs", |config| {
+        config.solver_backend(backend.clone());
+    });
+
+    // One panic for each choice of the 3 bytes out of 6 that are above 100.
+    assert_eq!(panic_inputs(&paths).len(), 20);
+    assert!(backend.hits() > 0);
+    assert!(backend.misses() > 0);
+}
+
+
+#[test]
+fn caching_backend_model_per_variable_count() {
+    use rustc::mir::BinOp;
+    use seer::{CachingBackend, Constraint, ConstraintSet, PrimVal, PrimValKind, SatResult, SolverBackend,
+               VarOrigin, VarType, Z3Backend};
+
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(ptr, 1).unwrap();
+        let x = memory.read_abstract(PrimVal::Ptr(ptr), 1).unwrap();
+
+        let constraints = [Constraint::new_compare(BinOp::Eq, PrimValKind::U8, x, PrimVal::Bytes(7))];
+        let variables = [(VarType::BitVec8, VarOrigin::StdIn), (VarType::BitVec8, VarOrigin::StdIn)];
+        let query = |n: usize| ConstraintSet {
+            variables: &variables[..n],
+            constraints: &constraints,
+            extra_constraints: &[],
+            timeout_ms: None,
+        };
+
+        let backend = CachingBackend::new(Z3Backend);
+        assert_eq!(backend.get_model(&query(1)).unwrap().values, vec![Some(7)]);
+        // A path that created another variable since gets a value for it too.
+        assert_eq!(backend.get_model(&query(2)).unwrap().values.len(), 2);
+        assert_eq!(backend.check_sat(&query(2)), SatResult::Sat);
+        assert_eq!((backend.hits(), backend.misses()), (1, 2));
+    });
+}
+
+#[test]
+fn incremental_solver_backend() {
+    let incremental = Rc::new(::seer::IncrementalBackend::new());
+    let backend = Rc::new(::seer::CachingBackend::new(incremental.clone()));
+    let paths = collect_paths("tests/symbolic/comparisons.rs", |config| {
+        config.solver_backend(backend.clone());
+    });
+
+    assert_eq!(panic_inputs(&paths), vec![vec![17, 18, 38, 37, 101]]);
+    let hit_rate = backend.hits() as f64 / (backend.hits() + backend.misses()) as f64;
+    assert!(hit_rate > 0.0, "no cache hits");
+    // Most of each query that reaches the solver was asserted by the query before.
+    assert!(incremental.reused_constraints() > incremental.asserted_constraints(),
+            "reused {} of {} constraints", incremental.reused_constraints(),
+            incremental.reused_constraints() + incremental.asserted_constraints());
+}
+
+
+#[test]
+fn simplified_constraint_set() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, ConstraintSet, PrimVal, PrimValKind};
+
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(ptr, 1).unwrap();
+        let x = memory.read_abstract(PrimVal::Ptr(ptr), 1).unwrap();
+
+        let below_3 = Constraint::new_compare(BinOp::Lt, PrimValKind::U8, x, PrimVal::Bytes(3));
+        let constraints = [
+            Constraint::new_compare(BinOp::Eq, PrimValKind::U8, x, x),
+            below_3,
+            Constraint::new_compare(BinOp::Le, PrimValKind::I8, PrimVal::from_i128(-1), PrimVal::Bytes(2)),
+        ];
+        let set = ConstraintSet {
+            variables: &[],
+            constraints: &constraints,
+            extra_constraints: &[below_3],
+            timeout_ms: None,
+        };
+        assert_eq!(set.simplified(), Some(vec![below_3]));
+
+        let false_constraint = [Constraint::new_compare(BinOp::Lt, PrimValKind::U64, PrimVal::Bytes(5), PrimVal::Bytes(3))];
+        let set = ConstraintSet { extra_constraints: &false_constraint, ..set };
+        assert_eq!(set.simplified(), None);
+
+        // The query is answered without asking the backend.
+        let queries = Rc::new(Cell::new(0));
+        let mut context = ::seer::ConstraintContext::new();
+        context.set_backend(Rc::new(CountingBackend { queries: queries.clone() }));
+        assert!(!context.is_feasible_with(&false_constraint));
+        assert_eq!(queries.get(), 0);
+        assert_eq!(context.solver_queries(), 0);
+    });
+}
+
+#[test]
+fn branch_depth_limit() {
+    let paths = collect_paths("tests/symbolic/symbolic_loop.rs", |config| {
+        config.max_branch_depth(10);
+    });
+
+    let limited = paths.iter().filter(|complete| match complete.result {
+        Err(::seer::StaticEvalError::BranchDepthLimitReached) => true,
+        _ => false,
+    }).count();
+    let finished = paths.iter().filter(|complete| complete.result.is_ok()).count();
+    assert_eq!(limited, 2);
+    assert_eq!(finished, 10);
+}
+
+
+#[test]
+fn explore_lazily() {
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let found1 = found.clone();
+    let explorer = move |paths: &mut Iterator<Item = ::seer::ExecutionComplete>| {
+        // Every path reads another byte, so the exploration only ends because
+        // no more paths are requested.
+        found1.borrow_mut().extend(paths.take(1));
+    };
+
+    let args = vec!["seer".to_string(), "tests/symbolic/endless_input.rs".to_string()];
+    ::seer::ExecutionConfig::new()
+        .explore(args, explorer);
+
+    let found = found.borrow();
+    assert_eq!(found.len(), 1);
+    match found[0].result {
+        Err(::seer::StaticEvalError::DivisionByZero(_)) => {}
+        ref other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(found[0].input, vec![0]);
+}
+
+#[test]
+fn dump_allocation() {
+    with_memory(|memory, layout| {
+        let target = memory.allocate(1, 1).unwrap();
+        let ptr = memory.allocate(16, 8).unwrap();
+        memory.write_bytes(ptr, &[0x01, 0xab]).unwrap();
+        memory.write_fresh_abstract_bytes(ptr.offset(2, layout).unwrap(), 1).unwrap();
+        memory.write_ptr(ptr.offset(8, layout).unwrap(), target).unwrap();
+
+        assert_eq!(
+            memory.dump_allocation(ptr.alloc_id),
+            "Alloc 3: 01 ab $0 __ __ __ __ __ [alloc 2: 00 00 00 00 00 00 00 00] (16 bytes, alignment 8)");
+    });
+}
+
+#[test]
+fn memory_snapshot_restore() {
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(2, 1).unwrap();
+        memory.write_bytes(ptr, &[0x01, 0x02]).unwrap();
+
+        let snapshot = memory.snapshot();
+        memory.write_bytes(ptr, &[0x03, 0x04]).unwrap();
+        let later = memory.allocate(4, 1).unwrap();
+        assert_eq!(memory.dump_allocation(ptr.alloc_id), "Alloc 2: 03 04 (2 bytes, alignment 1)");
+
+        memory.restore(snapshot);
+        assert_eq!(memory.dump_allocation(ptr.alloc_id), "Alloc 2: 01 02 (2 bytes, alignment 1)");
+        assert!(memory.get(later.alloc_id).is_err());
+    });
+}
+
+#[test]
+fn inline_asm_summary() {
+    use seer::AsmOutput;
+
+    let paths = collect_paths("tests/symbolic/asm_summary.rs", |config| {
+        config.asm_summary("rdtsc", |inputs| {
+            assert!(inputs.is_empty());
+            vec![AsmOutput::Symbolic, AsmOutput::Symbolic]
+        });
+    });
+
+    assert_eq!(paths.len(), 2);
+    assert!(paths.iter().any(|complete| complete.result.is_ok()));
+    assert_eq!(panic_inputs(&paths).len(), 1);
+}
+
+
+#[test]
+fn replay_crashing_model() {
+    let paths = collect_paths("tests/symbolic/simple.rs", |_| {});
+    let crash = paths.iter().find(|complete| complete.result.is_err()).unwrap();
+    let model = crash.model().unwrap().clone();
+    assert_eq!(crash.input, vec![43]);
+
+    for _ in 0..2 {
+        let replayed = collect_paths("tests/symbolic/simple.rs", |config| {
+            config.replay(model.clone());
+        });
+        assert_eq!(replayed.len(), 1);
+        match replayed[0].result {
+            Err(::seer::StaticEvalError::Panic) => {}
+            ref other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(replayed[0].input, vec![43]);
+    }
+}
+
+
+fn path_stats(filename: &str) -> Vec<::seer::PathStats> {
+    collect_paths(filename, |_| {}).iter().map(|complete| *complete.stats()).collect()
+}
+
+
+#[test]
+fn path_step_counts() {
+    // `_0 = ();` and `return`.
+    let stats = path_stats("tests/symbolic/empty_main.rs");
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].steps, 2);
+    assert_eq!(stats[0].forks, 0);
+
+    // Both paths fork once, on the comparison of the input.
+    let stats = path_stats("tests/symbolic/simple.rs");
+    assert_eq!(stats.len(), 2);
+    for path in &stats {
+        assert_eq!(path.forks, 1);
+        assert!(path.steps > 2);
+        assert!(path.solver_queries > 0);
+    }
+}
+
+#[test]
+fn allocation_kinds() {
+    use seer::{AllocKind, StaticEvalError};
+
+    with_memory(|memory, _| {
+        let heap = memory.allocate_with_kind(4, 1, AllocKind::Heap).unwrap();
+        let constant = memory.allocate(4, 1).unwrap();
+        memory.write_bytes(constant, &[1, 2, 3, 4]).unwrap();
+        memory.mark_static_initalized(constant.alloc_id, false).unwrap();
+
+        assert_eq!(memory.get(heap.alloc_id).unwrap().kind, AllocKind::Heap);
+        assert_eq!(memory.get(constant.alloc_id).unwrap().kind, AllocKind::ImmutableStatic);
+
+        memory.write_bytes(heap, &[5, 6, 7, 8]).unwrap();
+        match memory.write_bytes(constant, &[5, 6, 7, 8]) {
+            Err(e) => match StaticEvalError::from(e) {
+                StaticEvalError::ModifiedConstantMemory => {}
+                other => panic!("unexpected error {:?}", other),
+            },
+            Ok(()) => panic!("modified a constant"),
+        }
+        match memory.deallocate(constant) {
+            Err(e) => match StaticEvalError::from(e) {
+                StaticEvalError::DeallocatedStaticMemory => {}
+                other => panic!("unexpected error {:?}", other),
+            },
+            Ok(()) => panic!("deallocated a constant"),
+        }
+        memory.deallocate(heap).unwrap();
+    });
+}
+
+#[test]
+fn allocation_access_stats() {
+    use seer::{AccessStats, AllocKind, PrimVal};
+
+    with_memory(|memory, _| {
+        memory.track_accesses();
+        let leaked = memory.allocate_with_kind(4, 1, AllocKind::Heap).unwrap();
+        let freed = memory.allocate_with_kind(4, 1, AllocKind::Heap).unwrap();
+
+        memory.write_bytes(leaked, &[1, 2, 3, 4]).unwrap();
+        memory.read_bytes(PrimVal::Ptr(leaked), 4).unwrap();
+        memory.read_bytes(PrimVal::Ptr(leaked), 2).unwrap();
+        memory.write_bytes(freed, &[5, 6, 7, 8]).unwrap();
+        memory.deallocate(freed).unwrap();
+
+        let leaks = memory.unfreed_allocations();
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].id, leaked.alloc_id);
+        assert_eq!(leaks[0].kind, AllocKind::Heap);
+        assert_eq!(leaks[0].size, 4);
+        assert_eq!(leaks[0].stats, AccessStats { reads: 2, writes: 1, freed: false });
+        assert_eq!(memory.access_stats(freed.alloc_id),
+                   Some(AccessStats { reads: 0, writes: 1, freed: true }));
+    });
+}
+
+#[test]
+fn feasibility_query() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind, Value};
+
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(ptr, 1).unwrap();
+        let x = memory.read_abstract(PrimVal::Ptr(ptr), 1).unwrap();
+
+        let below_5 = memory.constraints.add_binop_constraint(BinOp::Lt, x, PrimVal::Bytes(5), PrimValKind::U8);
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Eq, PrimValKind::Bool, below_5, PrimVal::from_bool(true)));
+
+        let above_10 = memory.constraints.add_binop_constraint(BinOp::Gt, x, PrimVal::Bytes(10), PrimValKind::U8);
+        let below_3 = memory.constraints.add_binop_constraint(BinOp::Lt, x, PrimVal::Bytes(3), PrimValKind::U8);
+        assert!(!memory.is_feasible(&Value::ByVal(above_10)).unwrap());
+        assert!(memory.is_feasible(&Value::ByVal(below_3)).unwrap());
+        assert!(!memory.is_feasible(&Value::ByVal(PrimVal::from_bool(false))).unwrap());
+    });
+}
+
+#[test]
+fn enumerate_feasible_values() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind};
+
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(ptr, 1).unwrap();
+        let x = memory.read_abstract(PrimVal::Ptr(ptr), 1).unwrap();
+
+        let below_3 = memory.constraints.add_binop_constraint(BinOp::Lt, x, PrimVal::Bytes(3), PrimValKind::U8);
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Eq, PrimValKind::Bool, below_3, PrimVal::from_bool(true)));
+
+        assert_eq!(memory.constraints.enumerate_values(x, PrimValKind::U8, 10), (vec![0, 1, 2], false));
+        assert_eq!(memory.constraints.enumerate_values(x, PrimValKind::U8, 3), (vec![0, 1, 2], false));
+
+        let (values, truncated) = memory.constraints.enumerate_values(x, PrimValKind::U8, 2);
+        assert_eq!(values.len(), 2);
+        assert!(truncated);
+    });
+}
+
+/// Answers the first `answers` model queries, and then runs out of time on every one.
+#[derive(Debug)]
+struct TimingOutBackend {
+    answers: Cell<usize>,
+}
+
+impl ::seer::SolverBackend for TimingOutBackend {
+    fn check_sat(&self, constraints: &::seer::ConstraintSet) -> ::seer::SatResult {
+        ::seer::SolverBackend::check_sat(&::seer::Z3Backend, constraints)
+    }
+
+    fn get_model(&self, constraints: &::seer::ConstraintSet) -> Option<::seer::Model> {
+        if self.answers.get() == 0 {
+            ::std::thread::sleep(::std::time::Duration::from_millis(5));
+            return None;
+        }
+        self.answers.set(self.answers.get() - 1);
+        ::seer::SolverBackend::get_model(&::seer::Z3Backend, constraints)
+    }
+}
+
+#[test]
+fn enumerate_values_timeout() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind};
+
+    with_memory(|memory, _| {
+        let ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(ptr, 1).unwrap();
+        let x = memory.read_abstract(PrimVal::Ptr(ptr), 1).unwrap();
+
+        let below_3 = memory.constraints.add_binop_constraint(BinOp::Lt, x, PrimVal::Bytes(3), PrimValKind::U8);
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Eq, PrimValKind::Bool, below_3, PrimVal::from_bool(true)));
+        memory.constraints.set_backend(Rc::new(TimingOutBackend { answers: Cell::new(2) }));
+        memory.constraints.set_solver_timeout(Some(1));
+
+        // The third query runs out of time, so the two values found may not be all of them.
+        let (values, truncated) = memory.constraints.enumerate_values(x, PrimValKind::U8, 10);
+        assert_eq!(values.len(), 2);
+        assert!(truncated);
+    });
+}
+
+#[test]
+fn read_symbolic_region() {
+    use rustc::mir::BinOp;
+    use seer::{Constraint, PrimVal, PrimValKind, StaticEvalError, Value};
+
+    with_memory(|memory, _| {
+        let buffer = memory.allocate(16, 1).unwrap();
+        memory.write_fresh_abstract_bytes(buffer, 16).unwrap();
+
+        let queries = memory.constraints.solver_queries();
+        assert_eq!(memory.read_region(buffer, PrimVal::Bytes(16)).unwrap(), Value::ByRef(buffer));
+        assert_eq!(memory.constraints.solver_queries(), queries);
+        match memory.read_region(buffer, PrimVal::Bytes(17)) {
+            Err(err) => match StaticEvalError::from(err) {
+                StaticEvalError::PointerOutOfBounds { allocation_size: 16, .. } => {}
+                other => panic!("unexpected error: {:?}", other),
+            },
+            Ok(value) => panic!("read past the end: {:?}", value),
+        }
+
+        // A symbolic length is bounded with a single query.
+        let len_ptr = memory.allocate(1, 1).unwrap();
+        memory.write_fresh_abstract_bytes(len_ptr, 1).unwrap();
+        let len = memory.read_abstract(PrimVal::Ptr(len_ptr), 1).unwrap();
+        let queries = memory.constraints.solver_queries();
+        assert!(memory.read_region(buffer, len).is_err());
+        assert_eq!(memory.constraints.solver_queries(), queries + 1);
+
+        let at_most_16 = memory.constraints.add_binop_constraint(BinOp::Le, len, PrimVal::Bytes(16), PrimValKind::U8);
+        memory.constraints.push_constraint(
+            Constraint::new_compare(BinOp::Eq, PrimValKind::Bool, at_most_16, PrimVal::from_bool(true)));
+        let queries = memory.constraints.solver_queries();
+        assert_eq!(memory.read_region(buffer, len).unwrap(), Value::ByRef(buffer));
+        assert_eq!(memory.constraints.solver_queries(), queries + 1);
+    });
+}
+
+#[test]
+fn symbolic_nul_positions() {
+    with_memory(|memory, layout| {
+        let buffer = memory.allocate(4, 1).unwrap();
+        memory.write_fresh_abstract_bytes(buffer, 4).unwrap();
+
+        // A nul at each of the offsets 0..4, or none at all.
+        let mut states = memory.nul_positions(buffer, 4).unwrap();
+        assert_eq!(states.len(), 5);
+        match states.pop() {
+            Some((Err(err), constraints)) => {
+                assert_eq!(constraints.len(), 4);
+                match ::seer::StaticEvalError::from(err) {
+                    ::seer::StaticEvalError::UnterminatedCString(_) => {}
+                    other => panic!("unexpected error: {:?}", other),
+                }
+            }
+            other => panic!("expected an unterminated buffer: {:?}", other),
+        }
+        for (expected, (pos, constraints)) in states.into_iter().enumerate() {
+            let pos = pos.unwrap();
+            assert_eq!(pos, expected as u64);
+            assert_eq!(constraints.len() as u64, pos + 1);
+        }
+
+        assert!(memory.nul_positions(buffer, 5).is_err());
+
+        memory.write_bytes(buffer.offset(1, layout).unwrap(), &[0]).unwrap();
+        let positions: Vec<u64> = memory.nul_positions(buffer, 4).unwrap()
+            .into_iter().map(|(pos, _)| pos.unwrap()).collect();
+        assert_eq!(positions, vec![0, 1]);
+    });
+}
+
+#[test]
+fn symbolic_c_str_lengths() {
+    use seer::StaticEvalError;
+
+    with_memory(|memory, layout| {
+        let buffer = memory.allocate(5, 1).unwrap();
+        memory.write_fresh_abstract_bytes(buffer, 4).unwrap();
+        memory.write_bytes(buffer.offset(4, layout).unwrap(), &[0]).unwrap();
+
+        // The string may end at any of the symbolic bytes, or at the concrete terminator.
+        let lengths: Vec<u64> = memory.c_str_lengths(buffer, None).unwrap()
+            .into_iter().map(|(len, _)| len.unwrap()).collect();
+        assert_eq!(lengths, vec![0, 1, 2, 3, 4]);
+
+        // Within two bytes, the string may also not end at all.
+        let mut lengths = memory.c_str_lengths(buffer, Some(2)).unwrap();
+        match lengths.pop() {
+            Some((Err(err), constraints)) => {
+                assert_eq!(constraints.len(), 3);
+                match StaticEvalError::from(err) {
+                    StaticEvalError::UnterminatedCString(_) => {}
+                    other => panic!("unexpected error: {:?}", other),
+                }
+            }
+            other => panic!("expected an unterminated string: {:?}", other),
+        }
+        let lengths: Vec<u64> = lengths.into_iter().map(|(len, _)| len.unwrap()).collect();
+        assert_eq!(lengths, vec![0, 1, 2]);
+
+        match memory.read_c_str(buffer) {
+            Err(err) => match StaticEvalError::from(err) {
+                StaticEvalError::UnsupportedSymbolicOperation { .. } => {}
+                other => panic!("unexpected error: {:?}", other),
+            },
+            Ok(s) => panic!("read a symbolic string: {:?}", s),
+        }
+
+        let name = memory.allocate(4, 1).unwrap();
+        memory.write_bytes(name, b"abc\0").unwrap();
+        assert_eq!(memory.read_c_str(name).unwrap(), b"abc".to_vec());
+    });
+}
+
+#[test]
+fn strlen_sometimes_unterminated() {
+    let paths = collect_paths("tests/symbolic/strlen_unterminated.rs", |_| {});
+
+    let unterminated: Vec<_> = paths.iter().filter_map(|complete| match complete.result {
+        Err(::seer::StaticEvalError::UnterminatedCString(_)) => Some(complete.input.clone()),
+        _ => None,
+    }).collect();
+    assert_eq!(unterminated.len(), 1);
+    assert!(unterminated[0].iter().all(|&b| b != 0));
+
+    let panics = panic_inputs(&paths);
+    assert_eq!(panics.len(), 1);
+    assert!(panics[0][0] != 0 && panics[0][1] != 0 && panics[0][2] == 0);
+}
+
+
+#[test]
+fn coverage_report() {
+    let args = vec!["seer".to_string(), "tests/symbolic/coverage.rs".to_string()];
+    let mut config = ::seer::ExecutionConfig::new();
+    assert!(config.coverage().is_none());
+    config.track_coverage(true).run(args);
+
+    let report = config.coverage().unwrap();
+    let main = report.function("main").unwrap();
+    assert!(main.visited_blocks > 0);
+    assert!(main.visited_blocks < main.blocks);
+    assert!(main.visited_edges < main.edges);
+    assert_eq!(main.unreached_blocks().len(), main.blocks - main.visited_blocks);
+}
+
+#[test]
+fn path_tree_dot() {
+    use seer::PathOutcome;
+
+    let args = vec!["seer".to_string(), "tests/symbolic/simple.rs".to_string()];
+    let mut config = ::seer::ExecutionConfig::new();
+    assert!(config.path_tree().is_none());
+    config.track_path_tree(true).run(args);
+
+    // The root forks on the comparison of the input into two leaves.
+    let tree = config.path_tree().unwrap();
+    assert_eq!(tree.nodes.len(), 3);
+    assert!(tree.nodes[0].location.as_ref().unwrap().starts_with("main "));
+    let mut outcomes: Vec<_> = tree.nodes[1..].iter().map(|node| {
+        assert_eq!(node.parent, Some(0));
+        assert!(!node.constraints.is_empty());
+        node.outcome.unwrap()
+    }).collect();
+    outcomes.sort_by_key(|outcome| *outcome == PathOutcome::Panicked);
+    assert_eq!(outcomes, vec![PathOutcome::Completed, PathOutcome::Panicked]);
+
+    let dot = tree.export_dot();
+    assert!(dot.starts_with("digraph paths {"));
+    assert_eq!(dot.lines().filter(|line| line.contains(" [label=") && !line.contains("->")).count(), 3);
+    assert_eq!(dot.matches(" -> ").count(), 2);
+    assert!(dot.contains("color=red"));
+    assert!(dot.contains("color=green"));
+}
+
+#[test]
+fn target_pointer_width() {
+    // The 32-bit analysis needs the i686 standard library in the sysroot.
+    let host = ::rustc::session::config::host_triple();
+    let i686 = format!("i686{}", host.find('-').map_or("", |i| &host[i..]));
+    let sysroot = ::std::process::Command::new("rustc").args(&["--print", "sysroot"]).output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    let has_i686 = sysroot.map(|sysroot| {
+        ::std::path::Path::new(&sysroot).join("lib/rustlib").join(&i686).join("lib").exists()
+    }).unwrap_or(false);
+
+    for &(bits, expected) in &[(32, 31), (64, 63)] {
+        if bits == 32 && !has_i686 {
+            continue;
+        }
+        let paths = collect_paths("tests/symbolic/pointer_width.rs", |config| {
+            config.target_pointer_width(bits);
+        });
+        assert_eq!(panic_inputs(&paths), vec![vec![expected]]);
+    }
+}
+
+#[test]
+fn max_paths_truncates() {
+    let found = Rc::new(Cell::new(0));
+    let found1 = found.clone();
+    let consumer = move |_: ::seer::ExecutionComplete| {
+        found1.set(found1.get() + 1);
+        true
+    };
+
+    let args = vec!["seer".to_string(), "tests/symbolic/symbolic_loop.rs".to_string()];
+    let mut config = ::seer::ExecutionConfig::new();
+    config.max_paths(3).consumer(consumer);
+    assert!(!config.truncated());
+    config.run(args);
+
+    assert_eq!(found.get(), 3);
+    assert!(config.truncated());
+
+    // A later exploration that runs to completion is not truncated.
+    let args = vec!["seer".to_string(), "tests/symbolic/simple.rs".to_string()];
+    config.run(args);
+    assert!(!config.truncated());
+}
+
+#[test]
+fn symbolic_assume_prunes() {
+    let paths = collect_paths("tests/symbolic/assume.rs", |_| {});
+    assert_eq!(paths.len(), 2);
+    for complete in &paths {
+        match complete.result {
+            Ok(()) => assert!(complete.input[0] < 5),
+            Err(::seer::StaticEvalError::Panic) => assert_eq!(complete.input, vec![5]),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
+
+
+#[test]
+fn unreachable_reached() {
+    let paths = collect_paths("tests/symbolic/unreachable.rs", |_| {});
+    assert_eq!(paths.len(), 2);
+    for complete in &paths {
+        match complete.result {
+            Ok(()) => assert!(complete.input[0] != 7),
+            Err(::seer::StaticEvalError::Unreachable) => assert_eq!(complete.input, vec![7]),
+            ref other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
+
+
+#[test]
+fn assume_prunes_unreachable() {
+    let paths = collect_paths("tests/symbolic/assume_unreachable.rs", |_| {});
+    assert_eq!(paths.len(), 1);
+    match paths[0].result {
+        Ok(()) => assert!(paths[0].input[0] < 100),
+        ref other => panic!("unexpected result {:?}", other),
+    }
+}
+
+
+extern "C" fn ignore_z3_error(_ctx: ::z3_sys::Z3_context, _error: ::z3_sys::Z3_error_code) {}
+
+/// Parses `script` with a fresh Z3 context and checks it, or returns `None`
+/// if it does not parse.
+fn check_smtlib(script: &str) -> Option<::seer::SatResult> {
+    use z3_sys::*;
+    let script = ::std::ffi::CString::new(script).unwrap();
+    unsafe {
+        let cfg = Z3_mk_config();
+        let ctx = Z3_mk_context(cfg);
+        Z3_set_error_handler(ctx, ignore_z3_error);
+        let formula = Z3_parse_smtlib2_string(
+            ctx, script.as_ptr(), 0, ::std::ptr::null(), ::std::ptr::null(),
+            0, ::std::ptr::null(), ::std::ptr::null());
+        let result = if Z3_get_error_code(ctx) != Z3_OK {
+            None
+        } else {
+            let solver = Z3_mk_solver(ctx);
+            Z3_solver_inc_ref(ctx, solver);
+            Z3_solver_assert(ctx, solver, formula);
+            let result = match Z3_solver_check(ctx, solver) {
+                Z3_L_TRUE => ::seer::SatResult::Sat,
+                Z3_L_FALSE => ::seer::SatResult::Unsat,
+                _ => ::seer::SatResult::Unknown,
+            };
+            Z3_solver_dec_ref(ctx, solver);
+            Some(result)
+        };
+        Z3_del_context(ctx);
+        Z3_del_config(cfg);
+        result
+    }
+}
+
+/// Answers every query with `Z3Backend`, and checks that its SMT-LIB rendering
+/// gets the same answer.
+#[derive(Debug)]
+struct SmtlibBackend {
+    queries: Rc<Cell<usize>>,
+}
+
+impl ::seer::SolverBackend for SmtlibBackend {
+    fn check_sat(&self, constraints: &::seer::ConstraintSet) -> ::seer::SatResult {
+        self.queries.set(self.queries.get() + 1);
+        let script = constraints.to_smtlib();
+        assert_eq!(script.matches("(assert ").count(), constraints.iter().count());
+        let result = ::seer::SolverBackend::check_sat(&::seer::Z3Backend, constraints);
+        assert_eq!(check_smtlib(&script), Some(result), "{}", script);
+        result
+    }
+
+    fn get_model(&self, constraints: &::seer::ConstraintSet) -> Option<::seer::Model> {
+        ::seer::SolverBackend::get_model(&::seer::Z3Backend, constraints)
+    }
+}
+
+#[test]
+fn smtlib_round_trip() {
+    for filename in &["tests/symbolic/comparisons.rs",
+                      "tests/symbolic/read_signed_int.rs",
+                      "tests/symbolic/trailing_zeros.rs",
+                      "tests/symbolic/indexing.rs",
+                      "tests/symbolic/infeasible.rs"] {
+        let queries = Rc::new(Cell::new(0));
+        let args = vec!["seer".to_string(), filename.to_string()];
+        ::seer::ExecutionConfig::new()
+            .solver_backend(SmtlibBackend { queries: queries.clone() })
+            .consumer(|_| true)
+            .run(args);
+        assert!(queries.get() > 0, "no queries for {}", filename);
+    }
+}
@@ -0,0 +1,16 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let a = Box::new(1u8);
+    let b = vec![2u8, 3, 4];
+    if data[0] > 100 {
+        let c = Box::new(5u8);
+        assert_eq!(*a + *c, 6);
+    }
+    let p = b.as_ptr();
+    let x = unsafe { *p.offset(data[0] as isize) };
+    assert_eq!(x + *a, 3);
+}
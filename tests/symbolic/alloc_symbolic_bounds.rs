@@ -0,0 +1,18 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let n = data[0] as usize;
+    let mut v: Vec<u8> = Vec::with_capacity(n);
+    if n != 0 {
+        unsafe {
+            *v.as_mut_ptr().offset(3) = 42;
+        }
+    }
+    if n == 200 {
+        panic!()
+    }
+}
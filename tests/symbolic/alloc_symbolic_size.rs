@@ -0,0 +1,13 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let n = data[0] as usize;
+    let v: Vec<u8> = Vec::with_capacity(n);
+    if v.capacity() == 7 {
+        panic!()
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let fixed = vec![0u8; 4];
+    if fixed[2] != 0 {
+        panic!()
+    }
+
+    let n = data[0] as usize;
+    if n == 0 {
+        return;
+    }
+    let v = vec![0u8; n];
+    if v[n - 1] != 0 {
+        panic!()
+    }
+}
@@ -0,0 +1,16 @@
+#![feature(asm)]
+
+fn rdtsc() -> u64 {
+    let lo: u32;
+    let hi: u32;
+    unsafe {
+        asm!("rdtsc" : "={eax}"(lo), "={edx}"(hi) : : : "volatile");
+    }
+    ((hi as u64) << 32) | lo as u64
+}
+
+fn main() {
+    if rdtsc() == 0x1234_5678_9abc {
+        panic!()
+    }
+}
@@ -0,0 +1,25 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics::assume;
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if x > 20 {
+        // Cannot hold, so nothing below is explored.
+        unsafe { assume(x < 10) }
+        if x == 30 {
+            panic!()
+        }
+        if x == 40 {
+            panic!()
+        }
+    } else if x >= 5 {
+        unsafe { assume(x < 6) }
+        panic!()
+    }
+}
@@ -0,0 +1,17 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics::{assume, unreachable};
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    unsafe { assume(x < 100) }
+    if x >= 100 {
+        // The assumption makes this path infeasible.
+        unsafe { unreachable() }
+    }
+}
@@ -0,0 +1,22 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 3];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if (x & 0xf0) != 0x10 || (x | 0xf0) != 0xf7 || (x ^ 0x0f) != 0x18 {
+        return;
+    }
+
+    let y = data[1];
+    if (y << 2) != 0x14 || (y >> 3) != 0 {
+        return;
+    }
+
+    // Shift by a symbolic amount.
+    let s = data[2];
+    if s < 8 && (1u8 << s) == 8 && (0x80u8 >> s) == 0x10 {
+        panic!()
+    }
+}
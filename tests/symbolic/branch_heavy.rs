@@ -0,0 +1,20 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 6];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let mut count = 0;
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] > 100 {
+            count += 1;
+        }
+        i += 1;
+    }
+
+    if count == 3 {
+        panic!()
+    }
+}
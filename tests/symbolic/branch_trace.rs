@@ -0,0 +1,11 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if x > 0 {
+        panic!()
+    }
+}
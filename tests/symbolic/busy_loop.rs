@@ -0,0 +1,12 @@
+#![feature(custom_attribute, attr_literals)]
+#![miri(step_limit=1000000000000)]
+
+fn main() {
+    let mut x: u64 = 0;
+    loop {
+        x = x.wrapping_add(1);
+        if x == 0 {
+            break;
+        }
+    }
+}
@@ -0,0 +1,17 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut x = 0u32;
+    for idx in 0..4 {
+        x |= (data[idx] as u32) << (8 * idx);
+    }
+    if x >= 0xD000 && x < 0xE000 {
+        let c = unsafe { ::std::char::from_u32_unchecked(x) };
+        if c == '\u{D7FF}' {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if x.checked_add(1).is_none() {
+        panic!() // 255
+    }
+
+    if x < 129 && x.checked_mul(2).is_none() {
+        panic!() // 128
+    }
+
+    let y = x as i8;
+    if y > 0 && y < 44 && y.checked_mul(3).is_none() {
+        panic!() // 43
+    }
+}
@@ -0,0 +1,17 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut sum: u32 = 0;
+    let mut i: u32 = 0;
+    while i < 1000 {
+        sum = (sum * 3 + (i ^ 0x5a)) % 65521;
+        i += 1;
+    }
+    if data[0] as u32 == sum & 0xff {
+        panic!()
+    }
+}
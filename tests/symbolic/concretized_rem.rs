@@ -0,0 +1,16 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 8];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut bits = 0u64;
+    for idx in 0..8 {
+        bits |= (data[idx] as u64) << (8 * idx);
+    }
+    let x = f64::from_bits(bits);
+
+    // Pins `x`, after which the path cannot fail.
+    let _rem = x % 2.0;
+}
@@ -0,0 +1,17 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if x == 5 {
+        // `x` is fully determined here, so this should not fork.
+        match x {
+            4 => {}
+            5 => panic!(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use std::io::{self, Read};
+use std::ptr;
+
+fn main() {
+    let mut data = [0; 5];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let n = data[0] as usize;
+    let mut buf = [0u8; 4];
+    if n <= 4 {
+        unsafe {
+            ptr::copy_nonoverlapping(data[1..].as_ptr(), buf.as_mut_ptr(), n);
+        }
+        if buf == [1, 2, 3, 4] {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,11 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0] as u16 | (data[1] as u16) << 8;
+    if x.count_ones() == 1 {
+        panic!()
+    }
+}
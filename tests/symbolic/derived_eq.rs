@@ -0,0 +1,19 @@
+use std::io::Read;
+
+#[derive(PartialEq)]
+struct Point {
+    x: u8,
+    y: u8,
+}
+
+fn main() {
+    let mut data = [0; 3];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let p = Point { x: data[0], y: data[1] };
+    let q = Point { x: data[2], y: 7 };
+    if p == q && p.x == 3 {
+        panic!()
+    }
+}
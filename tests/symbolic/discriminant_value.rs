@@ -0,0 +1,18 @@
+#![feature(core_intrinsics)]
+
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let result: Result<u8, u8> = unsafe { ::std::mem::transmute(data) };
+    let discr = unsafe { ::std::intrinsics::discriminant_value(&result) };
+    if discr == 0 && data[1] == 7 {
+        panic!()
+    }
+    if discr == 1 && data[1] == 42 {
+        panic!()
+    }
+}
@@ -0,0 +1,11 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let q = 100 / data[0];
+    if q == 50 {
+        panic!()
+    }
+}
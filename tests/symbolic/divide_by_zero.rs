@@ -0,0 +1,10 @@
+fn average(total: u32, count: u32) {
+    let average = total / count;
+    if average > total {
+        unreachable!()
+    }
+}
+
+fn main() {
+    average(10, 2);
+}
@@ -0,0 +1,10 @@
+fn main() {
+    use std::io::Read;
+    let mut stdin = ::std::io::stdin();
+    let mut sum = 0u8;
+    loop {
+        let mut data = [0; 1];
+        stdin.read_exact(&mut data[..]).unwrap();
+        sum = sum.wrapping_add(100 / data[0]);
+    }
+}
@@ -0,0 +1,15 @@
+use std::io::Read;
+
+fn f() {
+    f()
+}
+
+fn main() {
+    let mut data = [0];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    if data[0] == 7 {
+        f();
+    }
+}
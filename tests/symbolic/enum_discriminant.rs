@@ -0,0 +1,20 @@
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+enum Letter {
+    A,
+    B,
+    C,
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let letter: Letter = unsafe { ::std::mem::transmute(data[0]) };
+    match letter {
+        Letter::A | Letter::B => {}
+        Letter::C => panic!(),
+    }
+}
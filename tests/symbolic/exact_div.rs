@@ -0,0 +1,15 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics::exact_div;
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let q = unsafe { exact_div(data[0], 3) };
+    if q == 5 {
+        panic!()
+    }
+}
@@ -0,0 +1,11 @@
+extern "C" fn f(x: i32) -> i32 {
+    x
+}
+
+fn main() {
+    let g = unsafe {
+        std::mem::transmute::<extern "C" fn(i32) -> i32, fn(i32) -> i32>(f)
+    };
+
+    g(42);
+}
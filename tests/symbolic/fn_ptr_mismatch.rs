@@ -0,0 +1,9 @@
+fn main() {
+    fn f() {}
+
+    let g = unsafe {
+        std::mem::transmute::<fn(), fn(i32)>(f)
+    };
+
+    g(42)
+}
@@ -0,0 +1,20 @@
+fn zero() {}
+
+fn one() {}
+
+fn two() {
+    panic!()
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let table: [fn(); 3] = [zero, one, two];
+    if data[0] < 3 {
+        let f = table[data[0] as usize];
+        f();
+    }
+}
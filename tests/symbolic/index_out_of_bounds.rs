@@ -0,0 +1,11 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let v = [10, 20, 30, 40];
+    if v[data[0] as usize] == 30 {
+        panic!()
+    }
+}
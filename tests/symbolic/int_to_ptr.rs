@@ -0,0 +1,13 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // Cast straight from a byte, and take a reference rather than copying the pointee.
+    let ptr = data[0] as *const u8;
+    let byte = unsafe { &*ptr };
+    if *byte == 1 {
+        panic!()
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // Pointers into allocations are never null, whatever their offset.
+    let buffer = [1u8; 4];
+    let real: *const u8 = &buffer[0];
+    let offset: *const u8 = &buffer[(data[0] % 4) as usize];
+    if real.is_null() || offset.is_null() {
+        panic!()
+    }
+
+    // An address cast from a symbolic integer is null if the integer is zero.
+    let symbolic = data[0] as usize as *const u8;
+    if symbolic.is_null() {
+        panic!()
+    }
+}
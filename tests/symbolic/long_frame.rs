@@ -0,0 +1,15 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    // Once `n` is known to be nonzero, this loop runs for at least a billion
+    // iterations without forking again.
+    let bound = data[0] as u64 * 1_000_000_000;
+    let mut i = 0;
+    while i < bound {
+        i += 1;
+    }
+}
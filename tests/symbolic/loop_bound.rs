@@ -0,0 +1,12 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut x = data[0];
+    while x > 0 {
+        x -= 1;
+    }
+}
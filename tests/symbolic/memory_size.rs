@@ -0,0 +1,3 @@
+fn main() {
+    let _x = [42u8; 1024];
+}
@@ -0,0 +1,14 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = if data[0] == 7 { 10u8 } else { 20u8 };
+    let y = if data[1] > 100 { 1u8 } else { 2u8 };
+
+    if x + y == 11 {
+        panic!()
+    }
+}
@@ -0,0 +1,17 @@
+use std::cmp;
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if cmp::min(x, 5) > 5 {
+        panic!("min(x, 5) is at most 5")
+    }
+
+    if x.max(200) == 201 {
+        panic!() // 201
+    }
+}
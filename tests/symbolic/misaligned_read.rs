@@ -0,0 +1,19 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let words: [u32; 2] = [0x1111_1111, 0x2222_2222];
+    let offset = data[0] as isize;
+    if offset > 4 {
+        return;
+    }
+
+    // Only the offsets that are a multiple of 4 are aligned for a `u32`.
+    let p = unsafe { (words.as_ptr() as *const u8).offset(offset) as *const u32 };
+    let x = unsafe { *p };
+    if x == 0x2222_2222 {
+        panic!()
+    }
+}
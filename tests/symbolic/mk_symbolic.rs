@@ -0,0 +1,16 @@
+mod seer {
+    pub fn mk_symbolic<T>() -> T {
+        unimplemented!()
+    }
+}
+
+fn main() {
+    let b: bool = seer::mk_symbolic();
+    let mut count = 0;
+    if b {
+        count += 1;
+    }
+    if count > 1 {
+        panic!()
+    }
+}
@@ -0,0 +1,22 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // `None` is encoded as a null reference.
+    let x = 5u8;
+    let niche: Option<&u8> = if data[0] == 7 { None } else { Some(&x) };
+    let raw: usize = unsafe { ::std::mem::transmute(niche) };
+    if raw == 0 {
+        panic!() // 7
+    }
+
+    // `None` is encoded as a zero tag in front of the payload.
+    let tagged: Option<u8> = if data[0] == 9 { None } else { Some(data[0]) };
+    let tag = unsafe { *(&tagged as *const Option<u8> as *const u8) };
+    if tag == 0 {
+        panic!() // 9
+    }
+}
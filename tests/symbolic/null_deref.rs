@@ -0,0 +1,12 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let ptr = data[0] as usize as *const u8;
+    let byte = unsafe { *ptr };
+    if byte == 1 {
+        panic!()
+    }
+}
@@ -0,0 +1,20 @@
+use std::io::Read;
+
+fn checksum(data: &[u8]) -> u32 {
+    let mut sum = 0u32;
+    for &byte in data {
+        sum = sum.wrapping_mul(31).wrapping_add(byte as u32);
+    }
+    sum * 2
+}
+
+fn main() {
+    let mut data = [0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // An even checksum can only be odd if the call is skipped.
+    if checksum(&data) == 7 {
+        panic!()
+    }
+}
@@ -0,0 +1,12 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let y = data[0] + 200;
+    if y == 210 {
+        panic!()
+    }
+}
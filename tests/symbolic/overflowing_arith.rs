@@ -0,0 +1,19 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0];
+    let (wrapped, overflowed) = x.overflowing_add(1);
+    if overflowed {
+        assert_eq!(wrapped, 0);
+        panic!() // 255
+    }
+
+    let (wrapped, overflowed) = (x as i8).overflowing_sub(1);
+    if overflowed && wrapped == 127 {
+        panic!() // 128
+    }
+}
@@ -0,0 +1,11 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    if data[0] > 200 {
+        panic!("too big");
+    }
+}
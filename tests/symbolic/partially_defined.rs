@@ -0,0 +1,21 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut v: [u8; 4] = unsafe { ::std::mem::uninitialized() };
+    v[0] = 1;
+    v[1] = 2;
+
+    // Reading the defined bytes is fine.
+    if v[0] + v[1] != 3 {
+        return;
+    }
+
+    if data[0] < 4 {
+        if v[data[0] as usize] == 2 {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 3];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut x = 0;
+    if data[0] == 1 {
+        x += 1;
+    }
+
+    // Paths with `data[1] == 1` fork twice, all others three times.
+    if data[1] != 1 && data[2] == 1 {
+        x += 2;
+    }
+    let _ = x;
+}
@@ -0,0 +1,11 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // Only the highest bit of `usize::MAX` is left, which depends on the target.
+    if usize::max_value().checked_shr(data[0] as u32) == Some(1) {
+        panic!()
+    }
+}
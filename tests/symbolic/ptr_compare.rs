@@ -0,0 +1,20 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let v = [10u8, 20, 30, 40];
+    let other = [50u8];
+    let (i, j) = (data[0] as usize, data[1] as usize);
+    if i < 4 && j < 4 {
+        let p = &v[i] as *const u8;
+        let q = &v[j] as *const u8;
+        if p == &other[0] as *const u8 {
+            panic!()
+        }
+        if p < q && p != q && i >= 2 && q <= &v[3] as *const u8 {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let v = [10u16, 20, 30, 40];
+    let p = unsafe { v.as_ptr().offset(data[0] as isize) };
+
+    if unsafe { *p } == 30 {
+        panic!()
+    }
+}
@@ -0,0 +1,18 @@
+use std::io::Read;
+use std::ptr;
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let pair = (data[0] as u32, data[1] as u32 * 3);
+    let mut dest = (0u32, 0u32);
+    unsafe {
+        ptr::write(&mut dest, pair);
+    }
+
+    if dest.0 == 5 && dest.1 == 21 {
+        panic!()
+    }
+}
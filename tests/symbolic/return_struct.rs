@@ -0,0 +1,22 @@
+use std::io::Read;
+
+struct Pair {
+    first: u8,
+    second: u8,
+}
+
+fn make_pair(x: u8) -> Pair {
+    let mut pair = Pair { first: x, second: 0 };
+    pair.second = pair.first.wrapping_add(1);
+    pair
+}
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    if make_pair(data[0]).second == 43 {
+        panic!()
+    }
+}
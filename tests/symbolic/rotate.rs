@@ -0,0 +1,15 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    if x.rotate_left(4).rotate_right(4) != x {
+        // Rotating back restores every value.
+        unreachable!()
+    }
+    if x.rotate_left(4) == 0x21 {
+        panic!()
+    }
+}
@@ -0,0 +1,22 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = data[0];
+    let y = data[1] as i8;
+
+    if x.saturating_add(1) < x || x.saturating_sub(1) > x {
+        panic!()
+    }
+
+    // Saturating at the top needs `y >= 27`, and at the bottom `y <= -28`.
+    if y.saturating_add(100) == 127 && y.saturating_sub(100) == -128 {
+        panic!()
+    }
+
+    if x.saturating_add(1) == x && y.saturating_sub(1) == y {
+        panic!()
+    }
+}
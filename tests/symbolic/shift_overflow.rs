@@ -0,0 +1,12 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = 1u32 << data[0];
+    if x == 1 << 31 {
+        panic!()
+    }
+}
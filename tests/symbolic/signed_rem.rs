@@ -0,0 +1,19 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // `i32::min_value() % -1` overflows.
+    let a = ((data[0] as u32) << 24) as i32;
+    let b = data[1] as i8 as i32;
+    let _ = a % b;
+
+    // The remainder takes the sign of the dividend: -7 % 3 == -1.
+    let c = data[2] as i8;
+    let d = data[3] as i8;
+    if d == 3 && c < -4 && c > -9 && c % d == -1 {
+        panic!()
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::Read;
+use std::mem::{align_of, size_of};
+
+const SEVEN: usize = size_of::<[u8; 7]>();
+
+fn size_of_generic<T>() -> usize {
+    size_of::<T>()
+}
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let n = size_of::<[u8; 7]>() * 3 + align_of::<u32>();
+    if n == 25 && SEVEN == 7 && size_of_generic::<[u8; 7]>() == 7 {
+        if data[0] as usize + n == 28 {
+            panic!()
+        }
+    }
+}
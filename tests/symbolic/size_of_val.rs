@@ -0,0 +1,19 @@
+use std::io::{self, Read};
+use std::mem;
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let v = [10u8, 20, 30, 40];
+    if data[0] as usize > v.len() {
+        return;
+    }
+
+    let s = &v[..data[0] as usize];
+    assert_eq!(mem::align_of_val(s), 1);
+    if mem::size_of_val(s) == s.len() && s.len() == 3 {
+        panic!()
+    }
+}
@@ -0,0 +1,17 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let v = [10u8, 20, 30, 40];
+    if data[0] as usize > v.len() {
+        return;
+    }
+
+    let s = &v[..data[0] as usize];
+    if s.len() == 3 && s[data[1] as usize] == 30 {
+        panic!()
+    }
+}
@@ -0,0 +1,19 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let buf = [1u8; 8];
+    let n = data[0] as usize;
+    if n > buf.len() {
+        return;
+    }
+
+    let s = &buf[..n];
+    let (a, b) = s.split_at(data[1] as usize);
+    if a.len() == 2 && b.len() == 3 {
+        panic!()
+    }
+}
@@ -0,0 +1,14 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    if data[0] < b'a' {
+        panic!()
+    }
+    if data[0] == b'q' {
+        panic!()
+    }
+}
@@ -0,0 +1,18 @@
+use std::io::Read;
+
+fn next_byte() -> u8 {
+    let mut data = [0];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+    data[0]
+}
+
+fn read_all() {
+    if next_byte() != 0 {
+        read_all()
+    }
+}
+
+fn main() {
+    read_all();
+}
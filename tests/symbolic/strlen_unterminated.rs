@@ -0,0 +1,16 @@
+extern "C" {
+    fn strlen(s: *const u8) -> usize;
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 3];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // Only some inputs have a nul byte within the buffer.
+    let len = unsafe { strlen(data.as_ptr()) };
+    if len == 2 {
+        panic!()
+    }
+}
@@ -0,0 +1,26 @@
+#[derive(Clone, Copy)]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let points = [
+        Point { x: 1, y: 10 },
+        Point { x: 2, y: 20 },
+        Point { x: 3, y: 30 },
+        Point { x: 4, y: 40 },
+    ];
+
+    let idx = data[0] as usize;
+    if idx >= points.len() { return }
+
+    if points[idx].y == 30 && points[idx].x == 3 {
+        panic!()
+    }
+}
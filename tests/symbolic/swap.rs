@@ -0,0 +1,24 @@
+use std::io::Read;
+use std::{mem, ptr};
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let mut a = data[0] as u32 * 1000;
+    let mut b = data[1] as u32 + 7;
+    mem::swap(&mut a, &mut b);
+    if a != 12 || b != 9000 {
+        return;
+    }
+
+    // Swapping a value with itself leaves it alone.
+    let p = &mut a as *mut u32;
+    unsafe {
+        ptr::swap(p, p);
+    }
+    if a == 12 {
+        panic!()
+    }
+}
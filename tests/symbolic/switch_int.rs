@@ -0,0 +1,12 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    match data[0] {
+        1 => panic!("one"),
+        2 => panic!("two"),
+        _ => {}
+    }
+}
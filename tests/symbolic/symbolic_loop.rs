@@ -0,0 +1,17 @@
+use std::io::{self, Read};
+
+fn main() {
+    let mut data = [0; 1];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let n = data[0];
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+
+    if i == 200 {
+        panic!()
+    }
+}
@@ -0,0 +1,13 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let x = (data[0] as u32) | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24;
+    let f: f32 = unsafe { ::std::mem::transmute(x) };
+    let bytes: [u8; 4] = unsafe { ::std::mem::transmute(f) };
+    if bytes[0] == 0x11 && bytes[1] == 0x22 && bytes[2] == 0x33 && bytes[3] == 0x44 {
+        panic!()
+    }
+}
@@ -0,0 +1,13 @@
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    if data[1] >= 0xD0 && data[1] < 0xE0 && data[2] == 0 && data[3] == 0 {
+        let c: char = unsafe { ::std::mem::transmute(data) };
+        if c == '\u{D7FF}' {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,10 @@
+fn check(input: (u32, bool)) {
+    let (x, b) = input;
+    if b && x ^ 0x1234 == 0xdeadbeef {
+        panic!()
+    }
+}
+
+fn main() {
+    check((0, false));
+}
@@ -0,0 +1,31 @@
+// `MaybeUninit` is not available on this toolchain, but `mem::uninitialized`
+// followed by writes through a raw pointer is the same pattern.
+use std::mem;
+use std::ptr;
+
+struct Header {
+    tag: u8,
+    len: u32,
+    flags: u16,
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    let header = unsafe {
+        let mut header: Header = mem::uninitialized();
+        let p: *mut Header = &mut header;
+        // The padding between the fields is never written.
+        ptr::write(&mut (*p).tag, data[0]);
+        ptr::write(&mut (*p).len, data[1] as u32 * 3);
+        ptr::write(&mut (*p).flags, 7);
+        header
+    };
+
+    if header.tag == 1 && header.len == 30 && header.flags == 7 {
+        panic!()
+    }
+}
@@ -0,0 +1,18 @@
+use std::mem;
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // The address of the pair is never taken, so it stays a pair of values
+    // whose halves are initialized one at a time.
+    let mut pair: (u8, u32) = unsafe { mem::uninitialized() };
+    pair.0 = data[0];
+    pair.1 = data[1] as u32 * 3;
+
+    if pair.0 == 1 && pair.1 == 30 {
+        panic!()
+    }
+}
@@ -0,0 +1,14 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics::unreachable;
+
+fn main() {
+    use std::io::Read;
+    let mut data = [0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    if data[0] == 7 {
+        unsafe { unreachable() }
+    }
+}
@@ -0,0 +1,9 @@
+fn wide(x: u128) {
+    if x == 3 {
+        panic!()
+    }
+}
+
+fn main() {
+    wide(0);
+}
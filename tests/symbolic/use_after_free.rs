@@ -0,0 +1,7 @@
+fn main() {
+    let b = Box::new(5u8);
+    let p: *const u8 = &*b;
+    drop(b);
+    let x = unsafe { *p };
+    assert_eq!(x, 5);
+}
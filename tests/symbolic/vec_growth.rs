@@ -0,0 +1,19 @@
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read_exact(&mut data[..]).unwrap();
+
+    // Four pushes outgrow every capacity, so the buffer is reallocated to a
+    // symbolic size at least once.
+    let mut v = Vec::with_capacity((data[0] % 3 + 1) as usize);
+    for &b in &data[1..] {
+        v.push(b);
+    }
+    v.push(9);
+
+    if v[0] == 7 && v[2] == 42 && v[3] == 9 {
+        panic!()
+    }
+}
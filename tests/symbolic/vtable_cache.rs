@@ -0,0 +1,14 @@
+use std::fmt::Debug;
+
+fn vtable(x: &Debug) -> *const u8 {
+    let parts: [*const u8; 2] = unsafe { ::std::mem::transmute(x) };
+    parts[1]
+}
+
+fn main() {
+    let a: &Debug = &1u8;
+    let b: &Debug = &2u8;
+    if vtable(a) == vtable(b) {
+        panic!()
+    }
+}
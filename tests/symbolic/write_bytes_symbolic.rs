@@ -0,0 +1,19 @@
+use std::io::{self, Read};
+use std::ptr;
+
+fn main() {
+    let mut data = [0; 2];
+    let mut stdin = io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let n = data[0] as usize;
+    let mut buf = [9u8; 4];
+    if n <= 4 {
+        unsafe {
+            ptr::write_bytes(buf.as_mut_ptr(), data[1], n);
+        }
+        if buf == [7, 7, 7, 9] {
+            panic!()
+        }
+    }
+}